@@ -0,0 +1,29 @@
+-- Uploaded product images with generated variants
+ALTER TABLE product_images ADD COLUMN IF NOT EXISTS webp_url VARCHAR(500);
+ALTER TABLE product_images ADD COLUMN IF NOT EXISTS thumbnail_url VARCHAR(500);
+ALTER TABLE product_images ADD COLUMN IF NOT EXISTS width INTEGER;
+ALTER TABLE product_images ADD COLUMN IF NOT EXISTS height INTEGER;
+ALTER TABLE product_images ADD COLUMN IF NOT EXISTS storage_keys TEXT[] NOT NULL DEFAULT '{}'; -- blobs to delete with the row
+ALTER TABLE product_images ADD COLUMN IF NOT EXISTS updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
+
+UPDATE product_images SET sort_order = 0 WHERE sort_order IS NULL;
+ALTER TABLE product_images ALTER COLUMN sort_order SET NOT NULL;
+
+-- Keep only the first primary image per product before enforcing it
+UPDATE product_images pi SET is_primary = false
+WHERE pi.is_primary AND EXISTS (
+    SELECT 1 FROM product_images other
+    WHERE other.product_id = pi.product_id AND other.is_primary
+      AND (other.sort_order, other.id) < (pi.sort_order, pi.id)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_product_images_one_primary
+    ON product_images(product_id) WHERE is_primary;
+
+CREATE TRIGGER update_product_images_updated_at BEFORE UPDATE ON product_images
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- Products without a cover image get their primary image
+UPDATE products p SET image_url = pi.image_url
+FROM product_images pi
+WHERE pi.product_id = p.id AND pi.is_primary AND p.image_url IS NULL;
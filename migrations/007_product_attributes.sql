@@ -0,0 +1,50 @@
+-- First-class batik attributes used for filtering
+ALTER TABLE products ADD COLUMN IF NOT EXISTS technique VARCHAR(20); -- 'tulis', 'cap', 'printing', 'kombinasi'
+ALTER TABLE products ADD COLUMN IF NOT EXISTS fabric VARCHAR(100);
+ALTER TABLE products ADD COLUMN IF NOT EXISTS origin_region VARCHAR(100);
+ALTER TABLE products ADD COLUMN IF NOT EXISTS motif VARCHAR(100);
+ALTER TABLE products ADD COLUMN IF NOT EXISTS dye_type VARCHAR(50);
+
+ALTER TABLE products ADD CONSTRAINT products_technique_check
+    CHECK (technique IS NULL OR technique IN ('tulis', 'cap', 'printing', 'kombinasi'));
+
+-- Backfill from the free-form product features
+UPDATE products p SET technique = CASE
+        WHEN pf.feature_value ILIKE '%kombinasi%' THEN 'kombinasi'
+        WHEN pf.feature_value ILIKE '%tulis%' THEN 'tulis'
+        WHEN pf.feature_value ILIKE '%cap%' THEN 'cap'
+        WHEN pf.feature_value ILIKE '%print%' THEN 'printing'
+    END
+FROM product_features pf
+WHERE pf.product_id = p.id AND pf.feature_name = 'Teknik' AND p.technique IS NULL;
+
+UPDATE products p SET fabric = pf.feature_value
+FROM product_features pf
+WHERE pf.product_id = p.id AND pf.feature_name IN ('Bahan', 'Material') AND p.fabric IS NULL;
+
+UPDATE products p SET origin_region = pf.feature_value
+FROM product_features pf
+WHERE pf.product_id = p.id AND pf.feature_name IN ('Asal Daerah', 'Origin') AND p.origin_region IS NULL;
+
+UPDATE products p SET dye_type = pf.feature_value
+FROM product_features pf
+WHERE pf.product_id = p.id AND pf.feature_name IN ('Pewarna', 'Dye') AND p.dye_type IS NULL;
+
+-- ... and from the category names used so far
+UPDATE products SET technique = CASE
+        WHEN category ILIKE '%kombinasi%' THEN 'kombinasi'
+        WHEN category ILIKE '%tulis%' THEN 'tulis'
+        WHEN category ILIKE '%cap%' THEN 'cap'
+        WHEN category ILIKE '%print%' THEN 'printing'
+    END
+WHERE technique IS NULL;
+
+-- Classic motifs mentioned in product names
+UPDATE products p SET motif = m.motif
+FROM (VALUES ('Parang'), ('Mega Mendung'), ('Kawung'), ('Lereng'), ('Sidomukti'),
+             ('Truntum'), ('Sekar Jagad'), ('Tiga Negeri'), ('Geometric')) AS m(motif)
+WHERE p.motif IS NULL AND p.name ILIKE '%' || m.motif || '%';
+
+CREATE INDEX IF NOT EXISTS idx_products_technique ON products(technique);
+CREATE INDEX IF NOT EXISTS idx_products_origin_region ON products(origin_region);
+CREATE INDEX IF NOT EXISTS idx_products_motif ON products(motif);
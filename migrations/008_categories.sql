@@ -0,0 +1,40 @@
+-- Category hierarchy; products.category keeps the category name for older clients
+CREATE TABLE IF NOT EXISTS categories (
+    id SERIAL PRIMARY KEY,
+    parent_id INTEGER REFERENCES categories(id) ON DELETE RESTRICT,
+    name VARCHAR(100) NOT NULL,
+    slug VARCHAR(120) NOT NULL UNIQUE,
+    description TEXT,
+    image_url VARCHAR(500),
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT categories_not_own_parent CHECK (parent_id IS NULL OR parent_id <> id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_categories_parent_id ON categories(parent_id);
+
+DROP TRIGGER IF EXISTS update_categories_updated_at ON categories;
+CREATE TRIGGER update_categories_updated_at BEFORE UPDATE ON categories
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+ALTER TABLE products ADD COLUMN IF NOT EXISTS category_id INTEGER REFERENCES categories(id) ON DELETE RESTRICT;
+CREATE INDEX IF NOT EXISTS idx_products_category_id ON products(category_id);
+
+-- One top-level category per distinct free-text value
+INSERT INTO categories (name, slug)
+SELECT DISTINCT ON (slug) name, slug
+FROM (
+    SELECT TRIM(category) AS name,
+           TRIM(BOTH '-' FROM LOWER(REGEXP_REPLACE(TRIM(category), '[^a-zA-Z0-9]+', '-', 'g'))) AS slug
+    FROM products
+    WHERE TRIM(category) <> ''
+) existing
+ORDER BY slug, name
+ON CONFLICT (slug) DO NOTHING;
+
+UPDATE products p SET category_id = c.id, category = c.name
+FROM categories c
+WHERE p.category_id IS NULL
+  AND c.slug = TRIM(BOTH '-' FROM LOWER(REGEXP_REPLACE(TRIM(p.category), '[^a-zA-Z0-9]+', '-', 'g')));
@@ -0,0 +1,22 @@
+-- Shareable URLs: products get a slug like categories already have
+ALTER TABLE products ADD COLUMN IF NOT EXISTS slug VARCHAR(255);
+
+UPDATE products SET slug = TRIM(BOTH '-' FROM LOWER(REGEXP_REPLACE(TRIM(name), '[^a-zA-Z0-9]+', '-', 'g')))
+WHERE slug IS NULL;
+
+-- Same name twice: keep the oldest product on the plain slug
+UPDATE products p SET slug = p.slug || '-' || p.id
+FROM products older
+WHERE older.slug = p.slug AND older.id < p.id;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_products_slug ON products(slug);
+
+-- Old slugs keep working after a rename
+CREATE TABLE IF NOT EXISTS slug_redirects (
+    id SERIAL PRIMARY KEY,
+    entity_type VARCHAR(20) NOT NULL, -- 'product', 'category'
+    old_slug VARCHAR(255) NOT NULL,
+    entity_id INTEGER NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (entity_type, old_slug)
+);
@@ -0,0 +1,31 @@
+-- Reviews wait for moderation before they are shown. Reviews published
+-- before moderation existed stay visible.
+ALTER TABLE reviews ADD COLUMN IF NOT EXISTS status VARCHAR(20) NOT NULL DEFAULT 'approved'; -- 'pending', 'approved', 'hidden'
+ALTER TABLE reviews ALTER COLUMN status SET DEFAULT 'pending';
+ALTER TABLE reviews ADD COLUMN IF NOT EXISTS moderation_reason TEXT;
+ALTER TABLE reviews ADD COLUMN IF NOT EXISTS moderated_by INTEGER REFERENCES users(id) ON DELETE SET NULL;
+ALTER TABLE reviews ADD COLUMN IF NOT EXISTS moderated_at TIMESTAMP;
+ALTER TABLE reviews ADD COLUMN IF NOT EXISTS helpful_count INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE reviews ADD COLUMN IF NOT EXISTS updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
+
+ALTER TABLE reviews ADD CONSTRAINT reviews_status_check
+    CHECK (status IN ('pending', 'approved', 'hidden'));
+
+-- Verified = the reviewer has a delivered order containing the product
+UPDATE reviews r SET is_verified = EXISTS (
+    SELECT 1 FROM orders o JOIN order_items oi ON oi.order_id = o.id
+    WHERE o.user_id = r.user_id AND oi.product_id = r.product_id AND o.status = 'delivered'
+);
+
+CREATE INDEX IF NOT EXISTS idx_reviews_status ON reviews(status);
+
+DROP TRIGGER IF EXISTS update_reviews_updated_at ON reviews;
+CREATE TRIGGER update_reviews_updated_at BEFORE UPDATE ON reviews
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS review_helpful_votes (
+    review_id INTEGER NOT NULL REFERENCES reviews(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (review_id, user_id)
+);
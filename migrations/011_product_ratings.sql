@@ -0,0 +1,28 @@
+-- Rating summary per product, kept in sync with approved reviews
+ALTER TABLE products ADD COLUMN IF NOT EXISTS average_rating NUMERIC(3, 2) NOT NULL DEFAULT 0;
+ALTER TABLE products ADD COLUMN IF NOT EXISTS review_count INTEGER NOT NULL DEFAULT 0;
+-- Number of 1..5 star reviews, index 1 = one star
+ALTER TABLE products ADD COLUMN IF NOT EXISTS rating_histogram INTEGER[] NOT NULL DEFAULT '{0,0,0,0,0}';
+
+UPDATE products p SET
+    average_rating = COALESCE(s.average_rating, 0),
+    review_count = COALESCE(s.review_count, 0),
+    rating_histogram = COALESCE(s.rating_histogram, '{0,0,0,0,0}')
+FROM (
+    SELECT p2.id AS product_id,
+           ROUND(AVG(r.rating), 2) AS average_rating,
+           COUNT(r.id)::INTEGER AS review_count,
+           ARRAY[
+               COUNT(*) FILTER (WHERE r.rating = 1)::INTEGER,
+               COUNT(*) FILTER (WHERE r.rating = 2)::INTEGER,
+               COUNT(*) FILTER (WHERE r.rating = 3)::INTEGER,
+               COUNT(*) FILTER (WHERE r.rating = 4)::INTEGER,
+               COUNT(*) FILTER (WHERE r.rating = 5)::INTEGER
+           ] AS rating_histogram
+    FROM products p2
+    LEFT JOIN reviews r ON r.product_id = p2.id AND r.status = 'approved'
+    GROUP BY p2.id
+) s
+WHERE s.product_id = p.id;
+
+CREATE INDEX IF NOT EXISTS idx_products_average_rating ON products(average_rating DESC, review_count DESC);
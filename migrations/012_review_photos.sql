@@ -0,0 +1,18 @@
+-- Customer photos on reviews. Photos are uploaded first and attached when
+-- the review is submitted; review_id stays NULL until then.
+CREATE TABLE IF NOT EXISTS review_photos (
+    id SERIAL PRIMARY KEY,
+    review_id INTEGER REFERENCES reviews(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    image_url VARCHAR(500) NOT NULL,
+    webp_url VARCHAR(500),
+    thumbnail_url VARCHAR(500),
+    width INTEGER,
+    height INTEGER,
+    storage_keys TEXT[] NOT NULL DEFAULT '{}',
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_review_photos_review_id ON review_photos(review_id);
+CREATE INDEX IF NOT EXISTS idx_review_photos_unattached ON review_photos(user_id, created_at) WHERE review_id IS NULL;
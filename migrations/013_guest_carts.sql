@@ -0,0 +1,11 @@
+-- Anonymous carts. A guest cart is identified by the id inside the signed
+-- cart token instead of a user; it is merged into the user's cart on login.
+ALTER TABLE carts ALTER COLUMN user_id DROP NOT NULL;
+ALTER TABLE carts ADD COLUMN IF NOT EXISTS guest_token UUID UNIQUE;
+
+ALTER TABLE carts DROP CONSTRAINT IF EXISTS carts_owner_check;
+ALTER TABLE carts ADD CONSTRAINT carts_owner_check
+    CHECK ((user_id IS NULL) <> (guest_token IS NULL));
+
+-- Guest carts are swept after a period of inactivity
+CREATE INDEX IF NOT EXISTS idx_carts_guest_updated_at ON carts(updated_at) WHERE guest_token IS NOT NULL;
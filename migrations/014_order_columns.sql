@@ -0,0 +1,17 @@
+-- Align orders/order_items with what checkout writes: addresses are stored
+-- as JSON snapshots and every order has a final amount.
+ALTER TABLE orders ALTER COLUMN shipping_address TYPE JSONB
+    USING CASE WHEN shipping_address ~ '^\s*[\{\[]' THEN shipping_address::jsonb ELSE to_jsonb(shipping_address) END;
+ALTER TABLE orders ALTER COLUMN billing_address TYPE JSONB
+    USING CASE WHEN billing_address ~ '^\s*[\{\[]' THEN billing_address::jsonb ELSE to_jsonb(billing_address) END;
+
+UPDATE orders SET final_amount = total_amount + shipping_cost + tax_amount - discount_amount
+WHERE final_amount IS NULL;
+ALTER TABLE orders ALTER COLUMN final_amount SET NOT NULL;
+
+UPDATE order_items SET price_at_time = unit_price WHERE price_at_time IS NULL;
+ALTER TABLE order_items ALTER COLUMN price_at_time SET NOT NULL;
+
+UPDATE order_items oi SET product_name = p.name
+FROM products p
+WHERE oi.product_id = p.id AND oi.product_name IS NULL;
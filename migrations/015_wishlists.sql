@@ -0,0 +1,32 @@
+-- Named wishlists and the per-user "saved for later" list. Favorites without
+-- a wishlist_id keep forming the original favorites list.
+CREATE TABLE IF NOT EXISTS wishlists (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    kind VARCHAR(20) NOT NULL DEFAULT 'wishlist', -- wishlist, saved_for_later
+    share_token UUID UNIQUE, -- set while the list is shared publicly
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT wishlists_kind_check CHECK (kind IN ('wishlist', 'saved_for_later'))
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_wishlists_user_name ON wishlists(user_id, LOWER(name)) WHERE kind = 'wishlist';
+CREATE UNIQUE INDEX IF NOT EXISTS idx_wishlists_saved_for_later ON wishlists(user_id) WHERE kind = 'saved_for_later';
+
+DROP TRIGGER IF EXISTS update_wishlists_updated_at ON wishlists;
+CREATE TRIGGER update_wishlists_updated_at BEFORE UPDATE ON wishlists
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+ALTER TABLE favorites ADD COLUMN IF NOT EXISTS wishlist_id INTEGER REFERENCES wishlists(id) ON DELETE CASCADE;
+ALTER TABLE favorites ADD COLUMN IF NOT EXISTS note TEXT;
+-- Variant and quantity, kept so saved-for-later lines can go back to the cart
+ALTER TABLE favorites ADD COLUMN IF NOT EXISTS size VARCHAR(50);
+ALTER TABLE favorites ADD COLUMN IF NOT EXISTS color VARCHAR(50);
+ALTER TABLE favorites ADD COLUMN IF NOT EXISTS quantity INTEGER NOT NULL DEFAULT 1 CHECK (quantity > 0);
+
+-- A product appears once in the favorites list and once per variant in a wishlist
+ALTER TABLE favorites DROP CONSTRAINT IF EXISTS favorites_user_id_product_id_key;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_favorites_default_list ON favorites(user_id, product_id) WHERE wishlist_id IS NULL;
+CREATE UNIQUE INDEX IF NOT EXISTS idx_favorites_wishlist_item
+    ON favorites(wishlist_id, product_id, COALESCE(size, ''), COALESCE(color, '')) WHERE wishlist_id IS NOT NULL;
@@ -0,0 +1,16 @@
+-- Explicit price-drop / back-in-stock subscriptions. Favoriting a product
+-- subscribes to both implicitly.
+CREATE TABLE IF NOT EXISTS product_alert_subscriptions (
+    id SERIAL PRIMARY KEY,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    product_id INTEGER NOT NULL REFERENCES products(id) ON DELETE CASCADE,
+    alert_type VARCHAR(20) NOT NULL, -- price_drop, back_in_stock
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(user_id, product_id, alert_type),
+    CONSTRAINT product_alert_subscriptions_type_check CHECK (alert_type IN ('price_drop', 'back_in_stock'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_product_alert_subscriptions_product ON product_alert_subscriptions(product_id, alert_type);
+
+-- Rate limiting looks up a user's recent alerts
+CREATE INDEX IF NOT EXISTS idx_notifications_user_type_created ON notifications(user_id, type, created_at DESC);
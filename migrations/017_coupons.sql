@@ -0,0 +1,52 @@
+-- Bring coupons in line with the coupon engine: a validity window instead of
+-- a bare expiry, discount caps, per-user limits and product/category scoping.
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'coupons' AND column_name = 'minimum_amount') THEN
+        ALTER TABLE coupons RENAME COLUMN minimum_amount TO min_order_amount;
+    END IF;
+    IF EXISTS (SELECT 1 FROM information_schema.columns WHERE table_name = 'coupons' AND column_name = 'expires_at') THEN
+        ALTER TABLE coupons RENAME COLUMN expires_at TO valid_until;
+    END IF;
+END $$;
+
+ALTER TABLE coupons ADD COLUMN IF NOT EXISTS description TEXT;
+ALTER TABLE coupons ADD COLUMN IF NOT EXISTS max_discount_amount DECIMAL(12, 2);
+ALTER TABLE coupons ADD COLUMN IF NOT EXISTS per_user_limit INTEGER;
+ALTER TABLE coupons ADD COLUMN IF NOT EXISTS valid_from TIMESTAMP;
+-- Empty/NULL means the coupon applies to every product
+ALTER TABLE coupons ADD COLUMN IF NOT EXISTS product_ids INTEGER[];
+ALTER TABLE coupons ADD COLUMN IF NOT EXISTS category_ids INTEGER[];
+ALTER TABLE coupons ADD COLUMN IF NOT EXISTS first_order_only BOOLEAN NOT NULL DEFAULT false;
+ALTER TABLE coupons ADD COLUMN IF NOT EXISTS updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP;
+
+UPDATE coupons SET used_count = 0 WHERE used_count IS NULL;
+ALTER TABLE coupons ALTER COLUMN used_count SET NOT NULL;
+UPDATE coupons SET is_active = false WHERE is_active IS NULL;
+ALTER TABLE coupons ALTER COLUMN is_active SET NOT NULL;
+UPDATE coupons SET code = UPPER(code) WHERE code <> UPPER(code);
+
+-- discount_type: percentage, fixed, free_shipping
+ALTER TABLE coupons DROP CONSTRAINT IF EXISTS coupons_discount_type_check;
+ALTER TABLE coupons ADD CONSTRAINT coupons_discount_type_check
+    CHECK (discount_type IN ('percentage', 'fixed', 'free_shipping'));
+
+DROP TRIGGER IF EXISTS update_coupons_updated_at ON coupons;
+CREATE TRIGGER update_coupons_updated_at BEFORE UPDATE ON coupons
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- One row per coupon used on an order; drives per-user limits and lets a
+-- cancelled order give its coupon use back
+CREATE TABLE IF NOT EXISTS coupon_redemptions (
+    id SERIAL PRIMARY KEY,
+    coupon_id INTEGER NOT NULL REFERENCES coupons(id),
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    order_id INTEGER NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    discount_amount DECIMAL(12, 2) NOT NULL DEFAULT 0,
+    shipping_discount DECIMAL(12, 2) NOT NULL DEFAULT 0,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE(coupon_id, order_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_coupon_redemptions_coupon_user ON coupon_redemptions(coupon_id, user_id);
+CREATE INDEX IF NOT EXISTS idx_coupon_redemptions_order ON coupon_redemptions(order_id);
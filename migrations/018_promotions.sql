@@ -0,0 +1,80 @@
+-- Scheduled price rules. A promotion targets products, categories and/or
+-- brands and is live between starts_at and ends_at; flash sales cap how
+-- many units sell at the promotional price.
+CREATE TABLE IF NOT EXISTS promotions (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(255) NOT NULL,
+    description TEXT,
+    discount_type VARCHAR(20) NOT NULL, -- percentage, fixed
+    discount_value DECIMAL(12, 2) NOT NULL,
+    product_ids INTEGER[],
+    category_ids INTEGER[],
+    brands TEXT[], -- lowercase
+    starts_at TIMESTAMP NOT NULL,
+    ends_at TIMESTAMP NOT NULL,
+    quantity_limit INTEGER, -- units sold at the promotional price, NULL for no cap
+    sold_quantity INTEGER NOT NULL DEFAULT 0,
+    is_active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT promotions_discount_type_check CHECK (discount_type IN ('percentage', 'fixed')),
+    CONSTRAINT promotions_discount_value_check CHECK (discount_value > 0),
+    CONSTRAINT promotions_window_check CHECK (ends_at > starts_at),
+    CONSTRAINT promotions_quantity_check CHECK (quantity_limit IS NULL OR sold_quantity <= quantity_limit)
+);
+
+CREATE INDEX IF NOT EXISTS idx_promotions_window ON promotions(starts_at, ends_at) WHERE is_active = true;
+
+DROP TRIGGER IF EXISTS update_promotions_updated_at ON promotions;
+CREATE TRIGGER update_promotions_updated_at BEFORE UPDATE ON promotions
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+-- The promotion an order line was sold under, so its flash-sale quantity
+-- can be given back when the order is cancelled
+ALTER TABLE order_items ADD COLUMN IF NOT EXISTS promotion_id INTEGER REFERENCES promotions(id) ON DELETE SET NULL;
+
+-- The price every product sells for right now: the lowest of the list
+-- price, the manual discount price and the best running promotion.
+-- Listing, cart and checkout all read prices from here. A promotion on a
+-- category also covers its subcategories.
+CREATE OR REPLACE VIEW product_prices AS
+SELECT p.id AS product_id,
+       CASE WHEN best.promo_price < LEAST(p.price, COALESCE(p.discount_price, p.price))
+            THEN best.promo_price
+            ELSE LEAST(p.price, COALESCE(p.discount_price, p.price))
+       END AS current_price,
+       CASE WHEN best.promo_price < LEAST(p.price, COALESCE(p.discount_price, p.price))
+            THEN best.id
+       END AS promotion_id,
+       CASE WHEN best.promo_price < LEAST(p.price, COALESCE(p.discount_price, p.price))
+            THEN best.ends_at
+       END AS promotion_ends_at,
+       CASE WHEN best.promo_price < LEAST(p.price, COALESCE(p.discount_price, p.price))
+            THEN best.quantity_limit - best.sold_quantity
+       END AS promotion_remaining
+FROM products p
+LEFT JOIN LATERAL (
+    -- The product's category and all of its ancestors
+    WITH RECURSIVE ancestors AS (
+        SELECT c.id, c.parent_id FROM categories c WHERE c.id = p.category_id
+        UNION
+        SELECT c.id, c.parent_id FROM categories c JOIN ancestors a ON c.id = a.parent_id
+    )
+    SELECT ARRAY_AGG(id) AS ids FROM ancestors
+) categories ON true
+LEFT JOIN LATERAL (
+    SELECT pr.id, pr.ends_at, pr.quantity_limit, pr.sold_quantity,
+           CASE pr.discount_type
+               WHEN 'percentage' THEN ROUND(p.price * (100 - LEAST(pr.discount_value, 100)) / 100, 2)
+               ELSE GREATEST(p.price - pr.discount_value, 0)
+           END AS promo_price
+    FROM promotions pr
+    WHERE pr.is_active = true
+      AND pr.starts_at <= NOW() AND pr.ends_at > NOW()
+      AND (pr.quantity_limit IS NULL OR pr.sold_quantity < pr.quantity_limit)
+      AND (p.id = ANY(pr.product_ids)
+           OR pr.category_ids && categories.ids
+           OR LOWER(p.brand) = ANY(pr.brands))
+    ORDER BY promo_price, pr.ends_at, pr.id
+    LIMIT 1
+) best ON true;
@@ -0,0 +1,3 @@
+-- Courier service the customer picked at checkout
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS shipping_courier VARCHAR(50);
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS shipping_service VARCHAR(50);
@@ -0,0 +1,28 @@
+-- Parcels handed to a courier. An order usually ships as one parcel but can
+-- be split; each parcel has its own waybill (resi) number.
+CREATE TABLE IF NOT EXISTS shipments (
+    id SERIAL PRIMARY KEY,
+    order_id INTEGER NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    courier VARCHAR(50) NOT NULL, -- lowercase courier code, e.g. jne, pos, tiki
+    service VARCHAR(50),
+    waybill VARCHAR(100) NOT NULL,
+    weight_grams INTEGER NOT NULL,
+    cost DECIMAL(12, 2) NOT NULL DEFAULT 0,
+    notes TEXT,
+    -- Last checkpoint history fetched from the courier, reused until it is
+    -- older than the cache window (or forever once delivered)
+    tracking JSONB,
+    tracked_at TIMESTAMP,
+    delivered_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT shipments_weight_check CHECK (weight_grams > 0),
+    CONSTRAINT shipments_cost_check CHECK (cost >= 0),
+    CONSTRAINT shipments_courier_waybill_key UNIQUE (courier, waybill)
+);
+
+CREATE INDEX IF NOT EXISTS idx_shipments_order ON shipments(order_id);
+
+DROP TRIGGER IF EXISTS update_shipments_updated_at ON shipments;
+CREATE TRIGGER update_shipments_updated_at BEFORE UPDATE ON shipments
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
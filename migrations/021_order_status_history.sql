@@ -0,0 +1,20 @@
+-- Every status change of an order: who made it and why. Orders placed
+-- before this table existed get one row with their status at the time.
+CREATE TABLE IF NOT EXISTS order_status_history (
+    id SERIAL PRIMARY KEY,
+    order_id INTEGER NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    from_status VARCHAR(20), -- NULL when the order was placed
+    to_status VARCHAR(20) NOT NULL,
+    actor_id INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    actor_role VARCHAR(20) NOT NULL, -- customer, admin, system
+    note TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_order_status_history_order ON order_status_history(order_id, created_at);
+
+INSERT INTO order_status_history (order_id, from_status, to_status, actor_role, note, created_at)
+SELECT o.id, NULL, o.status, 'system', 'Status before history was recorded',
+       COALESCE(o.updated_at, o.created_at, CURRENT_TIMESTAMP)
+FROM orders o
+WHERE NOT EXISTS (SELECT 1 FROM order_status_history h WHERE h.order_id = o.id);
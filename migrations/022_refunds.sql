@@ -0,0 +1,26 @@
+-- Money owed back to a customer, e.g. for a paid order that was cancelled.
+-- Refunds are paid out by hand (bank transfer) and marked completed with
+-- the transfer reference; the order's payment_status becomes 'refunded'
+-- once completed refunds cover its final amount.
+CREATE TABLE IF NOT EXISTS refunds (
+    id SERIAL PRIMARY KEY,
+    order_id INTEGER NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    amount DECIMAL(12, 2) NOT NULL,
+    reason TEXT NOT NULL,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending', -- pending, completed
+    reference VARCHAR(100),
+    created_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    processed_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    processed_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT refunds_amount_check CHECK (amount > 0),
+    CONSTRAINT refunds_status_check CHECK (status IN ('pending', 'completed'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_refunds_order ON refunds(order_id);
+CREATE INDEX IF NOT EXISTS idx_refunds_status ON refunds(status, created_at);
+
+DROP TRIGGER IF EXISTS update_refunds_updated_at ON refunds;
+CREATE TRIGGER update_refunds_updated_at BEFORE UPDATE ON refunds
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
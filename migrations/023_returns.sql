@@ -0,0 +1,61 @@
+-- Return requests (RMA) for delivered orders. The buyer picks the order
+-- lines and quantities to send back; the admin approves or rejects, marks
+-- the parcel received (optionally restocking it) and issues a refund.
+CREATE TABLE IF NOT EXISTS return_requests (
+    id SERIAL PRIMARY KEY,
+    order_id INTEGER NOT NULL REFERENCES orders(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    status VARCHAR(20) NOT NULL DEFAULT 'pending', -- pending, approved, rejected, received, refunded
+    reason VARCHAR(30) NOT NULL, -- defect, damaged, wrong_item, not_as_described, wrong_size
+    description TEXT,
+    admin_note TEXT,
+    restocked BOOLEAN NOT NULL DEFAULT false,
+    reviewed_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    reviewed_at TIMESTAMP,
+    received_at TIMESTAMP,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    CONSTRAINT return_requests_status_check CHECK (status IN ('pending', 'approved', 'rejected', 'received', 'refunded')),
+    CONSTRAINT return_requests_reason_check CHECK (reason IN ('defect', 'damaged', 'wrong_item', 'not_as_described', 'wrong_size'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_return_requests_order ON return_requests(order_id);
+CREATE INDEX IF NOT EXISTS idx_return_requests_user ON return_requests(user_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_return_requests_status ON return_requests(status, created_at);
+
+DROP TRIGGER IF EXISTS update_return_requests_updated_at ON return_requests;
+CREATE TRIGGER update_return_requests_updated_at BEFORE UPDATE ON return_requests
+    FOR EACH ROW EXECUTE FUNCTION update_updated_at_column();
+
+CREATE TABLE IF NOT EXISTS return_items (
+    id SERIAL PRIMARY KEY,
+    return_id INTEGER NOT NULL REFERENCES return_requests(id) ON DELETE CASCADE,
+    order_item_id INTEGER NOT NULL REFERENCES order_items(id) ON DELETE CASCADE,
+    quantity INTEGER NOT NULL,
+    CONSTRAINT return_items_quantity_check CHECK (quantity > 0),
+    UNIQUE(return_id, order_item_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_return_items_order_item ON return_items(order_item_id);
+
+-- Photos of the defect. Uploaded first and attached when the request is
+-- submitted, like review photos; return_id stays NULL until then.
+CREATE TABLE IF NOT EXISTS return_photos (
+    id SERIAL PRIMARY KEY,
+    return_id INTEGER REFERENCES return_requests(id) ON DELETE CASCADE,
+    user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    image_url VARCHAR(500) NOT NULL,
+    webp_url VARCHAR(500),
+    thumbnail_url VARCHAR(500),
+    width INTEGER,
+    height INTEGER,
+    storage_keys TEXT[] NOT NULL DEFAULT '{}',
+    sort_order INTEGER NOT NULL DEFAULT 0,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_return_photos_return_id ON return_photos(return_id);
+CREATE INDEX IF NOT EXISTS idx_return_photos_unattached ON return_photos(user_id, created_at) WHERE return_id IS NULL;
+
+-- The return a refund pays for; NULL for refunds of cancelled orders
+ALTER TABLE refunds ADD COLUMN IF NOT EXISTS return_id INTEGER REFERENCES return_requests(id) ON DELETE SET NULL;
@@ -0,0 +1,30 @@
+-- Invoices get their own gapless number per calendar year (INV-2026-000001),
+-- independent of the order number. The counter row is locked while a number
+-- is taken, so a rolled back issue doesn't leave a hole like a SEQUENCE would.
+CREATE TABLE IF NOT EXISTS invoice_counters (
+    year INTEGER PRIMARY KEY,
+    last_number INTEGER NOT NULL DEFAULT 0
+);
+
+-- One invoice per order. `content` is the snapshot the PDF is rendered from
+-- (seller, billing address, lines, totals), so the document never changes
+-- once issued, even if the order or the seller details do.
+CREATE TABLE IF NOT EXISTS invoices (
+    id SERIAL PRIMARY KEY,
+    order_id INTEGER NOT NULL UNIQUE REFERENCES orders(id) ON DELETE CASCADE,
+    invoice_number VARCHAR(30) NOT NULL UNIQUE,
+    content JSONB NOT NULL,
+    issued_by INTEGER REFERENCES users(id) ON DELETE SET NULL,
+    issued_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE OR REPLACE FUNCTION prevent_invoice_update()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'invoice % has been issued and cannot be changed', OLD.invoice_number;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS invoices_immutable ON invoices;
+CREATE TRIGGER invoices_immutable BEFORE UPDATE ON invoices
+    FOR EACH ROW EXECUTE FUNCTION prevent_invoice_update();
@@ -0,0 +1,12 @@
+-- Sales tax (PPN) per order. orders.tax_amount already exists; these record
+-- the rules it was computed with and the taxable base (DPP), so the split
+-- stays correct when the rules change later.
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS tax_rate DECIMAL(5, 2) NOT NULL DEFAULT 0;
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS prices_include_tax BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS taxable_amount DECIMAL(12, 2) NOT NULL DEFAULT 0;
+
+-- The line's share of the order's coupon discounts, and its tax after that
+-- share, so a return can refund what was actually paid for the line
+ALTER TABLE order_items ADD COLUMN IF NOT EXISTS discount_amount DECIMAL(12, 2) NOT NULL DEFAULT 0;
+ALTER TABLE order_items ADD COLUMN IF NOT EXISTS tax_amount DECIMAL(12, 2) NOT NULL DEFAULT 0;
+ALTER TABLE order_items ADD COLUMN IF NOT EXISTS tax_exempt BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,11 @@
+-- Order numbers count up per calendar day in Jakarta time
+-- (BK-20261018-000123-7: a six digit sequence plus a check digit). The
+-- counter is bumped inside the checkout transaction and its row stays locked
+-- until commit, so a failed checkout gives its number back and the numbers
+-- have no gaps. A day can't go past what six digits can show; the CHECK
+-- fails the checkout instead. orders.order_number stays UNIQUE as the last
+-- line of defence.
+CREATE TABLE IF NOT EXISTS order_number_counters (
+    day DATE PRIMARY KEY,
+    last_number INTEGER NOT NULL DEFAULT 0 CHECK (last_number <= 999999)
+);
@@ -1,41 +1,7 @@
 use actix_web::{web, App, HttpServer, HttpResponse, middleware::Logger, Result};
 use actix_cors::Cors;
 use serde_json::json;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-
-// Global cart storage (in-memory for simplicity)
-type CartStorage = Arc<Mutex<HashMap<String, Vec<CartItem>>>>;
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct CartItem {
-    id: i32,
-    product_id: i32,
-    quantity: i32,
-    product: CartProduct,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-struct CartProduct {
-    id: i32,
-    name: String,
-    price: i64,
-    discount_price: Option<i64>,
-    image_url: String,
-    stock_quantity: i32,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct AddToCartRequest {
-    product_id: i32,
-    quantity: i32,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct UpdateCartRequest {
-    quantity: i32,
-}
+use serde::Deserialize;
 
 #[derive(Deserialize)]
 struct LoginRequest {
@@ -520,220 +486,6 @@ async fn register(register_data: web::Json<RegisterRequest>) -> Result<HttpRespo
     })))
 }
 
-// Cart functions
-async fn get_cart(cart_storage: web::Data<CartStorage>) -> Result<HttpResponse> {
-    // For simplicity, use a fixed user ID (in real app, get from JWT token)
-    let user_id = "user_1".to_string();
-    
-    let carts = cart_storage.lock().unwrap();
-    let user_cart = carts.get(&user_id).cloned().unwrap_or_default();
-    
-    let total_items: i32 = user_cart.iter().map(|item| item.quantity).sum();
-    let subtotal: i64 = user_cart.iter().map(|item| {
-        let price = item.product.discount_price.unwrap_or(item.product.price);
-        price * item.quantity as i64
-    }).sum();
-    
-    Ok(HttpResponse::Ok().json(json!({
-        "success": true,
-        "data": {
-            "items": user_cart,
-            "total_items": total_items,
-            "subtotal": subtotal,
-            "total_discount": 0,
-            "total_amount": subtotal
-        },
-        "message": "Cart retrieved successfully"
-    })))
-}
-
-async fn add_to_cart(
-    cart_storage: web::Data<CartStorage>,
-    add_request: web::Json<AddToCartRequest>
-) -> Result<HttpResponse> {
-    let user_id = "user_1".to_string();
-    
-    // Mock product data lookup
-    let products_map = get_products_map();
-    let product = match products_map.get(&add_request.product_id) {
-        Some(p) => p,
-        None => return Ok(HttpResponse::NotFound().json(json!({
-            "success": false,
-            "message": "Product not found"
-        })))
-    };
-    
-    let mut carts = cart_storage.lock().unwrap();
-    let user_cart = carts.entry(user_id).or_insert_with(Vec::new);
-    
-    // Check if item already exists
-    if let Some(existing_item) = user_cart.iter_mut().find(|item| item.product_id == add_request.product_id) {
-        existing_item.quantity += add_request.quantity;
-    } else {
-        let new_cart_item = CartItem {
-            id: user_cart.len() as i32 + 1,
-            product_id: add_request.product_id,
-            quantity: add_request.quantity,
-            product: product.clone(),
-        };
-        user_cart.push(new_cart_item);
-    }
-    
-    Ok(HttpResponse::Created().json(json!({
-        "success": true,
-        "data": {
-            "message": "Item added to cart successfully"
-        },
-        "message": "Item added to cart"
-    })))
-}
-
-async fn update_cart_item(
-    cart_storage: web::Data<CartStorage>,
-    path: web::Path<i32>,
-    update_request: web::Json<UpdateCartRequest>
-) -> Result<HttpResponse> {
-    let item_id = path.into_inner();
-    let user_id = "user_1".to_string();
-    
-    let mut carts = cart_storage.lock().unwrap();
-    let user_cart = carts.entry(user_id).or_insert_with(Vec::new);
-    
-    if let Some(item) = user_cart.iter_mut().find(|item| item.id == item_id) {
-        if update_request.quantity <= 0 {
-            user_cart.retain(|item| item.id != item_id);
-        } else {
-            item.quantity = update_request.quantity;
-        }
-        Ok(HttpResponse::Ok().json(json!({
-            "success": true,
-            "message": "Cart item updated successfully"
-        })))
-    } else {
-        Ok(HttpResponse::NotFound().json(json!({
-            "success": false,
-            "message": "Cart item not found"
-        })))
-    }
-}
-
-async fn remove_from_cart(
-    cart_storage: web::Data<CartStorage>,
-    path: web::Path<i32>
-) -> Result<HttpResponse> {
-    let item_id = path.into_inner();
-    let user_id = "user_1".to_string();
-    
-    let mut carts = cart_storage.lock().unwrap();
-    let user_cart = carts.entry(user_id).or_insert_with(Vec::new);
-    
-    let initial_len = user_cart.len();
-    user_cart.retain(|item| item.id != item_id);
-    
-    if user_cart.len() < initial_len {
-        Ok(HttpResponse::Ok().json(json!({
-            "success": true,
-            "message": "Item removed from cart"
-        })))
-    } else {
-        Ok(HttpResponse::NotFound().json(json!({
-            "success": false,
-            "message": "Cart item not found"
-        })))
-    }
-}
-
-async fn clear_cart(cart_storage: web::Data<CartStorage>) -> Result<HttpResponse> {
-    let user_id = "user_1".to_string();
-    
-    let mut carts = cart_storage.lock().unwrap();
-    carts.insert(user_id, Vec::new());
-    
-    Ok(HttpResponse::Ok().json(json!({
-        "success": true,
-        "message": "Cart cleared successfully"
-    })))
-}
-
-// Helper function to get products as a map for cart operations
-fn get_products_map() -> HashMap<i32, CartProduct> {
-    let mut products = HashMap::new();
-    
-    products.insert(1, CartProduct {
-        id: 1,
-        name: "Batik Mega Mendung".to_string(),
-        price: 150000,
-        discount_price: Some(120000),
-        image_url: "/images/batik-1.jpg".to_string(),
-        stock_quantity: 10,
-    });
-    
-    products.insert(2, CartProduct {
-        id: 2,
-        name: "Batik Parang".to_string(),
-        price: 200000,
-        discount_price: Some(160000),
-        image_url: "/images/batik-2.jpg".to_string(),
-        stock_quantity: 5,
-    });
-    
-    products.insert(3, CartProduct {
-        id: 3,
-        name: "Batik Kawung".to_string(),
-        price: 175000,
-        discount_price: Some(140000),
-        image_url: "/images/batik-3.jpg".to_string(),
-        stock_quantity: 8,
-    });
-    
-    products.insert(4, CartProduct {
-        id: 4,
-        name: "Batik Sekar Jagad".to_string(),
-        price: 225000,
-        discount_price: Some(180000),
-        image_url: "/images/batik-4.jpg".to_string(),
-        stock_quantity: 6,
-    });
-    
-    products.insert(5, CartProduct {
-        id: 5,
-        name: "Batik Truntum".to_string(),
-        price: 135000,
-        discount_price: Some(108000),
-        image_url: "/images/batik-5.jpg".to_string(),
-        stock_quantity: 15,
-    });
-    
-    products.insert(6, CartProduct {
-        id: 6,
-        name: "Batik Sogan Klasik".to_string(),
-        price: 190000,
-        discount_price: Some(152000),
-        image_url: "/images/batik-6.jpg".to_string(),
-        stock_quantity: 7,
-    });
-    
-    products.insert(7, CartProduct {
-        id: 7,
-        name: "Batik Pekalongan Pesisir".to_string(),
-        price: 165000,
-        discount_price: Some(132000),
-        image_url: "/images/batik-7.jpg".to_string(),
-        stock_quantity: 12,
-    });
-    
-    products.insert(8, CartProduct {
-        id: 8,
-        name: "Batik Indigo Modern".to_string(),
-        price: 145000,
-        discount_price: Some(116000),
-        image_url: "/images/batik-8.jpg".to_string(),
-        stock_quantity: 20,
-    });
-    
-    products
-}
-
 async fn get_product_by_id(path: web::Path<i32>) -> Result<HttpResponse> {
     let product_id = path.into_inner();
     
@@ -799,10 +551,6 @@ pub async fn run_api_server() -> std::io::Result<()> {
     println!("📱 Health check: http://127.0.0.1:8080/health");
     println!("🛍️  Products API: http://127.0.0.1:8080/api/products");
     println!("🔐 Auth API: http://127.0.0.1:8080/api/auth/login");
-    println!("🛒 Cart API: http://127.0.0.1:8080/api/cart");
-
-    // Initialize cart storage
-    let cart_storage: CartStorage = Arc::new(Mutex::new(HashMap::new()));
 
     HttpServer::new(move || {
         // Get environment variables for CORS
@@ -818,7 +566,6 @@ pub async fn run_api_server() -> std::io::Result<()> {
             .supports_credentials();
 
         App::new()
-            .app_data(web::Data::new(cart_storage.clone()))
             .wrap(cors)
             .wrap(Logger::default())
             .route("/", web::get().to(health))
@@ -833,14 +580,6 @@ pub async fn run_api_server() -> std::io::Result<()> {
                             .route("/login", web::post().to(login))
                             .route("/register", web::post().to(register))
                     )
-                    .service(
-                        web::scope("/cart")
-                            .route("", web::get().to(get_cart))
-                            .route("", web::post().to(add_to_cart))
-                            .route("", web::delete().to(clear_cart))
-                            .route("/{id}", web::put().to(update_cart_item))
-                            .route("/{id}", web::delete().to(remove_from_cart))
-                    )
             )
     })
     .bind("127.0.0.1:8080")?
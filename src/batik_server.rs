@@ -16,6 +16,20 @@ pub async fn run_batik_server() -> std::io::Result<()> {
         .await
         .expect("Failed to connect to database");
 
+    // Cancel orders that were never paid and return their stock
+    crate::services::cancellation::spawn_reservation_sweeper(pool.clone());
+    // Drop guest carts nobody came back to
+    crate::services::carts::spawn_guest_cart_sweeper(pool.clone());
+    // Forget Idempotency-Key responses older than a day
+    crate::services::idempotency::spawn_key_purger(pool.clone());
+
+    // Uploaded images (local disk unless STORAGE_BACKEND=s3)
+    let blob_store = crate::services::storage::from_env();
+    let upload_dir = crate::services::storage::local_upload_dir();
+    // Shipping rates (built-in rate table unless SHIPPING_PROVIDER=rajaongkir)
+    let shipping_provider = crate::services::shipping::from_env();
+    let shipment_tracker = crate::services::tracking::from_env();
+
     println!("🚀 Starting BatikKita Backend Server");
     println!("📍 Server running at: http://localhost:8080");
     println!("🔐 Authentication endpoints:");
@@ -27,29 +41,115 @@ pub async fn run_batik_server() -> std::io::Result<()> {
     println!("📦 Product endpoints:");
     println!("   GET /api/products - Get all products");
     println!("   GET /api/products/{{id}} - Get product by ID");
-    println!("   POST /api/products/{{id}}/reviews - Create product review");
-    println!("   GET /api/products/{{id}}/reviews - Get product reviews");
+    println!("   GET /api/products/slug/{{slug}} - Get product by slug (301 if renamed)");
     println!("   POST /api/admin/products - Create product (Admin)");
     println!("   PUT /api/admin/products/{{id}} - Update product (Admin)");
     println!("   DELETE /api/admin/products/{{id}} - Delete product (Admin)");
+    println!("🗂️ Category endpoints:");
+    println!("   GET /api/categories - Category tree with product counts");
+    println!("   GET /api/categories/{{slug}} - Category with subcategories (301 if renamed)");
+    println!("   GET /sitemap.xml - Sitemap of active products and categories");
+    println!("   GET /api/admin/categories - List all categories (Admin)");
+    println!("   POST /api/admin/categories - Create category (Admin)");
+    println!("   PUT /api/admin/categories/{{id}} - Update category (Admin)");
+    println!("   DELETE /api/admin/categories/{{id}} - Delete category (Admin)");
+    println!("⭐ Review endpoints:");
+    println!("   GET /api/products/{{id}}/reviews?sort=newest|helpful|rating_high|rating_low&with_photos=true - Get approved reviews");
+    println!("   POST /api/products/{{id}}/reviews - Create review (held for moderation)");
+    println!("   POST /api/reviews/photos - Upload up to 5 review photos, multipart");
+    println!("   PUT /api/reviews/{{id}} - Edit own review");
+    println!("   DELETE /api/reviews/{{id}} - Delete own review");
+    println!("   POST /api/reviews/{{id}}/helpful - Mark review helpful");
+    println!("   DELETE /api/reviews/{{id}}/helpful - Remove helpful vote");
+    println!("   GET /api/admin/reviews?status=pending - Moderation queue (Admin)");
+    println!("   PUT /api/admin/reviews/{{id}}/moderation - Approve or hide review (Admin)");
+    println!("   DELETE /api/admin/reviews/{{id}}/photos/{{photo_id}} - Remove review photo (Admin)");
+    println!("🏷️ Product attribute endpoints:");
+    println!("   GET /api/products/attributes - Available technique/fabric/origin/motif/dye values");
+    println!("   GET /api/products/{{id}}/features - Get product features");
+    println!("   POST /api/admin/products/{{id}}/features - Add product feature (Admin)");
+    println!("   PUT /api/admin/products/{{id}}/features/{{feature_id}} - Update product feature (Admin)");
+    println!("   DELETE /api/admin/products/{{id}}/features/{{feature_id}} - Delete product feature (Admin)");
+    println!("🖼️ Product image endpoints:");
+    println!("   GET /api/products/{{id}}/images - Get product images");
+    println!("   POST /api/admin/products/{{id}}/images - Upload images, multipart (Admin)");
+    println!("   PUT /api/admin/products/{{id}}/images/order - Reorder images (Admin)");
+    println!("   PUT /api/admin/products/{{id}}/images/{{image_id}} - Update alt text / primary (Admin)");
+    println!("   DELETE /api/admin/products/{{id}}/images/{{image_id}} - Delete image (Admin)");
     println!("❤️ Favorite endpoints:");
-    println!("   GET /api/auth/favorites - Get user favorites");
-    println!("   POST /api/auth/favorites/{{id}} - Add to favorites");
-    println!("   DELETE /api/auth/favorites/{{id}} - Remove from favorites");
-    println!("   GET /api/auth/favorites/check/{{id}} - Check favorite status");
-    println!("   DELETE /api/auth/favorites/clear - Clear all favorites");
+    println!("   GET /api/favorites - Get user favorites");
+    println!("   POST /api/favorites/{{id}} - Add to favorites");
+    println!("   DELETE /api/favorites/{{id}} - Remove from favorites");
+    println!("   GET /api/favorites/check/{{id}} - Check favorite status");
+    println!("   DELETE /api/favorites/clear - Clear all favorites");
+    println!("   PUT /api/favorites/{{id}}/note - Set note on a favorite");
+    println!("   GET /api/product-alerts - Price-drop/back-in-stock subscriptions");
+    println!("   POST /api/products/{{id}}/alerts - Subscribe to product alerts");
+    println!("   DELETE /api/products/{{id}}/alerts - Unsubscribe from product alerts");
+    println!("📝 Wishlist endpoints:");
+    println!("   GET /api/wishlists - List named wishlists");
+    println!("   POST /api/wishlists - Create wishlist");
+    println!("   GET /api/wishlists/{{id}} - Wishlist with items");
+    println!("   PUT /api/wishlists/{{id}} - Rename / share (shared: true|false)");
+    println!("   DELETE /api/wishlists/{{id}} - Delete wishlist");
+    println!("   POST /api/wishlists/{{id}}/items - Add item with note");
+    println!("   PUT /api/wishlists/{{id}}/items/{{item_id}} - Update note / quantity");
+    println!("   DELETE /api/wishlists/{{id}}/items/{{item_id}} - Remove item");
+    println!("   GET /api/wishlists/shared/{{token}} - Public read-only shared wishlist");
     println!("🛒 Cart endpoints:");
-    println!("   GET /api/auth/cart - Get user cart");
+    println!("   GET /api/cart - Get guest cart (X-Cart-Token header or cart_token cookie)");
+    println!("   POST /api/cart/items - Add item to guest cart, issues cart token");
+    println!("   PUT /api/cart/items/{{id}} - Update guest cart item");
+    println!("   DELETE /api/cart/items/{{id}} - Remove from guest cart");
+    println!("   DELETE /api/cart/clear - Clear guest cart");
+    println!("   GET /api/auth/cart - Get user cart (guest cart is merged on login)");
     println!("   POST /api/auth/cart/items - Add item to cart");
     println!("   PUT /api/auth/cart/items/{{id}} - Update cart item");
     println!("   DELETE /api/auth/cart/items/{{id}} - Remove from cart");
     println!("   DELETE /api/auth/cart/clear - Clear cart");
+    println!("   GET /api/auth/cart/saved - Saved-for-later items");
+    println!("   POST /api/auth/cart/items/{{id}}/save-for-later - Move cart item to saved for later");
+    println!("   POST /api/auth/cart/saved/{{id}}/move-to-cart - Move saved item back to cart");
+    println!("   DELETE /api/auth/cart/saved/{{id}} - Remove saved item");
+    println!("   POST /api/auth/cart/accept-prices - Accept changed prices (also /api/cart/accept-prices)");
     println!("🛍️ Checkout & Order endpoints:");
-    println!("   POST /api/auth/checkout - Create order from cart");
+    println!("   POST /api/auth/checkout - Create order from items, or from_cart with optional cart_item_ids");
+    println!("   (POST/PUT/DELETE under /api/auth and /api/admin accept an Idempotency-Key header for safe retries)");
+    println!("   POST /api/shipping/quote - Courier services and costs for items or a weight");
     println!("   GET /api/auth/orders - Get user orders");
-    println!("   GET /api/auth/orders/{{id}} - Get order details");
+    println!("   GET /api/auth/orders/{{id}} - Order details with shipments and status timeline");
+    println!("   GET /api/auth/orders/{{id}}/tracking - Shipments with courier tracking history");
+    println!("   POST /api/auth/orders/{{id}}/cancel - Cancel a pending/confirmed order (restores stock and coupons)");
     println!("   GET /api/admin/orders - Get all orders (Admin)");
-    println!("   PUT /api/admin/orders/{{id}}/status - Update order status (Admin)");
+    println!("   PUT /api/admin/orders/{{id}}/status - Move order to an allowed next status (Admin)");
+    println!("   GET /api/admin/orders/{{id}}/shipments - Shipments with tracking (Admin)");
+    println!("   POST /api/admin/orders/{{id}}/shipments - Register waybill and mark order shipped (Admin)");
+    println!("   PUT /api/admin/shipments/{{id}} - Correct waybill / courier (Admin)");
+    println!("   GET /api/admin/refunds?status=pending|completed - Refunds to pay out (Admin)");
+    println!("   PUT /api/admin/refunds/{{id}}/complete - Mark refund paid with transfer reference (Admin)");
+    println!("   POST /api/auth/returns/photos - Upload photos for a return (multipart, field: file)");
+    println!("   POST /api/auth/orders/{{id}}/returns - Request a return for delivered items");
+    println!("   GET /api/auth/returns - My returns");
+    println!("   GET /api/admin/returns?status= - Return requests (Admin)");
+    println!("   PUT /api/admin/returns/{{id}}/review - Approve or reject a return (Admin)");
+    println!("   PUT /api/admin/returns/{{id}}/receive - Mark returned items received and restock (Admin)");
+    println!("   POST /api/admin/returns/{{id}}/refund - Refund a received return (Admin)");
+    println!("   GET /api/auth/orders/{{id}}/invoice.pdf - Download invoice (issued on first download)");
+    println!("   GET /api/admin/orders/{{id}}/invoice.pdf - Download invoice (Admin)");
+    println!("⚡ Promotion endpoints:");
+    println!("   GET /api/promotions/active - Running promotions and flash sales");
+    println!("   GET /api/admin/promotions - List promotions, ?status=scheduled|running|ended (Admin)");
+    println!("   GET /api/admin/promotions/{{id}} - Get promotion (Admin)");
+    println!("   POST /api/admin/promotions - Create promotion (Admin)");
+    println!("   PUT /api/admin/promotions/{{id}} - Update promotion (Admin)");
+    println!("   DELETE /api/admin/promotions/{{id}} - Delete or deactivate promotion (Admin)");
+    println!("🏷️ Coupon endpoints:");
+    println!("   POST /api/coupons/validate - Preview coupon discount for items or cart");
+    println!("   GET /api/admin/coupons - List coupons (Admin)");
+    println!("   GET /api/admin/coupons/{{id}} - Coupon with redemptions (Admin)");
+    println!("   POST /api/admin/coupons - Create coupon (Admin)");
+    println!("   PUT /api/admin/coupons/{{id}} - Update coupon (Admin)");
+    println!("   DELETE /api/admin/coupons/{{id}} - Delete or deactivate coupon (Admin)");
     println!("🔔 Notification endpoints:");
     println!("   GET /api/auth/notifications - Get user notifications");
     println!("   GET /api/auth/notifications/stats - Get notification stats");
@@ -66,49 +166,86 @@ pub async fn run_batik_server() -> std::io::Result<()> {
             .allow_any_origin()
             .allow_any_method()
             .allow_any_header()
+            .expose_headers(vec![crate::routes::cart::CART_TOKEN_HEADER])
             .supports_credentials();
 
-        App::new()
+        let mut app = App::new()
             .app_data(web::Data::new(pool.clone()))
+            .app_data(web::Data::from(blob_store.clone()))
+            .app_data(web::Data::from(shipping_provider.clone()))
+            .app_data(web::Data::from(shipment_tracker.clone()))
             .wrap(cors)
             .wrap(Logger::default())
             .route("/", web::get().to(health))
             .route("/health", web::get().to(health))
+            .configure(crate::routes::sitemap::init);
+
+        if let Some(dir) = &upload_dir {
+            app = app.service(actix_files::Files::new("/uploads", dir));
+        }
+
+        app
             .service(
                 web::scope("/api")
-                    // Public auth routes (login, register)
+                    // Public auth routes (login, register), then the protected
+                    // user routes. Both live in one /auth scope: a second scope
+                    // with the same prefix would never be reached.
                     .service(
                         web::scope("/auth")
                             .service(crate::routes::auth::register)
                             .service(crate::routes::auth::login)
-                    )
-                    // Protected user routes  
-                    .service(
-                        web::scope("/auth")
-                            .wrap(crate::middleware::AuthMiddleware)
-                            .configure(crate::routes::user::configure)
-                            .service(crate::routes::cart::get_cart)
-                            .service(crate::routes::cart::add_to_cart)
-                            .service(crate::routes::cart::update_cart_item)
-                            .service(crate::routes::cart::remove_from_cart)
-                            .service(crate::routes::cart::clear_cart)
-                            .service(crate::routes::cart::get_guest_cart)
-                            .configure(crate::routes::checkout::init)
+                            .service(
+                                web::scope("")
+                                    // Inside AuthMiddleware, which provides the user the keys belong to
+                                    .wrap(crate::middleware::Idempotency)
+                                    .wrap(crate::middleware::AuthMiddleware)
+                                    .configure(crate::routes::user::configure)
+                                    .configure(crate::routes::cart::user_routes)
+                                    .configure(crate::routes::checkout::init)
+                                    .configure(crate::routes::shipment::user_routes)
+                                    .configure(crate::routes::returns::user_routes)
+                                    .configure(crate::routes::invoice::user_routes)
+                            )
                     )
                     // Admin routes
                     .service(
                         web::scope("/admin")
+                            .wrap(crate::middleware::Idempotency)
                             .wrap(crate::middleware::AdminAuth)
                             .configure(crate::routes::admin::admin_scope)
+                            .configure(crate::routes::product::admin_routes)
+                            .configure(crate::routes::product_attribute::admin_routes)
+                            .configure(crate::routes::category::admin_routes)
+                            .configure(crate::routes::review::admin_routes)
+                            .configure(crate::routes::checkout::admin_routes)
+                            .configure(crate::routes::shipment::admin_routes)
+                            .configure(crate::routes::refund::admin_routes)
+                            .configure(crate::routes::returns::admin_routes)
+                            .configure(crate::routes::invoice::admin_routes)
+                            .configure(crate::routes::coupon::admin_routes)
+                            .configure(crate::routes::promotion::admin_routes)
+                            .configure(crate::routes::product_image::admin_routes)
                             .wrap(crate::middleware::AuthMiddleware)
                     )
                     // Public product routes
+                    .configure(crate::routes::product_attribute::init)
+                    .configure(crate::routes::category::init)
                     .configure(crate::routes::product::init)
+                    .configure(crate::routes::review::init)
+                    .configure(crate::routes::product_image::init)
+                    .configure(crate::routes::cart::init)
+                    .configure(crate::routes::wishlist::init)
+                    .configure(crate::routes::promotion::init)
+                    .configure(crate::routes::shipping::init)
                     // Protected routes that frontend calls without /auth prefix
                     .service(
                         web::scope("")
                             .wrap(crate::middleware::AuthMiddleware)
+                            .configure(crate::routes::review::user_routes)
                             .configure(crate::routes::favorite::init)
+                            .configure(crate::routes::wishlist::user_routes)
+                            .configure(crate::routes::product_alert::user_routes)
+                            .configure(crate::routes::coupon::user_routes)
                             .configure(crate::routes::notification::init)
                     )
             )
@@ -124,7 +261,7 @@ async fn health() -> Result<HttpResponse> {
         "data": {
             "status": "ok",
             "version": "1.0.0",
-            "features": ["authentication", "user_management", "admin", "product_management", "reviews", "favorites", "cart", "checkout", "orders", "notifications", "real_time"]
+            "features": ["authentication", "user_management", "admin", "product_management", "categories", "reviews", "favorites", "cart", "checkout", "orders", "notifications", "real_time"]
         },
         "message": "BatikKita Backend Server is running"
     })))
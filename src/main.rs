@@ -5,6 +5,7 @@ mod models;
 mod utils;
 mod middleware;
 mod db;
+mod services;
 mod seeder;
 
 #[actix_web::main]
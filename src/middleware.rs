@@ -1,6 +1,8 @@
 pub mod auth;
+pub mod idempotency;
 
 pub use auth::AuthMiddleware;
+pub use idempotency::Idempotency;
 
 use actix_web::{
     dev::{ServiceRequest, ServiceResponse, Transform, forward_ready},
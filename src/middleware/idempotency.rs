@@ -0,0 +1,212 @@
+use actix_web::{
+    body::{self, EitherBody, MessageBody},
+    dev::{forward_ready, Payload, Service, ServiceRequest, ServiceResponse, Transform},
+    http::{header, Method, StatusCode},
+    web, Error, HttpMessage, HttpResponse,
+};
+use futures_util::{future::LocalBoxFuture, StreamExt};
+use sqlx::PgPool;
+use std::{
+    future::{ready, Ready},
+    rc::Rc,
+};
+
+use crate::models::user::Claims;
+use crate::services::idempotency::{self, Claim};
+use crate::services::images::max_upload_bytes;
+
+const HEADER: &str = "Idempotency-Key";
+const MAX_KEY_LENGTH: usize = 255;
+// The biggest request is an upload of this many files (product images)...
+const MAX_FILES_PER_REQUEST: usize = 10;
+// ...plus the multipart framing and text fields around them
+const FORM_OVERHEAD_BYTES: usize = 64 * 1024;
+
+// Largest body buffered for fingerprinting; anything bigger is refused
+fn max_body_bytes() -> usize {
+    max_upload_bytes()
+        .saturating_mul(MAX_FILES_PER_REQUEST)
+        .saturating_add(FORM_OVERHEAD_BYTES)
+}
+
+fn body_too_large<B>(req: ServiceRequest, limit: usize) -> ServiceResponse<EitherBody<B>> {
+    let response = HttpResponse::PayloadTooLarge()
+        .json(format!("Request body must be at most {} bytes", limit));
+    short_circuit(req, response)
+}
+
+// Makes mutating requests that carry an `Idempotency-Key` header safe to
+// retry: the first response is stored and replayed for repeats of the same
+// request within 24 hours, and reusing the key for a different request is
+// rejected. Keys are per user, so this must be wrapped inside AuthMiddleware.
+// Requests without the header pass through untouched.
+pub struct Idempotency;
+
+impl<S, B> Transform<S, ServiceRequest> for Idempotency
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Transform = IdempotencyService<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(IdempotencyService {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct IdempotencyService<S> {
+    service: Rc<S>,
+}
+
+fn short_circuit<B>(req: ServiceRequest, response: HttpResponse) -> ServiceResponse<EitherBody<B>> {
+    req.into_response(response).map_into_right_body()
+}
+
+impl<S, B> Service<ServiceRequest> for IdempotencyService<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    S::Future: 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<EitherBody<B>>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    forward_ready!(service);
+
+    fn call(&self, mut req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+
+        Box::pin(async move {
+            let mutating = !matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS);
+            let key = req.headers().get(HEADER).map(|value| value.to_str().map(|key| key.trim().to_string()));
+            let user_id = req.extensions().get::<Claims>().and_then(|claims| claims.sub.parse::<i32>().ok());
+            let pool = req.app_data::<web::Data<PgPool>>().cloned();
+
+            let (key, user_id, pool) = match (key, user_id, pool) {
+                (Some(key), Some(user_id), Some(pool)) if mutating => (key, user_id, pool),
+                _ => return Ok(service.call(req).await?.map_into_left_body()),
+            };
+            let key = match key {
+                Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => key,
+                _ => {
+                    let response = HttpResponse::BadRequest()
+                        .json(format!("{} must be 1 to {} visible ASCII characters", HEADER, MAX_KEY_LENGTH));
+                    return Ok(short_circuit(req, response));
+                }
+            };
+
+            // Read the body to fingerprint it, then hand it back to the handler.
+            // Stop at the limit so an endless body can't exhaust memory.
+            let limit = max_body_bytes();
+            let declared_length = req
+                .headers()
+                .get(header::CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<usize>().ok());
+            if declared_length.is_some_and(|length| length > limit) {
+                return Ok(body_too_large(req, limit));
+            }
+            let mut payload = req.take_payload();
+            let mut bytes = web::BytesMut::new();
+            while let Some(chunk) = payload.next().await {
+                let chunk = chunk?;
+                if bytes.len() + chunk.len() > limit {
+                    return Ok(body_too_large(req, limit));
+                }
+                bytes.extend_from_slice(&chunk);
+            }
+            let bytes = bytes.freeze();
+            let request_hash = idempotency::request_hash(
+                req.method().as_str(),
+                req.path(),
+                req.query_string(),
+                &bytes,
+            );
+            req.set_payload(Payload::from(bytes));
+
+            let claim_id = match idempotency::claim(pool.get_ref(), user_id, &key, &request_hash).await {
+                Ok(Claim::New(claim_id)) => claim_id,
+                Ok(Claim::InProgress) => {
+                    let response = HttpResponse::Conflict()
+                        .json("A request with this Idempotency-Key is still being processed");
+                    return Ok(short_circuit(req, response));
+                }
+                Ok(Claim::Mismatch) => {
+                    let response = HttpResponse::UnprocessableEntity()
+                        .json("This Idempotency-Key was already used for a different request");
+                    return Ok(short_circuit(req, response));
+                }
+                Ok(Claim::Completed { status, headers, body }) => {
+                    let mut response = HttpResponse::build(StatusCode::from_u16(status).unwrap_or(StatusCode::OK));
+                    for header in headers {
+                        response.append_header(header);
+                    }
+                    response.insert_header(("Idempotent-Replayed", "true"));
+                    return Ok(short_circuit(req, response.body(body)));
+                }
+                Err(e) => {
+                    eprintln!("Database error: {}", e);
+                    let response = HttpResponse::InternalServerError().json("Failed to check Idempotency-Key");
+                    return Ok(short_circuit(req, response));
+                }
+            };
+
+            let res = match service.call(req).await {
+                Ok(res) => res,
+                Err(e) => {
+                    if let Err(db_error) = idempotency::release(pool.get_ref(), claim_id).await {
+                        eprintln!("Database error: {}", db_error);
+                    }
+                    return Err(e);
+                }
+            };
+
+            // Server errors are not stored so the client can retry them
+            if res.status().is_server_error() {
+                if let Err(e) = idempotency::release(pool.get_ref(), claim_id).await {
+                    eprintln!("Database error: {}", e);
+                }
+                return Ok(res.map_into_left_body());
+            }
+
+            let (req, response) = res.into_parts();
+            let status = response.status();
+            let headers: Vec<(String, String)> = response
+                .headers()
+                .iter()
+                .filter(|(name, _)| **name != header::CONTENT_LENGTH && **name != header::TRANSFER_ENCODING)
+                .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+                .collect();
+            let body = match body::to_bytes(response.into_body()).await {
+                Ok(body) => body,
+                Err(e) => {
+                    let e: Box<dyn std::error::Error> = e.into();
+                    eprintln!("Failed to read response for Idempotency-Key: {}", e);
+                    if let Err(db_error) = idempotency::release(pool.get_ref(), claim_id).await {
+                        eprintln!("Database error: {}", db_error);
+                    }
+                    let response = HttpResponse::InternalServerError().finish();
+                    return Ok(ServiceResponse::new(req, response).map_into_right_body());
+                }
+            };
+
+            if let Err(e) = idempotency::complete(pool.get_ref(), claim_id, status.as_u16(), &headers, &body).await {
+                eprintln!("Database error: {}", e);
+            }
+
+            let mut response = HttpResponse::build(status);
+            for header in headers {
+                response.append_header(header);
+            }
+            Ok(ServiceResponse::new(req, response.body(body)).map_into_right_body())
+        })
+    }
+}
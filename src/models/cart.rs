@@ -1,13 +1,17 @@
 use serde::{Serialize, Deserialize};
 use sqlx::FromRow;
-use chrono::{DateTime, Utc};
+use chrono::NaiveDateTime;
+use bigdecimal::BigDecimal;
+use uuid::Uuid;
 
+// Carts belong to either a user or a guest (signed cart token)
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Cart {
     pub id: i32,
-    pub user_id: i32,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
+    pub user_id: Option<i32>,
+    pub guest_token: Option<Uuid>,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -18,9 +22,9 @@ pub struct CartItem {
     pub quantity: i32,
     pub size: Option<String>,
     pub color: Option<String>,
-    pub price_at_time: i64, // harga saat ditambahkan ke cart
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
+    pub price_at_time: BigDecimal, // harga saat ditambahkan ke cart
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
 }
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
@@ -29,14 +33,18 @@ pub struct CartItemWithProduct {
     pub cart_id: i32,
     pub product_id: i32,
     pub product_name: String,
-    pub product_image: String,
+    pub product_image: Option<String>,
     pub quantity: i32,
     pub size: Option<String>,
     pub color: Option<String>,
-    pub price_at_time: i64,
-    pub current_price: i64,
+    pub price_at_time: BigDecimal,
+    pub current_price: BigDecimal,
     pub stock_available: i32,
-    pub created_at: DateTime<Utc>,
+    pub created_at: Option<NaiveDateTime>,
+    // Changes since the item was added; checkout refuses until resolved
+    pub price_changed: bool,
+    pub out_of_stock: bool,
+    pub unavailable: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -57,6 +65,25 @@ pub struct UpdateCartItemRequest {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CartSummary {
     pub total_items: i32,
-    pub total_price: i64,
+    pub total_price: BigDecimal,
+    pub has_changes: bool,
     pub items: Vec<CartItemWithProduct>,
+}
+
+// A cart line whose product price differs from the price the customer saw
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct CartPriceChange {
+    pub cart_item_id: i32,
+    pub product_id: i32,
+    pub size: Option<String>,
+    pub color: Option<String>,
+    pub price_at_time: BigDecimal,
+    pub current_price: BigDecimal,
+}
+
+// Payload of the signed token that identifies a guest cart
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CartTokenClaims {
+    pub cart: Uuid,
+    pub exp: usize,
 }
\ No newline at end of file
@@ -0,0 +1,60 @@
+use serde::{Serialize, Deserialize, Deserializer};
+use sqlx::FromRow;
+use chrono::NaiveDateTime;
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Category {
+    pub id: i32,
+    pub parent_id: Option<i32>,
+    pub name: String,
+    pub slug: String,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub sort_order: i32,
+    pub is_active: bool,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+// A category with its subcategories. `product_count` includes the
+// products of all descendants.
+#[derive(Debug, Serialize)]
+pub struct CategoryNode {
+    #[serde(flatten)]
+    pub category: Category,
+    pub product_count: i64,
+    pub children: Vec<CategoryNode>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateCategoryRequest {
+    pub name: String,
+    pub slug: Option<String>,
+    pub parent_id: Option<i32>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub sort_order: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateCategoryRequest {
+    pub name: Option<String>,
+    pub slug: Option<String>,
+    // Absent: keep the parent, null: move to the top level
+    #[serde(default, deserialize_with = "deserialize_some")]
+    pub parent_id: Option<Option<i32>>,
+    pub description: Option<String>,
+    pub image_url: Option<String>,
+    pub sort_order: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+// For Option<Option<T>> fields, so an absent field and an explicit null differ
+pub(crate) fn deserialize_some<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
@@ -0,0 +1,121 @@
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+use chrono::NaiveDateTime;
+use bigdecimal::BigDecimal;
+use crate::models::order::OrderItemRequest;
+
+pub const PERCENTAGE: &str = "percentage";
+pub const FIXED: &str = "fixed";
+pub const FREE_SHIPPING: &str = "free_shipping";
+pub const DISCOUNT_TYPES: [&str; 3] = [PERCENTAGE, FIXED, FREE_SHIPPING];
+
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Coupon {
+    pub id: i32,
+    pub code: String,
+    pub description: Option<String>,
+    pub discount_type: String, // percentage, fixed, free_shipping
+    pub discount_value: BigDecimal,
+    pub min_order_amount: Option<BigDecimal>,
+    pub max_discount_amount: Option<BigDecimal>,
+    pub usage_limit: Option<i32>,
+    pub used_count: i32,
+    pub per_user_limit: Option<i32>,
+    pub valid_from: Option<NaiveDateTime>,
+    pub valid_until: Option<NaiveDateTime>,
+    // Restrict the discount to these products/categories; empty means all
+    pub product_ids: Option<Vec<i32>>,
+    pub category_ids: Option<Vec<i32>>,
+    pub first_order_only: bool,
+    pub is_active: bool,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateCouponRequest {
+    pub code: String,
+    pub description: Option<String>,
+    pub discount_type: String,
+    // Ignored for free_shipping coupons
+    #[serde(default)]
+    pub discount_value: BigDecimal,
+    pub min_order_amount: Option<BigDecimal>,
+    pub max_discount_amount: Option<BigDecimal>,
+    pub usage_limit: Option<i32>,
+    pub per_user_limit: Option<i32>,
+    pub valid_from: Option<NaiveDateTime>,
+    pub valid_until: Option<NaiveDateTime>,
+    pub product_ids: Option<Vec<i32>>,
+    pub category_ids: Option<Vec<i32>>,
+    #[serde(default)]
+    pub first_order_only: bool,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateCouponRequest {
+    pub description: Option<String>,
+    pub discount_type: Option<String>,
+    pub discount_value: Option<BigDecimal>,
+    pub min_order_amount: Option<BigDecimal>,
+    pub max_discount_amount: Option<BigDecimal>,
+    pub usage_limit: Option<i32>,
+    pub per_user_limit: Option<i32>,
+    pub valid_from: Option<NaiveDateTime>,
+    pub valid_until: Option<NaiveDateTime>,
+    pub product_ids: Option<Vec<i32>>,
+    pub category_ids: Option<Vec<i32>>,
+    pub first_order_only: Option<bool>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CouponListQuery {
+    pub is_active: Option<bool>,
+    pub search: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+// Preview of what the codes would take off an order: the given items, or
+// the user's cart when no items are sent
+#[derive(Debug, Deserialize)]
+pub struct ValidateCouponRequest {
+    pub coupon_code: Option<String>,
+    #[serde(default)]
+    pub coupon_codes: Vec<String>,
+    #[serde(default)]
+    pub items: Vec<OrderItemRequest>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AppliedCoupon {
+    pub coupon_id: i32,
+    pub code: String,
+    pub discount_type: String,
+    pub discount_amount: BigDecimal,
+    pub free_shipping: bool,
+    // `discount_amount` split over the order lines, in the order they were given
+    #[serde(skip)]
+    pub line_discounts: Vec<BigDecimal>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CouponValidation {
+    pub subtotal: BigDecimal,
+    pub discount_amount: BigDecimal,
+    pub free_shipping: bool,
+    pub coupons: Vec<AppliedCoupon>,
+}
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct CouponRedemption {
+    pub id: i32,
+    pub coupon_id: i32,
+    pub user_id: i32,
+    pub order_id: i32,
+    pub discount_amount: BigDecimal,
+    pub shipping_discount: BigDecimal,
+    pub created_at: Option<NaiveDateTime>,
+}
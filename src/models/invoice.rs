@@ -0,0 +1,54 @@
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+use sqlx::types::Json;
+use chrono::NaiveDateTime;
+use bigdecimal::BigDecimal;
+
+#[derive(Debug, Serialize, FromRow)]
+pub struct Invoice {
+    pub id: i32,
+    pub order_id: i32,
+    pub invoice_number: String,
+    pub content: Json<InvoiceContent>,
+    pub issued_by: Option<i32>,
+    pub issued_at: NaiveDateTime,
+}
+
+// Everything printed on the invoice, frozen when it is issued
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InvoiceContent {
+    pub seller: InvoiceParty,
+    pub bill_to: InvoiceParty,
+    pub order_number: String,
+    pub order_date: Option<NaiveDateTime>,
+    pub payment_method: Option<String>,
+    pub lines: Vec<InvoiceLine>,
+    pub subtotal: BigDecimal,
+    pub discount: BigDecimal,
+    pub shipping: BigDecimal,
+    pub tax_lines: Vec<InvoiceTaxLine>,
+    pub total: BigDecimal,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InvoiceParty {
+    pub name: String,
+    pub address: Vec<String>,
+    // NPWP (Indonesian tax id)
+    pub tax_id: Option<String>,
+    pub phone: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InvoiceLine {
+    pub description: String,
+    pub quantity: i32,
+    pub unit_price: BigDecimal,
+    pub total: BigDecimal,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct InvoiceTaxLine {
+    pub label: String,
+    pub amount: BigDecimal,
+}
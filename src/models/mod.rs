@@ -2,4 +2,12 @@ pub mod user;
 pub mod product;
 pub mod cart;
 pub mod order;
-pub mod notification;
\ No newline at end of file
+pub mod notification;
+pub mod category;
+pub mod wishlist;
+pub mod coupon;
+pub mod promotion;
+pub mod shipping;
+pub mod refund;
+pub mod returns;
+pub mod invoice;
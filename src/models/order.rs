@@ -1,6 +1,8 @@
 use serde::{Serialize, Deserialize};
 use sqlx::FromRow;
-use chrono::{DateTime, Utc};
+use chrono::{NaiveDate, NaiveDateTime};
+use bigdecimal::BigDecimal;
+use crate::models::shipping::Shipment;
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct Order {
@@ -8,19 +10,27 @@ pub struct Order {
     pub user_id: i32,
     pub order_number: String,
     pub status: OrderStatus,
-    pub total_amount: i64,
-    pub shipping_cost: i64,
-    pub discount_amount: Option<i64>,
-    pub final_amount: i64,
-    pub payment_method: String,
+    pub total_amount: BigDecimal,
+    pub shipping_cost: BigDecimal,
+    pub discount_amount: BigDecimal,
+    // Sales tax (PPN). With `prices_include_tax` it is part of the item
+    // prices, otherwise it was added to `final_amount`.
+    pub tax_amount: BigDecimal,
+    pub tax_rate: BigDecimal,
+    pub prices_include_tax: bool,
+    pub taxable_amount: BigDecimal,
+    pub final_amount: BigDecimal,
+    pub payment_method: Option<String>,
     pub payment_status: PaymentStatus,
-    pub shipping_address: serde_json::Value,
-    pub billing_address: serde_json::Value,
+    pub shipping_address: Option<serde_json::Value>,
+    pub billing_address: Option<serde_json::Value>,
     pub notes: Option<String>,
-    pub shipped_at: Option<DateTime<Utc>>,
-    pub delivered_at: Option<DateTime<Utc>>,
-    pub created_at: DateTime<Utc>,
-    pub updated_at: DateTime<Utc>,
+    pub shipping_courier: Option<String>,
+    pub shipping_service: Option<String>,
+    pub shipped_at: Option<NaiveDateTime>,
+    pub delivered_at: Option<NaiveDateTime>,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -28,18 +38,23 @@ pub struct OrderItem {
     pub id: i32,
     pub order_id: i32,
     pub product_id: i32,
-    pub product_name: String,
-    pub product_image: String,
+    pub product_name: Option<String>,
+    pub product_image: Option<String>,
     pub quantity: i32,
     pub size: Option<String>,
     pub color: Option<String>,
-    pub price_at_time: i64,
-    pub total_price: i64,
-    pub created_at: DateTime<Utc>,
+    pub price_at_time: BigDecimal,
+    pub total_price: BigDecimal,
+    pub discount_amount: BigDecimal,
+    pub tax_amount: BigDecimal,
+    pub tax_exempt: bool,
+    pub promotion_id: Option<i32>,
+    pub created_at: Option<NaiveDateTime>,
 }
 
-#[derive(Debug, Serialize, Deserialize, sqlx::Type)]
-#[sqlx(type_name = "order_status", rename_all = "lowercase")]
+// Stored as lowercase VARCHAR
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
 pub enum OrderStatus {
     Pending,
     Confirmed,
@@ -50,8 +65,23 @@ pub enum OrderStatus {
     Refunded,
 }
 
-#[derive(Debug, Serialize, Deserialize, sqlx::Type)]
-#[sqlx(type_name = "payment_status", rename_all = "lowercase")]
+impl OrderStatus {
+    // The stored value, for messages
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OrderStatus::Pending => "pending",
+            OrderStatus::Confirmed => "confirmed",
+            OrderStatus::Processing => "processing",
+            OrderStatus::Shipped => "shipped",
+            OrderStatus::Delivered => "delivered",
+            OrderStatus::Cancelled => "cancelled",
+            OrderStatus::Refunded => "refunded",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "varchar", rename_all = "lowercase")]
 pub enum PaymentStatus {
     Pending,
     Paid,
@@ -59,14 +89,37 @@ pub enum PaymentStatus {
     Refunded,
 }
 
+impl PaymentStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PaymentStatus::Pending => "pending",
+            PaymentStatus::Paid => "paid",
+            PaymentStatus::Failed => "failed",
+            PaymentStatus::Refunded => "refunded",
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateOrderRequest {
+    // Explicit items, or `from_cart` to order the cart (optionally only the
+    // lines in `cart_item_ids`)
+    #[serde(default)]
     pub items: Vec<OrderItemRequest>,
+    #[serde(default)]
+    pub from_cart: bool,
+    pub cart_item_ids: Option<Vec<i32>>,
     pub shipping_address: AddressRequest,
     pub billing_address: Option<AddressRequest>,
     pub payment_method: String,
     pub notes: Option<String>,
+    // Courier service from /api/shipping/quote; the cheapest when omitted
+    pub shipping_courier: Option<String>,
+    pub shipping_service: Option<String>,
+    // One discount coupon and one free-shipping coupon can be combined
     pub coupon_code: Option<String>,
+    #[serde(default)]
+    pub coupon_codes: Vec<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -91,13 +144,37 @@ pub struct AddressRequest {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateOrderStatusRequest {
     pub status: OrderStatus,
+    // Kept in the status history; the customer's order notes stay as they are
     pub notes: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct CancelOrderRequest {
+    pub reason: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OrderWithItems {
     pub order: Order,
     pub items: Vec<OrderItem>,
+    #[serde(default)]
+    pub shipments: Vec<Shipment>,
+    #[serde(default)]
+    pub status_history: Vec<OrderStatusChange>,
+}
+
+// One entry of an order's timeline; `from_status` is empty for the row
+// written when the order was placed
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct OrderStatusChange {
+    pub id: i32,
+    pub order_id: i32,
+    pub from_status: Option<OrderStatus>,
+    pub to_status: OrderStatus,
+    pub actor_id: Option<i32>,
+    pub actor_role: String, // customer, admin, system
+    pub note: Option<String>,
+    pub created_at: NaiveDateTime,
 }
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
@@ -105,23 +182,66 @@ pub struct OrderSummary {
     pub id: i32,
     pub order_number: String,
     pub status: OrderStatus,
-    pub total_amount: i64,
-    pub item_count: i32,
-    pub created_at: DateTime<Utc>,
+    pub total_amount: BigDecimal,
+    pub tax_amount: BigDecimal,
+    pub item_count: i64,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+// Filters for the admin order list and export. Dates are inclusive;
+// `email` and `order_number` match partially, `product` matches product names.
+#[derive(Debug, Deserialize)]
+pub struct AdminOrderQuery {
+    pub status: Option<String>,
+    pub payment_status: Option<String>,
+    pub date_from: Option<NaiveDate>,
+    pub date_to: Option<NaiveDate>,
+    pub email: Option<String>,
+    pub order_number: Option<String>,
+    pub product_id: Option<i32>,
+    pub product: Option<String>,
+    pub sort_by: Option<String>, // created_at, final_amount, order_number
+    pub sort_order: Option<String>, // asc, desc
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    pub format: Option<String>, // csv, xlsx; export only
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
-pub struct Coupon {
+pub struct AdminOrderSummary {
     pub id: i32,
-    pub code: String,
-    pub discount_type: String, // percentage, fixed
-    pub discount_value: i64,
-    pub min_order_amount: Option<i64>,
-    pub max_discount_amount: Option<i64>,
-    pub usage_limit: Option<i32>,
-    pub used_count: i32,
-    pub valid_from: DateTime<Utc>,
-    pub valid_until: DateTime<Utc>,
-    pub is_active: bool,
-    pub created_at: DateTime<Utc>,
-}
\ No newline at end of file
+    pub order_number: String,
+    pub status: OrderStatus,
+    pub payment_status: PaymentStatus,
+    pub final_amount: BigDecimal,
+    pub created_at: Option<NaiveDateTime>,
+    pub user_name: String,
+    pub user_email: String,
+    pub item_count: i64,
+}
+
+// One line of the order export: the order with one of its items
+#[derive(Debug, FromRow)]
+pub struct OrderExportRow {
+    pub order_id: i32,
+    pub order_number: String,
+    pub created_at: Option<NaiveDateTime>,
+    pub status: OrderStatus,
+    pub payment_status: PaymentStatus,
+    pub payment_method: Option<String>,
+    pub user_name: String,
+    pub user_email: String,
+    pub total_amount: BigDecimal,
+    pub discount_amount: BigDecimal,
+    pub shipping_cost: BigDecimal,
+    pub tax_amount: BigDecimal,
+    pub final_amount: BigDecimal,
+    pub product_id: Option<i32>,
+    pub product_name: Option<String>,
+    pub size: Option<String>,
+    pub color: Option<String>,
+    pub quantity: Option<i32>,
+    pub price_at_time: Option<BigDecimal>,
+    pub item_total: Option<BigDecimal>,
+    pub item_tax: Option<BigDecimal>,
+}
@@ -28,18 +28,48 @@ pub struct Product {
     pub sold_count: Option<i32>,
     pub size_options: Option<Vec<String>>,
     pub color_options: Option<Vec<String>>,
+    pub technique: Option<String>, // tulis, cap, printing, kombinasi
+    pub fabric: Option<String>,
+    pub origin_region: Option<String>,
+    pub motif: Option<String>,
+    pub dye_type: Option<String>,
+    pub category_id: Option<i32>,
+    pub slug: Option<String>,
+    pub average_rating: BigDecimal,
+    pub review_count: i32,
+    pub rating_histogram: Vec<i32>, // counts of 1..5 star reviews
+    // Selling price from product_prices, filled in by the storefront queries
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_price: Option<BigDecimal>,
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub promotion_id: Option<i32>,
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub promotion_ends_at: Option<NaiveDateTime>,
+    // Units left at the promotional price for capped flash sales
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub promotion_remaining: Option<i32>,
 }
 
+pub const BATIK_TECHNIQUES: [&str; 4] = ["tulis", "cap", "printing", "kombinasi"];
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateProductRequest {
     pub name: String,
+    // Generated from the name when omitted
+    pub slug: Option<String>,
     pub description: Option<String>,
     pub short_description: Option<String>,
     pub price: BigDecimal,
     pub discount_price: Option<BigDecimal>,
     pub sku: String,
     pub stock_quantity: i32,
-    pub category: String,
+    // Either a category id or the name/slug of an existing category
+    pub category: Option<String>,
+    pub category_id: Option<i32>,
     pub brand: Option<String>,
     pub weight: Option<BigDecimal>,
     pub dimensions: Option<String>,
@@ -51,11 +81,17 @@ pub struct CreateProductRequest {
     pub original_price: Option<BigDecimal>,
     pub size_options: Option<Vec<String>>,
     pub color_options: Option<Vec<String>>,
+    pub technique: Option<String>,
+    pub fabric: Option<String>,
+    pub origin_region: Option<String>,
+    pub motif: Option<String>,
+    pub dye_type: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UpdateProductRequest {
     pub name: Option<String>,
+    pub slug: Option<String>,
     pub description: Option<String>,
     pub short_description: Option<String>,
     pub price: Option<BigDecimal>,
@@ -63,6 +99,7 @@ pub struct UpdateProductRequest {
     pub sku: Option<String>,
     pub stock_quantity: Option<i32>,
     pub category: Option<String>,
+    pub category_id: Option<i32>,
     pub brand: Option<String>,
     pub weight: Option<BigDecimal>,
     pub dimensions: Option<String>,
@@ -74,6 +111,11 @@ pub struct UpdateProductRequest {
     pub original_price: Option<BigDecimal>,
     pub size_options: Option<Vec<String>>,
     pub color_options: Option<Vec<String>>,
+    pub technique: Option<String>,
+    pub fabric: Option<String>,
+    pub origin_region: Option<String>,
+    pub motif: Option<String>,
+    pub dye_type: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -85,12 +127,19 @@ pub struct ProductFilter {
     pub size_options: Option<Vec<String>>,
     pub color_options: Option<Vec<String>>,
     pub in_stock_only: Option<bool>,
+    pub technique: Option<String>,
+    pub fabric: Option<String>,
+    pub origin_region: Option<String>,
+    pub motif: Option<String>,
+    pub dye_type: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ProductSort {
-    pub field: String, // price, rating, created_at, sold_count
-    pub direction: String, // asc, desc
+    #[serde(rename = "sort_by")]
+    pub field: Option<String>, // price, rating, created_at, sold_count
+    #[serde(rename = "sort_order")]
+    pub direction: Option<String>, // asc, desc
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -102,14 +151,62 @@ pub struct Review {
     pub rating: i32,
     pub comment: Option<String>,
     pub is_verified: bool,
+    pub status: String, // pending, approved, hidden
+    pub moderation_reason: Option<String>,
+    pub helpful_count: i32,
     pub created_at: NaiveDateTime,
+    pub updated_at: Option<NaiveDateTime>,
+    #[sqlx(skip)]
+    pub photos: Vec<ReviewPhoto>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct ReviewPhoto {
+    pub id: i32,
+    pub review_id: Option<i32>,
+    pub image_url: String,
+    pub webp_url: Option<String>,
+    pub thumbnail_url: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    #[serde(skip_serializing)]
+    pub storage_keys: Vec<String>,
+    pub sort_order: i32,
+    pub created_at: Option<NaiveDateTime>,
 }
 
+pub const MAX_REVIEW_PHOTOS: usize = 5;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateReviewRequest {
     pub product_id: i32,
     pub rating: i32,
     pub comment: Option<String>,
+    // Ids returned by POST /api/reviews/photos, at most MAX_REVIEW_PHOTOS
+    pub photo_ids: Option<Vec<i32>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateReviewRequest {
+    pub rating: Option<i32>,
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewQuery {
+    pub sort: Option<String>, // newest (default), helpful, rating_high, rating_low
+    pub with_photos: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewModerationQuery {
+    pub status: Option<String>, // defaults to pending
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ModerateReviewRequest {
+    pub action: String, // approve, hide
+    pub reason: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -117,5 +214,101 @@ pub struct Favorite {
     pub id: i32,
     pub user_id: i32,
     pub product_id: i32,
-    pub created_at: NaiveDateTime,
-}
\ No newline at end of file
+    pub wishlist_id: Option<i32>, // None for the favorites list
+    pub note: Option<String>,
+    pub size: Option<String>,
+    pub color: Option<String>,
+    pub quantity: i32,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FavoriteNoteRequest {
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct ProductAlertSubscription {
+    pub id: i32,
+    pub user_id: i32,
+    pub product_id: i32,
+    pub alert_type: String, // price_drop, back_in_stock
+    pub created_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProductAlertRequest {
+    // Both alert types when omitted
+    pub alert_types: Option<Vec<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProductAlertQuery {
+    pub alert_type: Option<String>,
+}
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct ProductImage {
+    pub id: i32,
+    pub product_id: i32,
+    pub image_url: String,
+    pub webp_url: Option<String>,
+    pub thumbnail_url: Option<String>,
+    pub alt_text: Option<String>,
+    pub is_primary: bool,
+    pub sort_order: i32,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    #[serde(skip_serializing)]
+    pub storage_keys: Vec<String>,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateProductImageRequest {
+    pub alt_text: Option<String>,
+    pub is_primary: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReorderProductImagesRequest {
+    pub image_ids: Vec<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductDetail {
+    #[serde(flatten)]
+    pub product: Product,
+    pub images: Vec<ProductImage>,
+    pub features: Vec<ProductFeature>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct ProductFeature {
+    pub id: i32,
+    pub product_id: i32,
+    pub feature_name: String,
+    pub feature_value: String,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductFeatureRequest {
+    pub feature_name: String,
+    pub feature_value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct AttributeValueCount {
+    pub value: String,
+    pub product_count: i64,
+}
+
+// Available filter values with the number of active products for each
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProductAttributeFacets {
+    pub techniques: Vec<AttributeValueCount>,
+    pub fabrics: Vec<AttributeValueCount>,
+    pub origin_regions: Vec<AttributeValueCount>,
+    pub motifs: Vec<AttributeValueCount>,
+    pub dye_types: Vec<AttributeValueCount>,
+}
@@ -0,0 +1,66 @@
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+use chrono::NaiveDateTime;
+use bigdecimal::BigDecimal;
+
+pub const PERCENTAGE: &str = "percentage";
+pub const FIXED: &str = "fixed";
+pub const DISCOUNT_TYPES: [&str; 2] = [PERCENTAGE, FIXED];
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Promotion {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub discount_type: String, // percentage, fixed
+    pub discount_value: BigDecimal,
+    pub product_ids: Option<Vec<i32>>,
+    pub category_ids: Option<Vec<i32>>,
+    pub brands: Option<Vec<String>>,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+    pub quantity_limit: Option<i32>,
+    pub sold_quantity: i32,
+    pub is_active: bool,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreatePromotionRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub discount_type: String,
+    pub discount_value: BigDecimal,
+    // At least one target is required
+    pub product_ids: Option<Vec<i32>>,
+    pub category_ids: Option<Vec<i32>>,
+    pub brands: Option<Vec<String>>,
+    pub starts_at: NaiveDateTime,
+    pub ends_at: NaiveDateTime,
+    pub quantity_limit: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdatePromotionRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub discount_type: Option<String>,
+    pub discount_value: Option<BigDecimal>,
+    pub product_ids: Option<Vec<i32>>,
+    pub category_ids: Option<Vec<i32>>,
+    pub brands: Option<Vec<String>>,
+    pub starts_at: Option<NaiveDateTime>,
+    pub ends_at: Option<NaiveDateTime>,
+    pub quantity_limit: Option<i32>,
+    pub is_active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PromotionListQuery {
+    // scheduled, running, ended
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
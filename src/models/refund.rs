@@ -0,0 +1,37 @@
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+use chrono::NaiveDateTime;
+use bigdecimal::BigDecimal;
+
+pub const PENDING: &str = "pending";
+pub const COMPLETED: &str = "completed";
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Refund {
+    pub id: i32,
+    pub order_id: i32,
+    pub return_id: Option<i32>,
+    pub amount: BigDecimal,
+    pub reason: String,
+    pub status: String, // pending, completed
+    pub reference: Option<String>,
+    pub created_by: Option<i32>,
+    pub processed_by: Option<i32>,
+    pub processed_at: Option<NaiveDateTime>,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+// Mark a refund as paid out, with the bank transfer reference
+#[derive(Debug, Deserialize)]
+pub struct CompleteRefundRequest {
+    pub reference: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefundListQuery {
+    // pending, completed
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
@@ -0,0 +1,104 @@
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+use chrono::NaiveDateTime;
+use bigdecimal::BigDecimal;
+
+pub const PENDING: &str = "pending";
+pub const APPROVED: &str = "approved";
+pub const REJECTED: &str = "rejected";
+pub const RECEIVED: &str = "received";
+pub const REFUNDED: &str = "refunded";
+pub const STATUSES: [&str; 5] = [PENDING, APPROVED, REJECTED, RECEIVED, REFUNDED];
+
+pub const REASONS: [&str; 5] = ["defect", "damaged", "wrong_item", "not_as_described", "wrong_size"];
+// A size exchange is the only reason that doesn't need photos
+pub const REASONS_WITHOUT_PHOTOS: [&str; 1] = ["wrong_size"];
+
+pub const MAX_RETURN_PHOTOS: usize = 5;
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct ReturnRequest {
+    pub id: i32,
+    pub order_id: i32,
+    pub user_id: i32,
+    pub status: String, // pending, approved, rejected, received, refunded
+    pub reason: String,
+    pub description: Option<String>,
+    pub admin_note: Option<String>,
+    pub restocked: bool,
+    pub reviewed_by: Option<i32>,
+    pub reviewed_at: Option<NaiveDateTime>,
+    pub received_at: Option<NaiveDateTime>,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+    #[sqlx(skip)]
+    pub items: Vec<ReturnItem>,
+    #[sqlx(skip)]
+    pub photos: Vec<ReturnPhoto>,
+}
+
+// A returned order line, with what the buyer paid for it
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct ReturnItem {
+    pub id: i32,
+    pub return_id: i32,
+    pub order_item_id: i32,
+    pub product_id: i32,
+    pub product_name: Option<String>,
+    pub quantity: i32,
+    pub price_at_time: BigDecimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct ReturnPhoto {
+    pub id: i32,
+    pub return_id: Option<i32>,
+    pub image_url: String,
+    pub webp_url: Option<String>,
+    pub thumbnail_url: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub sort_order: i32,
+    pub created_at: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateReturnRequest {
+    pub items: Vec<ReturnItemRequest>,
+    pub reason: String,
+    pub description: Option<String>,
+    // Ids from POST /returns/photos
+    pub photo_ids: Option<Vec<i32>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReturnItemRequest {
+    pub order_item_id: i32,
+    pub quantity: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReviewReturnRequest {
+    pub approve: bool,
+    pub note: Option<String>,
+}
+
+// Defective pieces usually can't be sold again; set restock to false
+#[derive(Debug, Deserialize)]
+pub struct ReceiveReturnRequest {
+    pub restock: Option<bool>,
+    pub note: Option<String>,
+}
+
+// Defaults to what the buyer paid for the returned items
+#[derive(Debug, Deserialize)]
+pub struct RefundReturnRequest {
+    pub amount: Option<BigDecimal>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReturnListQuery {
+    pub status: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
@@ -0,0 +1,56 @@
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+use chrono::NaiveDateTime;
+use bigdecimal::BigDecimal;
+use crate::models::order::OrderItemRequest;
+
+// Quote for the given items, or for a known parcel weight
+#[derive(Debug, Deserialize)]
+pub struct ShippingQuoteRequest {
+    #[serde(default)]
+    pub items: Vec<OrderItemRequest>,
+    pub weight_grams: Option<i32>,
+    pub province: String,
+    #[serde(default)]
+    pub city: String,
+    #[serde(default)]
+    pub postal_code: String,
+}
+
+// A parcel handed to a courier. The cached tracking history is returned by
+// the tracking endpoint, not with the shipment itself.
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Shipment {
+    pub id: i32,
+    pub order_id: i32,
+    pub courier: String,
+    pub service: Option<String>,
+    pub waybill: String,
+    pub weight_grams: i32,
+    pub cost: BigDecimal,
+    pub notes: Option<String>,
+    pub delivered_at: Option<NaiveDateTime>,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+// Courier, service and cost default to what the customer chose at checkout,
+// the weight to the weight of the ordered products
+#[derive(Debug, Deserialize)]
+pub struct CreateShipmentRequest {
+    pub waybill: String,
+    pub courier: Option<String>,
+    pub service: Option<String>,
+    pub weight_grams: Option<i32>,
+    pub cost: Option<BigDecimal>,
+    pub notes: Option<String>,
+}
+
+// Correct a mistyped waybill or courier; clears the cached tracking
+#[derive(Debug, Deserialize)]
+pub struct UpdateShipmentRequest {
+    pub waybill: Option<String>,
+    pub courier: Option<String>,
+    pub service: Option<String>,
+    pub notes: Option<String>,
+}
@@ -0,0 +1,86 @@
+use serde::{Serialize, Deserialize};
+use sqlx::FromRow;
+use chrono::NaiveDateTime;
+use bigdecimal::BigDecimal;
+use uuid::Uuid;
+
+// Wishlist kinds
+pub const WISHLIST: &str = "wishlist";
+pub const SAVED_FOR_LATER: &str = "saved_for_later";
+
+pub const MAX_NOTE_LENGTH: usize = 500;
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct Wishlist {
+    pub id: i32,
+    pub user_id: i32,
+    pub name: String,
+    pub kind: String,
+    pub share_token: Option<Uuid>,
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+    #[sqlx(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_count: Option<i64>,
+}
+
+// A favorites row together with the product it points at
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct WishlistItem {
+    pub id: i32,
+    pub product_id: i32,
+    pub note: Option<String>,
+    pub size: Option<String>,
+    pub color: Option<String>,
+    pub quantity: i32,
+    pub created_at: Option<NaiveDateTime>,
+    pub product_name: String,
+    pub product_slug: Option<String>,
+    pub product_image: Option<String>,
+    pub price: BigDecimal,
+    pub stock_available: i32,
+    pub is_active: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WishlistWithItems {
+    #[serde(flatten)]
+    pub wishlist: Wishlist,
+    pub items: Vec<WishlistItem>,
+}
+
+// Read-only view behind a share link
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SharedWishlist {
+    pub name: String,
+    pub owner_name: String,
+    pub items: Vec<WishlistItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateWishlistRequest {
+    pub name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateWishlistRequest {
+    pub name: Option<String>,
+    pub shared: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AddWishlistItemRequest {
+    pub product_id: i32,
+    pub note: Option<String>,
+    pub size: Option<String>,
+    pub color: Option<String>,
+    pub quantity: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateWishlistItemRequest {
+    // Absent: keep the note, null or empty: remove it
+    #[serde(default, deserialize_with = "crate::models::category::deserialize_some")]
+    pub note: Option<Option<String>>,
+    pub quantity: Option<i32>,
+}
@@ -1,6 +1,8 @@
-use actix_web::{post, web, HttpResponse, Responder};
+use actix_web::{post, web, HttpRequest, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
 use crate::models::user::User;
+use crate::routes::cart::{expired_cart_token_cookie, request_cart_token};
+use crate::services::carts::merge_guest_cart;
 use crate::utils::jwt::create_jwt;
 use crate::utils::response::ApiResponse;
 use sqlx::PgPool;
@@ -79,6 +81,7 @@ pub struct LoginResponse {
 
 #[post("/login")]
 pub async fn login(
+    req: HttpRequest,
     data: web::Json<LoginRequest>,
     pool: web::Data<PgPool>,
 ) -> impl Responder {
@@ -93,6 +96,15 @@ pub async fn login(
         Ok(Some(u)) => {
             if verify(&data.password, &u.password_hash).unwrap_or(false) {
                 let token = create_jwt(u.id, u.role.clone()).unwrap();
+
+                // Carry over whatever was put in the cart before logging in
+                let guest_token = request_cart_token(&req);
+                if let Some(guest_token) = guest_token {
+                    if let Err(e) = merge_guest_cart(pool.get_ref(), guest_token, u.id).await {
+                        eprintln!("Failed to merge guest cart: {}", e);
+                    }
+                }
+
                 let response_data = LoginResponse {
                     user: UserResponse {
                         id: u.id,
@@ -102,7 +114,11 @@ pub async fn login(
                     },
                     token,
                 };
-                HttpResponse::Ok().json(ApiResponse::success(response_data, "Login successful"))
+                let mut response = HttpResponse::Ok();
+                if guest_token.is_some() {
+                    response.cookie(expired_cart_token_cookie());
+                }
+                response.json(ApiResponse::success(response_data, "Login successful"))
             } else {
                 HttpResponse::Unauthorized().json("Invalid credentials")
             }
@@ -1,148 +1,206 @@
-use actix_web::{get, post, put, delete, web, HttpResponse, Responder, Result};
-use sqlx::PgPool;
+use actix_web::{get, post, put, delete, web, HttpRequest, HttpResponse, Responder, Result};
+use actix_web::cookie::{time::Duration, Cookie, SameSite};
+use actix_web::http::header::{HeaderName, HeaderValue};
+use bigdecimal::BigDecimal;
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
 use crate::models::cart::*;
 use crate::models::user::Claims;
-use crate::middleware::AuthMiddleware;
+use crate::routes::wishlist::{load_wishlist_items, saved_for_later_list};
+use crate::services::carts;
 use crate::utils::error_helpers::handle_db_error;
+use crate::utils::jwt::{create_cart_token, validate_cart_token, CART_TOKEN_TTL_DAYS};
+
+pub const CART_TOKEN_COOKIE: &str = "cart_token";
+pub const CART_TOKEN_HEADER: &str = "X-Cart-Token";
+
+// Guest cart id from the X-Cart-Token header, falling back to the cookie.
+// Tokens that fail verification are treated as absent.
+pub(crate) fn request_cart_token(req: &HttpRequest) -> Option<Uuid> {
+    let token = req
+        .headers()
+        .get(CART_TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+        .or_else(|| req.cookie(CART_TOKEN_COOKIE).map(|cookie| cookie.value().to_string()))?;
+
+    validate_cart_token(&token).ok()
+}
 
-#[get("/cart")]
-async fn get_cart(
-    pool: web::Data<PgPool>,
-    claims: web::ReqData<Claims>,
-) -> Result<impl Responder> {
-    let user_id: i32 = claims.sub.parse().unwrap();
+pub(crate) fn expired_cart_token_cookie() -> Cookie<'static> {
+    let mut cookie = Cookie::build(CART_TOKEN_COOKIE, "")
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .finish();
+    cookie.make_removal();
+    cookie
+}
 
-    // Get or create cart for user
-    let cart = handle_db_error(
-        sqlx::query_as::<_, Cart>(
-            "INSERT INTO carts (user_id, created_at, updated_at) 
-             VALUES ($1, NOW(), NOW()) 
-             ON CONFLICT (user_id) DO UPDATE SET updated_at = NOW()
-             RETURNING *"
-        )
-        .bind(user_id)
-        .fetch_one(pool.get_ref())
-        .await,
-        "Failed to get cart"
-    )?;
+// Hand a freshly issued guest cart token to the client
+fn attach_cart_token(response: &mut HttpResponse, guest_token: Uuid) -> Result<()> {
+    let token = create_cart_token(guest_token)
+        .map_err(|_| actix_web::error::ErrorInternalServerError("Failed to issue cart token"))?;
+
+    let cookie = Cookie::build(CART_TOKEN_COOKIE, token.clone())
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .max_age(Duration::days(CART_TOKEN_TTL_DAYS))
+        .finish();
+    response.add_cookie(&cookie)?;
+
+    if let Ok(value) = HeaderValue::from_str(&token) {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static("x-cart-token"), value);
+    }
+
+    Ok(())
+}
 
-    // Get cart items with product details
+fn empty_cart() -> CartSummary {
+    CartSummary {
+        total_items: 0,
+        total_price: BigDecimal::from(0),
+        has_changes: false,
+        items: vec![],
+    }
+}
+
+async fn cart_summary(pool: &PgPool, cart_id: i32) -> Result<CartSummary> {
+    // Get cart items with product details. Deactivated products stay in the
+    // cart, flagged, so the customer can see why they can't be ordered.
     let items = handle_db_error(
         sqlx::query_as::<_, CartItemWithProduct>(
             "SELECT ci.id, ci.cart_id, ci.product_id, p.name as product_name, p.image_url as product_image,
-                    ci.quantity, ci.size, ci.color, ci.price_at_time, p.price as current_price, 
-                    p.stock as stock_available, ci.created_at
+                    ci.quantity, ci.size, ci.color, ci.price_at_time, pp.current_price,
+                    COALESCE(p.stock, 0) as stock_available, ci.created_at,
+                    ci.price_at_time <> pp.current_price as price_changed,
+                    COALESCE(p.stock, 0) < ci.quantity as out_of_stock,
+                    NOT p.is_active as unavailable
              FROM cart_items ci
              JOIN products p ON ci.product_id = p.id
-             WHERE ci.cart_id = $1 AND p.is_active = true
+             JOIN product_prices pp ON pp.product_id = p.id
+             WHERE ci.cart_id = $1
              ORDER BY ci.created_at DESC"
         )
-        .bind(cart.id)
-        .fetch_all(pool.get_ref())
+        .bind(cart_id)
+        .fetch_all(pool)
         .await,
         "Failed to get cart items"
     )?;
 
-    let total_items = items.iter().map(|item| item.quantity).sum();
-    let total_price = items.iter().map(|item| item.price_at_time * item.quantity as i64).sum();
-
-    let cart_summary = CartSummary {
+    // Totals cover what can still be ordered, at the prices the customer saw
+    let orderable = items.iter().filter(|item| !item.unavailable);
+    let total_items = orderable.clone().map(|item| item.quantity).sum();
+    let total_price = orderable
+        .map(|item| &item.price_at_time * BigDecimal::from(item.quantity))
+        .sum();
+    let has_changes = items
+        .iter()
+        .any(|item| item.price_changed || item.out_of_stock || item.unavailable);
+
+    Ok(CartSummary {
         total_items,
         total_price,
+        has_changes,
         items,
-    };
-
-    Ok(HttpResponse::Ok().json(cart_summary))
+    })
 }
 
-#[post("/cart/items")]
-async fn add_to_cart(
-    pool: web::Data<PgPool>,
-    item_data: web::Json<AddToCartRequest>,
-    claims: web::ReqData<Claims>,
-) -> Result<impl Responder> {
-    let user_id: i32 = claims.sub.parse().unwrap();
-
-    // Get or create cart
-    let cart = handle_db_error(
-        sqlx::query_as::<_, Cart>(
-            "INSERT INTO carts (user_id, created_at, updated_at) 
-             VALUES ($1, NOW(), NOW()) 
-             ON CONFLICT (user_id) DO UPDATE SET updated_at = NOW()
-             RETURNING *"
+// Take over the current product prices for every line in the cart
+async fn accept_prices(pool: &PgPool, cart_id: i32) -> Result<HttpResponse> {
+    handle_db_error(
+        sqlx::query(
+            "UPDATE cart_items ci SET price_at_time = pp.current_price, updated_at = NOW()
+             FROM product_prices pp
+             WHERE ci.product_id = pp.product_id AND ci.cart_id = $1 AND ci.price_at_time <> pp.current_price"
         )
-        .bind(user_id)
-        .fetch_one(pool.get_ref())
+        .bind(cart_id)
+        .execute(pool)
         .await,
-        "Failed to get cart"
+        "Failed to update cart prices"
     )?;
 
+    let cart_summary = cart_summary(pool, cart_id).await?;
+    Ok(HttpResponse::Ok().json(cart_summary))
+}
+
+async fn add_item(conn: &mut PgConnection, cart_id: i32, item_data: &AddToCartRequest) -> Result<HttpResponse> {
+    if item_data.quantity < 1 {
+        return Ok(HttpResponse::BadRequest().json("Quantity must be at least 1"));
+    }
+
     // Get product details and check stock
     let product = handle_db_error(
-        sqlx::query!(
-            "SELECT price, stock FROM products WHERE id = $1 AND is_active = true",
-            item_data.product_id
+        sqlx::query_as::<_, (BigDecimal, Option<i32>)>(
+            "SELECT pp.current_price, p.stock
+             FROM products p
+             JOIN product_prices pp ON pp.product_id = p.id
+             WHERE p.id = $1 AND p.is_active = true"
         )
-        .fetch_optional(pool.get_ref())
+        .bind(item_data.product_id)
+        .fetch_optional(&mut *conn)
         .await,
         "Database error"
     )?;
 
-    let product = match product {
+    let (price, stock) = match product {
         Some(p) => p,
         None => return Ok(HttpResponse::NotFound().json("Product not found")),
     };
+    let stock = stock.unwrap_or(0);
 
-    if product.stock.unwrap_or(0) < item_data.quantity {
+    if stock < item_data.quantity {
         return Ok(HttpResponse::BadRequest().json("Insufficient stock"));
     }
 
     // Check if item already exists in cart
     let existing_item = handle_db_error(
-        sqlx::query!(
-            "SELECT id, quantity FROM cart_items 
-             WHERE cart_id = $1 AND product_id = $2 AND size = $3 AND color = $4",
-            cart.id,
-            item_data.product_id,
-            item_data.size,
-            item_data.color
+        sqlx::query_as::<_, (i32, i32)>(
+            "SELECT id, quantity FROM cart_items
+             WHERE cart_id = $1 AND product_id = $2
+               AND size IS NOT DISTINCT FROM $3 AND color IS NOT DISTINCT FROM $4"
         )
-        .fetch_optional(pool.get_ref())
+        .bind(cart_id)
+        .bind(item_data.product_id)
+        .bind(&item_data.size)
+        .bind(&item_data.color)
+        .fetch_optional(&mut *conn)
         .await,
         "Database error"
     )?;
 
-    if let Some(existing) = existing_item {
+    if let Some((existing_id, existing_quantity)) = existing_item {
         // Update existing item
-        let new_quantity = existing.quantity + item_data.quantity;
-        if product.stock.unwrap_or(0) < new_quantity {
+        let new_quantity = existing_quantity + item_data.quantity;
+        if stock < new_quantity {
             return Ok(HttpResponse::BadRequest().json("Insufficient stock"));
         }
 
         handle_db_error(
-            sqlx::query!(
-                "UPDATE cart_items SET quantity = $1, updated_at = NOW() WHERE id = $2",
-                new_quantity,
-                existing.id
-            )
-            .execute(pool.get_ref())
-            .await,
+            sqlx::query("UPDATE cart_items SET quantity = $1, updated_at = NOW() WHERE id = $2")
+                .bind(new_quantity)
+                .bind(existing_id)
+                .execute(&mut *conn)
+                .await,
             "Failed to update cart item"
         )?;
     } else {
         // Create new cart item
         handle_db_error(
-            sqlx::query!(
+            sqlx::query(
                 "INSERT INTO cart_items (cart_id, product_id, quantity, size, color, price_at_time, created_at, updated_at)
-                 VALUES ($1, $2, $3, $4, $5, $6, NOW(), NOW())",
-                cart.id,
-                item_data.product_id,
-                item_data.quantity,
-                item_data.size,
-                item_data.color,
-                product.price
+                 VALUES ($1, $2, $3, $4, $5, $6, NOW(), NOW())"
             )
-            .execute(pool.get_ref())
+            .bind(cart_id)
+            .bind(item_data.product_id)
+            .bind(item_data.quantity)
+            .bind(&item_data.size)
+            .bind(&item_data.color)
+            .bind(&price)
+            .execute(&mut *conn)
             .await,
             "Failed to add item to cart"
         )?;
@@ -151,62 +209,50 @@ async fn add_to_cart(
     Ok(HttpResponse::Created().json("Item added to cart successfully"))
 }
 
-#[put("/cart/items/{id}")]
-async fn update_cart_item(
-    pool: web::Data<PgPool>,
-    path: web::Path<i32>,
-    item_data: web::Json<UpdateCartItemRequest>,
-    claims: web::ReqData<Claims>,
-) -> Result<impl Responder> {
-    let user_id: i32 = claims.sub.parse().unwrap();
-    let item_id = path.into_inner();
+async fn update_item(
+    pool: &PgPool,
+    cart_id: i32,
+    item_id: i32,
+    item_data: &UpdateCartItemRequest,
+) -> Result<HttpResponse> {
+    if item_data.quantity < 1 {
+        return Ok(HttpResponse::BadRequest().json("Quantity must be at least 1"));
+    }
 
-    // Verify item belongs to user's cart
+    // Verify item belongs to this cart and check stock
     let cart_item = handle_db_error(
-        sqlx::query!(
-            "SELECT ci.id, ci.product_id FROM cart_items ci
-             JOIN carts c ON ci.cart_id = c.id
-             WHERE ci.id = $1 AND c.user_id = $2",
-            item_id,
-            user_id
+        sqlx::query_as::<_, (i32, Option<i32>)>(
+            "SELECT ci.id, p.stock FROM cart_items ci
+             JOIN products p ON ci.product_id = p.id
+             WHERE ci.id = $1 AND ci.cart_id = $2"
         )
-        .fetch_optional(pool.get_ref())
+        .bind(item_id)
+        .bind(cart_id)
+        .fetch_optional(pool)
         .await,
         "Database error"
     )?;
 
-    if cart_item.is_none() {
-        return Ok(HttpResponse::NotFound().json("Cart item not found"));
-    }
-
-    let cart_item = cart_item.unwrap();
-
-    // Check stock
-    let product = handle_db_error(
-        sqlx::query!(
-            "SELECT stock FROM products WHERE id = $1",
-            cart_item.product_id
-        )
-        .fetch_one(pool.get_ref())
-        .await,
-        "Database error"
-    )?;
+    let stock = match cart_item {
+        Some((_, stock)) => stock.unwrap_or(0),
+        None => return Ok(HttpResponse::NotFound().json("Cart item not found")),
+    };
 
-    if product.stock.unwrap_or(0) < item_data.quantity {
+    if stock < item_data.quantity {
         return Ok(HttpResponse::BadRequest().json("Insufficient stock"));
     }
 
     // Update cart item
     handle_db_error(
-        sqlx::query!(
-            "UPDATE cart_items SET quantity = $1, size = $2, color = $3, updated_at = NOW() 
-             WHERE id = $4",
-            item_data.quantity,
-            item_data.size,
-            item_data.color,
-            item_id
+        sqlx::query(
+            "UPDATE cart_items SET quantity = $1, size = $2, color = $3, updated_at = NOW()
+             WHERE id = $4"
         )
-        .execute(pool.get_ref())
+        .bind(item_data.quantity)
+        .bind(&item_data.size)
+        .bind(&item_data.color)
+        .bind(item_id)
+        .execute(pool)
         .await,
         "Failed to update cart item"
     )?;
@@ -214,76 +260,372 @@ async fn update_cart_item(
     Ok(HttpResponse::Ok().json("Cart item updated successfully"))
 }
 
+async fn remove_item(pool: &PgPool, cart_id: i32, item_id: i32) -> Result<HttpResponse> {
+    let result = handle_db_error(
+        sqlx::query("DELETE FROM cart_items WHERE id = $1 AND cart_id = $2")
+            .bind(item_id)
+            .bind(cart_id)
+            .execute(pool)
+            .await,
+        "Failed to remove item from cart"
+    )?;
+
+    if result.rows_affected() > 0 {
+        Ok(HttpResponse::Ok().json("Item removed from cart successfully"))
+    } else {
+        Ok(HttpResponse::NotFound().json("Cart item not found"))
+    }
+}
+
+async fn clear_items(pool: &PgPool, cart_id: i32) -> Result<HttpResponse> {
+    handle_db_error(
+        sqlx::query("DELETE FROM cart_items WHERE cart_id = $1")
+            .bind(cart_id)
+            .execute(pool)
+            .await,
+        "Failed to clear cart"
+    )?;
+
+    Ok(HttpResponse::Ok().json("Cart cleared successfully"))
+}
+
+async fn user_cart_id(pool: &PgPool, claims: &Claims) -> Result<i32> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    let mut conn = handle_db_error(pool.acquire().await, "Failed to get cart")?;
+    let cart = handle_db_error(carts::user_cart(&mut conn, user_id).await, "Failed to get cart")?;
+
+    Ok(cart.id)
+}
+
+// Existing guest cart for this request, if any
+async fn guest_cart_id(pool: &PgPool, req: &HttpRequest) -> Result<Option<i32>> {
+    let Some(guest_token) = request_cart_token(req) else {
+        return Ok(None);
+    };
+
+    let mut conn = handle_db_error(pool.acquire().await, "Failed to get cart")?;
+    let cart = handle_db_error(carts::find_guest_cart(&mut conn, guest_token).await, "Failed to get cart")?;
+
+    Ok(cart.map(|cart| cart.id))
+}
+
+#[get("/cart")]
+async fn get_cart(
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let cart_id = user_cart_id(pool.get_ref(), &claims).await?;
+    let cart_summary = cart_summary(pool.get_ref(), cart_id).await?;
+
+    Ok(HttpResponse::Ok().json(cart_summary))
+}
+
+#[post("/cart/items")]
+async fn add_to_cart(
+    pool: web::Data<PgPool>,
+    item_data: web::Json<AddToCartRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let cart_id = user_cart_id(pool.get_ref(), &claims).await?;
+    let mut conn = handle_db_error(pool.acquire().await, "Database error")?;
+    add_item(&mut conn, cart_id, &item_data).await
+}
+
+#[put("/cart/items/{id}")]
+async fn update_cart_item(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    item_data: web::Json<UpdateCartItemRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let cart_id = user_cart_id(pool.get_ref(), &claims).await?;
+    update_item(pool.get_ref(), cart_id, path.into_inner(), &item_data).await
+}
+
 #[delete("/cart/items/{id}")]
 async fn remove_from_cart(
     pool: web::Data<PgPool>,
     path: web::Path<i32>,
     claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let cart_id = user_cart_id(pool.get_ref(), &claims).await?;
+    remove_item(pool.get_ref(), cart_id, path.into_inner()).await
+}
+
+#[delete("/cart/clear")]
+async fn clear_cart(
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let cart_id = user_cart_id(pool.get_ref(), &claims).await?;
+    clear_items(pool.get_ref(), cart_id).await
+}
+
+#[post("/cart/accept-prices")]
+async fn accept_cart_prices(
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let cart_id = user_cart_id(pool.get_ref(), &claims).await?;
+    accept_prices(pool.get_ref(), cart_id).await
+}
+
+#[get("/cart/saved")]
+async fn get_saved_for_later(
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    let mut conn = handle_db_error(pool.acquire().await, "Failed to get saved items")?;
+    let saved = handle_db_error(saved_for_later_list(&mut conn, user_id).await, "Failed to get saved items")?;
+    drop(conn);
+
+    let items = handle_db_error(load_wishlist_items(pool.get_ref(), saved.id).await, "Failed to get saved items")?;
+    Ok(HttpResponse::Ok().json(items))
+}
+
+// Move a cart line to the saved-for-later list, keeping size, color and quantity
+#[post("/cart/items/{id}/save-for-later")]
+async fn save_for_later(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    claims: web::ReqData<Claims>,
 ) -> Result<impl Responder> {
     let user_id: i32 = claims.sub.parse().unwrap();
     let item_id = path.into_inner();
 
-    let result = handle_db_error(
-        sqlx::query!(
+    let moved = async {
+        let mut tx = pool.begin().await?;
+
+        let item = sqlx::query_as::<_, (i32, i32, Option<String>, Option<String>)>(
             "DELETE FROM cart_items ci
              USING carts c
-             WHERE ci.cart_id = c.id AND ci.id = $1 AND c.user_id = $2",
-            item_id,
-            user_id
+             WHERE ci.cart_id = c.id AND ci.id = $1 AND c.user_id = $2
+             RETURNING ci.product_id, ci.quantity, ci.size, ci.color"
         )
-        .execute(pool.get_ref())
+        .bind(item_id)
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some((product_id, quantity, size, color)) = item else {
+            return Ok(false);
+        };
+
+        let saved = saved_for_later_list(&mut tx, user_id).await?;
+        sqlx::query(
+            "INSERT INTO favorites (user_id, product_id, wishlist_id, size, color, quantity, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6, NOW())
+             ON CONFLICT (wishlist_id, product_id, COALESCE(size, ''), COALESCE(color, ''))
+                WHERE wishlist_id IS NOT NULL
+             DO UPDATE SET quantity = favorites.quantity + EXCLUDED.quantity"
+        )
+        .bind(user_id)
+        .bind(product_id)
+        .bind(saved.id)
+        .bind(&size)
+        .bind(&color)
+        .bind(quantity)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(true)
+    }
+    .await;
+
+    match handle_db_error(moved, "Failed to save item for later")? {
+        true => Ok(HttpResponse::Ok().json("Item saved for later")),
+        false => Ok(HttpResponse::NotFound().json("Cart item not found")),
+    }
+}
+
+// Put a saved-for-later line back into the cart, with the usual stock checks
+#[post("/cart/saved/{id}/move-to-cart")]
+async fn move_saved_to_cart(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+    let saved_id = path.into_inner();
+
+    let saved_item = handle_db_error(
+        sqlx::query_as::<_, (i32, i32, Option<String>, Option<String>)>(
+            "SELECT f.product_id, f.quantity, f.size, f.color
+             FROM favorites f
+             JOIN wishlists w ON w.id = f.wishlist_id
+             WHERE f.id = $1 AND w.user_id = $2 AND w.kind = 'saved_for_later'"
+        )
+        .bind(saved_id)
+        .bind(user_id)
+        .fetch_optional(pool.get_ref())
         .await,
-        "Failed to remove item from cart"
+        "Database error"
     )?;
 
-    if result.rows_affected() > 0 {
-        Ok(HttpResponse::Ok().json("Item removed from cart successfully"))
-    } else {
-        Ok(HttpResponse::NotFound().json("Cart item not found"))
+    let Some((product_id, quantity, size, color)) = saved_item else {
+        return Ok(HttpResponse::NotFound().json("Saved item not found"));
+    };
+
+    let cart_id = user_cart_id(pool.get_ref(), &claims).await?;
+    let request = AddToCartRequest { product_id, quantity, size, color };
+    let mut conn = handle_db_error(pool.acquire().await, "Database error")?;
+    let response = add_item(&mut conn, cart_id, &request).await?;
+    if !response.status().is_success() {
+        return Ok(response);
     }
+
+    handle_db_error(
+        sqlx::query("DELETE FROM favorites WHERE id = $1")
+            .bind(saved_id)
+            .execute(&mut *conn)
+            .await,
+        "Failed to update saved items"
+    )?;
+
+    Ok(HttpResponse::Ok().json("Item moved to cart"))
 }
 
-#[delete("/cart/clear")]
-async fn clear_cart(
+#[delete("/cart/saved/{id}")]
+async fn remove_saved_item(
     pool: web::Data<PgPool>,
+    path: web::Path<i32>,
     claims: web::ReqData<Claims>,
 ) -> Result<impl Responder> {
     let user_id: i32 = claims.sub.parse().unwrap();
 
-    handle_db_error(
-        sqlx::query!(
-            "DELETE FROM cart_items ci
-             USING carts c
-             WHERE ci.cart_id = c.id AND c.user_id = $1",
-            user_id
+    let result = handle_db_error(
+        sqlx::query(
+            "DELETE FROM favorites f
+             USING wishlists w
+             WHERE w.id = f.wishlist_id AND f.id = $1 AND w.user_id = $2 AND w.kind = 'saved_for_later'"
         )
+        .bind(path.into_inner())
+        .bind(user_id)
         .execute(pool.get_ref())
         .await,
-        "Failed to clear cart"
+        "Failed to remove saved item"
     )?;
 
-    Ok(HttpResponse::Ok().json("Cart cleared successfully"))
+    if result.rows_affected() > 0 {
+        Ok(HttpResponse::Ok().json("Saved item removed"))
+    } else {
+        Ok(HttpResponse::NotFound().json("Saved item not found"))
+    }
 }
 
-// Guest cart endpoint (no authentication required)
+// Guest cart endpoints (no authentication required). The cart is identified
+// by a signed token sent back as the cart_token cookie and X-Cart-Token header.
 #[get("/cart")]
-async fn get_guest_cart() -> Result<impl Responder> {
-    // Return empty cart for guest users
-    let cart_summary = CartSummary {
-        total_items: 0,
-        total_price: 0,
-        items: vec![],
+async fn get_guest_cart(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> Result<impl Responder> {
+    let cart_summary = match guest_cart_id(pool.get_ref(), &req).await? {
+        Some(cart_id) => cart_summary(pool.get_ref(), cart_id).await?,
+        None => empty_cart(),
     };
 
     Ok(HttpResponse::Ok().json(cart_summary))
 }
 
+#[post("/cart/items")]
+async fn add_to_guest_cart(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+    item_data: web::Json<AddToCartRequest>,
+) -> Result<impl Responder> {
+    // A cart is only created once something is added to it, so it is rolled
+    // back along with the item if that is refused
+    let existing_token = request_cart_token(&req);
+    let guest_token = existing_token.unwrap_or_else(Uuid::new_v4);
+
+    let mut tx = handle_db_error(pool.begin().await, "Failed to get cart")?;
+    let cart = handle_db_error(carts::guest_cart(&mut tx, guest_token).await, "Failed to get cart")?;
+
+    let mut response = add_item(&mut tx, cart.id, &item_data).await?;
+    if !response.status().is_success() {
+        return Ok(response);
+    }
+    handle_db_error(tx.commit().await, "Failed to add item to cart")?;
+
+    if existing_token.is_none() {
+        attach_cart_token(&mut response, guest_token)?;
+    }
+
+    Ok(response)
+}
+
+#[put("/cart/items/{id}")]
+async fn update_guest_cart_item(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+    item_data: web::Json<UpdateCartItemRequest>,
+) -> Result<impl Responder> {
+    match guest_cart_id(pool.get_ref(), &req).await? {
+        Some(cart_id) => update_item(pool.get_ref(), cart_id, path.into_inner(), &item_data).await,
+        None => Ok(HttpResponse::NotFound().json("Cart item not found")),
+    }
+}
+
+#[delete("/cart/items/{id}")]
+async fn remove_from_guest_cart(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+    path: web::Path<i32>,
+) -> Result<impl Responder> {
+    match guest_cart_id(pool.get_ref(), &req).await? {
+        Some(cart_id) => remove_item(pool.get_ref(), cart_id, path.into_inner()).await,
+        None => Ok(HttpResponse::NotFound().json("Cart item not found")),
+    }
+}
+
+#[delete("/cart/clear")]
+async fn clear_guest_cart(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> Result<impl Responder> {
+    match guest_cart_id(pool.get_ref(), &req).await? {
+        Some(cart_id) => clear_items(pool.get_ref(), cart_id).await,
+        None => Ok(HttpResponse::Ok().json("Cart cleared successfully")),
+    }
+}
+
+#[post("/cart/accept-prices")]
+async fn accept_guest_cart_prices(
+    pool: web::Data<PgPool>,
+    req: HttpRequest,
+) -> Result<impl Responder> {
+    match guest_cart_id(pool.get_ref(), &req).await? {
+        Some(cart_id) => accept_prices(pool.get_ref(), cart_id).await,
+        None => Ok(HttpResponse::Ok().json(empty_cart())),
+    }
+}
+
+// Public guest cart, mounted under /api
 pub fn init(cfg: &mut web::ServiceConfig) {
-    cfg
-        .service(get_cart)
+    cfg.service(get_guest_cart)
+        .service(add_to_guest_cart)
+        .service(update_guest_cart_item)
+        .service(remove_from_guest_cart)
+        .service(clear_guest_cart)
+        .service(accept_guest_cart_prices);
+}
+
+// Mounted inside the protected /api/auth scope
+pub fn user_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_cart)
         .service(add_to_cart)
         .service(update_cart_item)
         .service(remove_from_cart)
         .service(clear_cart)
-        .service(get_guest_cart); // Add guest cart endpoint without auth
-}
\ No newline at end of file
+        .service(accept_cart_prices)
+        .service(get_saved_for_later)
+        .service(save_for_later)
+        .service(move_saved_to_cart)
+        .service(remove_saved_item);
+}
@@ -0,0 +1,417 @@
+use actix_web::{get, post, put, delete, web, HttpResponse, Responder, Result};
+use sqlx::PgPool;
+use std::collections::HashMap;
+use crate::models::category::{Category, CategoryNode, CreateCategoryRequest, UpdateCategoryRequest};
+use crate::models::user::Claims;
+use crate::routes::product::slug_redirect;
+use crate::services::slugs;
+use crate::utils::slug::slugify;
+
+// Look a category up by id, or by slug / name for requests that still send
+// the old free-text category
+pub(crate) async fn resolve_category(
+    pool: &PgPool,
+    category_id: Option<i32>,
+    name: Option<&str>,
+) -> Result<Option<Category>, sqlx::Error> {
+    if let Some(category_id) = category_id {
+        return sqlx::query_as::<_, Category>("SELECT * FROM categories WHERE id = $1")
+            .bind(category_id)
+            .fetch_optional(pool)
+            .await;
+    }
+
+    match name.map(str::trim).filter(|n| !n.is_empty()) {
+        Some(name) => sqlx::query_as::<_, Category>(
+            "SELECT * FROM categories WHERE slug = $1 OR LOWER(name) = LOWER($2)
+             ORDER BY (slug = $1) DESC, id LIMIT 1"
+        )
+        .bind(slugify(name))
+        .bind(name)
+        .fetch_optional(pool)
+        .await,
+        None => Ok(None),
+    }
+}
+
+fn build_tree(
+    parent_id: Option<i32>,
+    by_parent: &mut HashMap<Option<i32>, Vec<Category>>,
+    counts: &HashMap<i32, i64>,
+) -> Vec<CategoryNode> {
+    let categories = by_parent.remove(&parent_id).unwrap_or_default();
+
+    categories
+        .into_iter()
+        .map(|category| {
+            let children = build_tree(Some(category.id), by_parent, counts);
+            let product_count = counts.get(&category.id).copied().unwrap_or(0)
+                + children.iter().map(|child| child.product_count).sum::<i64>();
+            CategoryNode { category, product_count, children }
+        })
+        .collect()
+}
+
+async fn load_category_tree(pool: &PgPool) -> Result<Vec<CategoryNode>, sqlx::Error> {
+    let categories = sqlx::query_as::<_, Category>(
+        "SELECT * FROM categories WHERE is_active = true ORDER BY sort_order, name"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let counts: HashMap<i32, i64> = sqlx::query_as::<_, (i32, i64)>(
+        "SELECT category_id, COUNT(*) FROM products
+         WHERE is_active = true AND category_id IS NOT NULL
+         GROUP BY category_id"
+    )
+    .fetch_all(pool)
+    .await?
+    .into_iter()
+    .collect();
+
+    let mut by_parent: HashMap<Option<i32>, Vec<Category>> = HashMap::new();
+    for category in categories {
+        by_parent.entry(category.parent_id).or_default().push(category);
+    }
+
+    // Children of inactive categories are left out along with their parent
+    Ok(build_tree(None, &mut by_parent, &counts))
+}
+
+fn find_node(nodes: Vec<CategoryNode>, slug: &str) -> Option<CategoryNode> {
+    for node in nodes {
+        if node.category.slug == slug {
+            return Some(node);
+        }
+        if let Some(found) = find_node(node.children, slug) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+#[get("/categories")]
+async fn get_category_tree(pool: web::Data<PgPool>) -> Result<impl Responder> {
+    match load_category_tree(pool.get_ref()).await {
+        Ok(tree) => Ok(HttpResponse::Ok().json(tree)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch categories"))
+        }
+    }
+}
+
+// One category with its subcategories
+#[get("/categories/{slug}")]
+async fn get_category_by_slug(
+    pool: web::Data<PgPool>,
+    path: web::Path<String>,
+) -> Result<impl Responder> {
+    let slug = path.into_inner();
+
+    let tree = match load_category_tree(pool.get_ref()).await {
+        Ok(tree) => tree,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Failed to fetch categories"));
+        }
+    };
+
+    if let Some(node) = find_node(tree, &slug) {
+        return Ok(HttpResponse::Ok().json(node));
+    }
+
+    let current_slug = async {
+        match slugs::find_redirect(pool.get_ref(), slugs::CATEGORY, &slug).await? {
+            Some(category_id) => sqlx::query_as::<_, (String,)>(
+                "SELECT slug FROM categories WHERE id = $1 AND is_active = true"
+            )
+            .bind(category_id)
+            .fetch_optional(pool.get_ref())
+            .await
+            .map(|row| row.map(|(slug,)| slug)),
+            None => Ok(None),
+        }
+    }
+    .await;
+
+    match current_slug {
+        Ok(Some(current_slug)) => Ok(slug_redirect("/api/categories", &current_slug)),
+        Ok(None) => Ok(HttpResponse::NotFound().json("Category not found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch category"))
+        }
+    }
+}
+
+// Admin routes
+#[get("/categories")]
+async fn get_all_categories(
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    match sqlx::query_as::<_, Category>("SELECT * FROM categories ORDER BY parent_id NULLS FIRST, sort_order, name")
+        .fetch_all(pool.get_ref())
+        .await {
+            Ok(categories) => Ok(HttpResponse::Ok().json(categories)),
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                Ok(HttpResponse::InternalServerError().json("Failed to fetch categories"))
+            }
+        }
+}
+
+async fn slug_taken(pool: &PgPool, slug: &str, except_id: Option<i32>) -> Result<bool, sqlx::Error> {
+    let existing = sqlx::query_as::<_, (i32,)>("SELECT id FROM categories WHERE slug = $1 AND id IS DISTINCT FROM $2")
+        .bind(slug)
+        .bind(except_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(existing.is_some())
+}
+
+#[post("/categories")]
+async fn create_category(
+    pool: web::Data<PgPool>,
+    category_data: web::Json<CreateCategoryRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let name = category_data.name.trim();
+    let slug = slugify(category_data.slug.as_deref().unwrap_or(name));
+    if name.is_empty() || slug.is_empty() {
+        return Ok(HttpResponse::BadRequest().json("Category name is required"));
+    }
+
+    match slug_taken(pool.get_ref(), &slug, None).await {
+        Ok(false) => {}
+        Ok(true) => return Ok(HttpResponse::BadRequest().json("A category with this slug already exists")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Database error"));
+        }
+    }
+
+    if let Some(parent_id) = category_data.parent_id {
+        match sqlx::query_as::<_, (i32,)>("SELECT id FROM categories WHERE id = $1")
+            .bind(parent_id)
+            .fetch_optional(pool.get_ref())
+            .await {
+                Ok(Some(_)) => {}
+                Ok(None) => return Ok(HttpResponse::BadRequest().json("Parent category not found")),
+                Err(e) => {
+                    eprintln!("Database error: {}", e);
+                    return Ok(HttpResponse::InternalServerError().json("Database error"));
+                }
+            }
+    }
+
+    match sqlx::query_as::<_, Category>(
+        "INSERT INTO categories (parent_id, name, slug, description, image_url, sort_order, is_active, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, NOW(), NOW())
+         RETURNING *"
+    )
+    .bind(category_data.parent_id)
+    .bind(name)
+    .bind(&slug)
+    .bind(&category_data.description)
+    .bind(&category_data.image_url)
+    .bind(category_data.sort_order.unwrap_or(0))
+    .bind(category_data.is_active.unwrap_or(true))
+    .fetch_one(pool.get_ref())
+    .await {
+        Ok(category) => Ok(HttpResponse::Created().json(category)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to create category"))
+        }
+    }
+}
+
+#[put("/categories/{id}")]
+async fn update_category(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    category_data: web::Json<UpdateCategoryRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let category_id = path.into_inner();
+
+    let name = category_data.name.as_deref().map(str::trim);
+    if name == Some("") {
+        return Ok(HttpResponse::BadRequest().json("Category name cannot be empty"));
+    }
+
+    let slug = category_data.slug.as_deref().map(slugify);
+    if let Some(slug) = &slug {
+        if slug.is_empty() {
+            return Ok(HttpResponse::BadRequest().json("Category slug cannot be empty"));
+        }
+        match slug_taken(pool.get_ref(), slug, Some(category_id)).await {
+            Ok(false) => {}
+            Ok(true) => return Ok(HttpResponse::BadRequest().json("A category with this slug already exists")),
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                return Ok(HttpResponse::InternalServerError().json("Database error"));
+            }
+        }
+    }
+
+    // A category cannot be moved below itself or one of its descendants
+    if let Some(Some(parent_id)) = category_data.parent_id {
+        let parent = sqlx::query_as::<_, (bool,)>(
+            "WITH RECURSIVE subtree AS (
+                 SELECT id FROM categories WHERE id = $1
+                 UNION ALL
+                 SELECT c.id FROM categories c JOIN subtree s ON c.parent_id = s.id
+             )
+             SELECT EXISTS (SELECT 1 FROM subtree WHERE id = $2) FROM categories WHERE id = $2"
+        )
+        .bind(category_id)
+        .bind(parent_id)
+        .fetch_optional(pool.get_ref())
+        .await;
+
+        match parent {
+            Ok(Some((false,))) => {}
+            Ok(Some((true,))) => return Ok(HttpResponse::BadRequest().json("A category cannot be its own ancestor")),
+            Ok(None) => return Ok(HttpResponse::BadRequest().json("Parent category not found")),
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                return Ok(HttpResponse::InternalServerError().json("Database error"));
+            }
+        }
+    }
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+
+        let old_slug = sqlx::query_as::<_, (String,)>("SELECT slug FROM categories WHERE id = $1 FOR UPDATE")
+            .bind(category_id)
+            .fetch_optional(&mut *tx)
+            .await?;
+        let old_slug = match old_slug {
+            Some((old_slug,)) => old_slug,
+            None => return Ok(None),
+        };
+
+        let category = sqlx::query_as::<_, Category>(
+            "UPDATE categories SET
+             name = COALESCE($1, name),
+             slug = COALESCE($2, slug),
+             parent_id = CASE WHEN $3 THEN $4 ELSE parent_id END,
+             description = COALESCE($5, description),
+             image_url = COALESCE($6, image_url),
+             sort_order = COALESCE($7, sort_order),
+             is_active = COALESCE($8, is_active),
+             updated_at = NOW()
+             WHERE id = $9
+             RETURNING *"
+        )
+        .bind(name)
+        .bind(&slug)
+        .bind(category_data.parent_id.is_some())
+        .bind(category_data.parent_id.flatten())
+        .bind(&category_data.description)
+        .bind(&category_data.image_url)
+        .bind(category_data.sort_order)
+        .bind(category_data.is_active)
+        .bind(category_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        // Keep the denormalized name on products in sync
+        if name.is_some() {
+            sqlx::query("UPDATE products SET category = $1, updated_at = NOW() WHERE category_id = $2")
+                .bind(&category.name)
+                .bind(category.id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        slugs::record_slug_change(&mut tx, slugs::CATEGORY, category.id, Some(&old_slug), &category.slug).await?;
+
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(Some(category))
+    }
+    .await;
+
+    match result {
+        Ok(Some(category)) => Ok(HttpResponse::Ok().json(category)),
+        Ok(None) => Ok(HttpResponse::NotFound().json("Category not found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to update category"))
+        }
+    }
+}
+
+#[delete("/categories/{id}")]
+async fn delete_category(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let category_id = path.into_inner();
+
+    let usage = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT (SELECT COUNT(*) FROM categories WHERE parent_id = $1),
+                (SELECT COUNT(*) FROM products WHERE category_id = $1)"
+    )
+    .bind(category_id)
+    .fetch_one(pool.get_ref())
+    .await;
+
+    match usage {
+        Ok((0, 0)) => {}
+        Ok((_, 0)) => {
+            return Ok(HttpResponse::BadRequest().json("Move or delete the subcategories first"));
+        }
+        Ok(_) => return Ok(HttpResponse::BadRequest().json("Category still has products")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Database error"));
+        }
+    }
+
+    match sqlx::query("DELETE FROM categories WHERE id = $1")
+        .bind(category_id)
+        .execute(pool.get_ref())
+        .await {
+            Ok(result) if result.rows_affected() > 0 => Ok(HttpResponse::Ok().json("Category deleted successfully")),
+            Ok(_) => Ok(HttpResponse::NotFound().json("Category not found")),
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                Ok(HttpResponse::InternalServerError().json("Failed to delete category"))
+            }
+        }
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_category_tree)
+        .service(get_category_by_slug);
+}
+
+// Mounted inside the /api/admin scope
+pub fn admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_all_categories)
+        .service(create_category)
+        .service(update_category)
+        .service(delete_category);
+}
@@ -1,19 +1,145 @@
-use actix_web::{get, post, put, web, HttpResponse, Responder, Result};
+use actix_web::{get, http::header, post, put, web, HttpResponse, Responder, Result};
 use sqlx::PgPool;
-use uuid::Uuid;
 use bigdecimal::{BigDecimal, Zero, ToPrimitive};
 use crate::models::order::*;
+use crate::models::shipping::Shipment;
 use crate::models::user::Claims;
-use crate::middleware::AuthMiddleware;
+use crate::services::{cancellation, carts, coupons, inventory, order_numbers, order_search, order_status, promotions, tax};
+use crate::services::order_search::OrderSearch;
+use crate::services::spreadsheet::SheetFormat;
+use crate::utils::response::PaginatedResponse;
+use crate::services::coupons::{CouponError, CouponLine};
+use crate::services::order_status::{Actor, TransitionError};
+use crate::services::shipping::{self, ShipmentRequest, ShippingProvider};
+
+#[derive(sqlx::FromRow)]
+struct CheckoutProduct {
+    price: BigDecimal,
+    stock: Option<i32>,
+    name: String,
+    image_url: Option<String>,
+    category_id: Option<i32>,
+    promotion_id: Option<i32>,
+    weight: Option<BigDecimal>,
+}
+
+// One line of the order being placed, taken from the request or the cart
+#[derive(sqlx::FromRow)]
+struct CheckoutLine {
+    cart_item_id: Option<i32>,
+    product_id: i32,
+    quantity: i32,
+    size: Option<String>,
+    color: Option<String>,
+    cart_price: Option<BigDecimal>,
+}
+
+impl CheckoutLine {
+    fn from_request(item: &OrderItemRequest) -> Self {
+        CheckoutLine {
+            cart_item_id: None,
+            product_id: item.product_id,
+            quantity: item.quantity,
+            size: item.size.clone(),
+            color: item.color.clone(),
+            cart_price: None,
+        }
+    }
+}
+
+// Weight (kg) and quantity of each line about to be ordered, read without
+// locks so shipping can be quoted before the checkout transaction starts
+async fn parcel_lines(
+    pool: &PgPool,
+    user_id: i32,
+    order_data: &CreateOrderRequest,
+) -> Result<Vec<(Option<BigDecimal>, i32)>, sqlx::Error> {
+    if order_data.from_cart {
+        return sqlx::query_as(
+            "SELECT p.weight, ci.quantity
+             FROM cart_items ci
+             JOIN carts c ON c.id = ci.cart_id
+             LEFT JOIN products p ON p.id = ci.product_id
+             WHERE c.user_id = $1 AND ($2::int[] IS NULL OR ci.id = ANY($2))"
+        )
+        .bind(user_id)
+        .bind(&order_data.cart_item_ids)
+        .fetch_all(pool)
+        .await;
+    }
+
+    let product_ids: Vec<i32> = order_data.items.iter().map(|item| item.product_id).collect();
+    let weights = sqlx::query_as::<_, (i32, Option<BigDecimal>)>("SELECT id, weight FROM products WHERE id = ANY($1)")
+        .bind(&product_ids)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(order_data
+        .items
+        .iter()
+        .map(|item| {
+            let weight = weights.iter().find(|(id, _)| *id == item.product_id).and_then(|(_, weight)| weight.clone());
+            (weight, item.quantity)
+        })
+        .collect())
+}
 
 #[post("/checkout")]
 async fn checkout(
     pool: web::Data<PgPool>,
+    shipping_provider: web::Data<dyn ShippingProvider>,
     order_data: web::Json<CreateOrderRequest>,
     claims: web::ReqData<Claims>,
 ) -> Result<impl Responder> {
     let user_id: i32 = claims.sub.parse().unwrap();
 
+    if order_data.from_cart && !order_data.items.is_empty() {
+        return Ok(HttpResponse::BadRequest().json("Send either items or from_cart, not both"));
+    }
+    if !order_data.from_cart && order_data.cart_item_ids.is_some() {
+        return Ok(HttpResponse::BadRequest().json("cart_item_ids requires from_cart"));
+    }
+
+    // Quote shipping for the parcel and use the chosen service, or the cheapest.
+    // The provider may be a remote API, so this happens before the transaction
+    // starts holding row locks.
+    let parcel_lines = match parcel_lines(pool.get_ref(), user_id, &order_data).await {
+        Ok(lines) => lines,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Database error"));
+        }
+    };
+    let Some(weight_grams) = shipping::parcel_weight_grams(parcel_lines.iter().map(|(weight, quantity)| (weight.as_ref(), *quantity))) else {
+        return Ok(HttpResponse::BadRequest().json("The order is too heavy to ship"));
+    };
+    let address = &order_data.shipping_address;
+    let parcel = ShipmentRequest {
+        province: address.province.clone(),
+        city: address.city.clone(),
+        postal_code: address.postal_code.clone(),
+        weight_grams,
+    };
+    let quotes = match shipping_provider.quote(&parcel).await {
+        Ok(quotes) => quotes,
+        Err(shipping::ShippingError::Unserviceable(message)) => return Ok(HttpResponse::BadRequest().json(message)),
+        #[cfg(feature = "rajaongkir")]
+        Err(e) => {
+            eprintln!("Shipping error: {}", e);
+            return Ok(HttpResponse::BadGateway().json("Shipping rates are unavailable, please try again"));
+        }
+    };
+    let shipping_quote = match (&order_data.shipping_courier, &order_data.shipping_service) {
+        (None, None) => quotes.into_iter().next(),
+        (courier, service) => quotes.into_iter().find(|quote| {
+            courier.as_ref().is_none_or(|c| c.eq_ignore_ascii_case(&quote.courier))
+                && service.as_ref().is_none_or(|s| s.eq_ignore_ascii_case(&quote.service))
+        }),
+    };
+    let Some(shipping_quote) = shipping_quote else {
+        return Ok(HttpResponse::BadRequest().json("The selected shipping service does not deliver to this address"));
+    };
+
     // Start transaction
     let mut tx = match pool.begin().await {
         Ok(tx) => tx,
@@ -23,107 +149,194 @@ async fn checkout(
         }
     };
 
-    // Calculate total amount
-    let mut total_amount = BigDecimal::zero();
-    let mut order_items = Vec::new();
-
-    for item in &order_data.items {
-        // Get product details and check stock
-        let product = match sqlx::query!(
-            "SELECT price, stock, name, image_url FROM products WHERE id = $1 AND is_active = true",
-            item.product_id
+    let lines = if order_data.from_cart {
+        // Build the order from the cart rows themselves, locked so the same
+        // lines can't be ordered twice by concurrent requests
+        let cart_lines = match sqlx::query_as::<_, CheckoutLine>(
+            "SELECT ci.id AS cart_item_id, ci.product_id, ci.quantity, ci.size, ci.color,
+                    ci.price_at_time AS cart_price
+             FROM cart_items ci
+             JOIN carts c ON c.id = ci.cart_id
+             WHERE c.user_id = $1 AND ($2::int[] IS NULL OR ci.id = ANY($2))
+             ORDER BY ci.id
+             FOR UPDATE OF ci"
         )
-        .fetch_optional(&mut *tx)
+        .bind(user_id)
+        .bind(&order_data.cart_item_ids)
+        .fetch_all(&mut *tx)
         .await {
-            Ok(Some(p)) => p,
-            Ok(None) => return Ok(HttpResponse::BadRequest().json(format!("Product {} not found", item.product_id))),
+            Ok(lines) => lines,
             Err(e) => {
                 eprintln!("Database error: {}", e);
-                return Ok(HttpResponse::InternalServerError().json("Database error"));
+                return Ok(HttpResponse::InternalServerError().json("Failed to fetch cart"));
             }
         };
 
-        if product.stock.unwrap_or(0) < item.quantity {
-            return Ok(HttpResponse::BadRequest().json(format!("Insufficient stock for product {}", item.product_id)));
+        if let Some(ids) = &order_data.cart_item_ids {
+            if let Some(missing) = ids.iter().find(|id| !cart_lines.iter().any(|line| line.cart_item_id == Some(**id))) {
+                return Ok(HttpResponse::BadRequest().json(format!("Cart item {} not found", missing)));
+            }
         }
 
-        let item_total = &product.price * BigDecimal::from(item.quantity);
-        total_amount += &item_total;
+        cart_lines
+    } else {
+        order_data.items.iter().map(CheckoutLine::from_request).collect()
+    };
 
-        order_items.push((item, product, item_total));
+    if lines.is_empty() {
+        return Ok(HttpResponse::BadRequest().json(if order_data.from_cart { "Cart is empty" } else { "No items to order" }));
+    }
+    if lines.iter().any(|line| line.quantity < 1) {
+        return Ok(HttpResponse::BadRequest().json("Quantity must be at least 1"));
+    }
+
+    // Calculate total amount
+    let mut total_amount = BigDecimal::zero();
+    let mut order_items = Vec::new();
+
+    // Lock the products before reading their stock
+    let product_ids: Vec<i32> = lines.iter().map(|line| line.product_id).collect();
+    if let Err(e) = inventory::lock_products(&mut tx, &product_ids).await {
+        eprintln!("Database error: {}", e);
+        return Ok(HttpResponse::InternalServerError().json("Database error"));
     }
 
-    // Apply coupon if provided
-    let mut discount_amount = BigDecimal::zero();
-    if let Some(coupon_code) = &order_data.coupon_code {
-        let coupon = match sqlx::query_as::<_, Coupon>(
-            "SELECT * FROM coupons WHERE code = $1 AND is_active = true 
-             AND valid_from <= NOW() AND valid_until >= NOW()"
+    for line in lines {
+        // Get product details, selling price and stock
+        let product = match sqlx::query_as::<_, CheckoutProduct>(
+            "SELECT pp.current_price AS price, p.stock, p.name, p.image_url, p.category_id, pp.promotion_id, p.weight
+             FROM products p
+             JOIN product_prices pp ON pp.product_id = p.id
+             WHERE p.id = $1 AND p.is_active = true"
         )
-        .bind(coupon_code)
+        .bind(line.product_id)
         .fetch_optional(&mut *tx)
         .await {
-            Ok(coupon) => coupon,
+            Ok(Some(p)) => p,
+            Ok(None) => return Ok(HttpResponse::BadRequest().json(format!("Product {} not found", line.product_id))),
             Err(e) => {
                 eprintln!("Database error: {}", e);
                 return Ok(HttpResponse::InternalServerError().json("Database error"));
             }
         };
 
-        if let Some(coupon) = coupon {
-            // Check usage limit
-            if let Some(limit) = coupon.usage_limit {
-                if coupon.used_count >= limit {
-                    return Ok(HttpResponse::BadRequest().json("Coupon usage limit exceeded"));
-                }
-            }
+        if product.stock.unwrap_or(0) < line.quantity {
+            return Ok(HttpResponse::BadRequest().json(format!("Insufficient stock for product {}", line.product_id)));
+        }
 
-            // Check minimum order amount
-            if let Some(min_amount) = coupon.min_order_amount {
-                if total_amount < BigDecimal::from(min_amount) {
-                    return Ok(HttpResponse::BadRequest().json("Order amount below minimum for coupon"));
-                }
-            }
+        // Cart lines are charged at the price the customer accepted in the cart
+        let unit_price = line.cart_price.clone().unwrap_or_else(|| product.price.clone());
+        let item_total = &unit_price * BigDecimal::from(line.quantity);
+        total_amount += &item_total;
 
-            // Calculate discount
-            if coupon.discount_type == "percentage" {
-                discount_amount = (&total_amount * BigDecimal::from(coupon.discount_value)) / BigDecimal::from(100);
-            } else {
-                discount_amount = BigDecimal::from(coupon.discount_value);
-            }
+        order_items.push((line, product, unit_price, item_total));
+    }
 
-            // Apply maximum discount limit
-            if let Some(max_discount) = coupon.max_discount_amount {
-                discount_amount = discount_amount.min(BigDecimal::from(max_discount));
-            }
+    // Refuse to charge a price the customer has not seen in their cart
+    let price_changes = match carts::price_changes(&mut tx, user_id).await {
+        Ok(changes) => changes,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Database error"));
+        }
+    };
+    let price_changes: Vec<_> = price_changes
+        .into_iter()
+        .filter(|change| {
+            order_items.iter().any(|(line, _, _, _)| match line.cart_item_id {
+                Some(cart_item_id) => cart_item_id == change.cart_item_id,
+                None => line.product_id == change.product_id && line.size == change.size && line.color == change.color,
+            })
+        })
+        .collect();
+    if !price_changes.is_empty() {
+        return Ok(HttpResponse::Conflict().json(serde_json::json!({
+            "error": "Prices changed since these items were added to the cart; accept the new prices to continue",
+            "price_changes": price_changes,
+        })));
+    }
 
-            // Update coupon usage
-            if let Err(e) = sqlx::query!(
-                "UPDATE coupons SET used_count = used_count + 1 WHERE id = $1",
-                coupon.id
-            )
-            .execute(&mut *tx)
-            .await {
-                eprintln!("Database error: {}", e);
-                return Ok(HttpResponse::InternalServerError().json("Database error"));
-            }
-        } else {
-            return Ok(HttpResponse::BadRequest().json("Invalid coupon code"));
+    // Apply coupons if provided
+    let coupon_codes = coupons::requested_codes(order_data.coupon_code.as_deref(), &order_data.coupon_codes);
+    let coupon_lines: Vec<CouponLine> = order_items
+        .iter()
+        .map(|(line, product, _, item_total)| CouponLine {
+            product_id: line.product_id,
+            category_id: product.category_id,
+            line_total: item_total.clone(),
+        })
+        .collect();
+    let applied_coupons = match coupons::apply_coupons(&mut tx, user_id, &coupon_codes, &coupon_lines, true).await {
+        Ok(applied) => applied,
+        Err(CouponError::Rejected(message)) => return Ok(HttpResponse::BadRequest().json(message)),
+        Err(CouponError::Database(e)) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Database error"));
         }
+    };
+    let discount_amount: BigDecimal = applied_coupons.iter().map(|coupon| &coupon.discount_amount).sum();
+
+    // The quote was for the parcel as it was before anything was locked;
+    // refuse it if the locked lines weigh something else
+    let weight_grams = shipping::parcel_weight_grams(
+        order_items.iter().map(|(line, product, _, _)| (product.weight.as_ref(), line.quantity)),
+    );
+    if weight_grams != Some(parcel.weight_grams) {
+        return Ok(HttpResponse::Conflict().json("The order changed while shipping was being quoted, please try again"));
     }
 
-    // Calculate shipping cost (simplified - you can implement more complex logic)
-    let shipping_cost = if total_amount >= BigDecimal::from(500000) { BigDecimal::zero() } else { BigDecimal::from(15000) };
-    let final_amount = &total_amount + &shipping_cost - &discount_amount;
+    let mut shipping_cost = shipping_quote.cost.clone();
+    let mut shipping_discount = BigDecimal::zero();
+    if applied_coupons.iter().any(|coupon| coupon.free_shipping) {
+        shipping_discount = std::mem::replace(&mut shipping_cost, BigDecimal::zero());
+    }
 
-    // Generate order number
-    let order_number = format!("BK-{}", Uuid::new_v4().to_string().split('-').next().unwrap().to_uppercase());
+    // Sales tax on what each line costs after its share of the discounts
+    let tax_rules = tax::TaxRules::from_env();
+    let product_ids: Vec<i32> = order_items.iter().map(|(line, _, _, _)| line.product_id).collect();
+    let exempt_products = match tax::exempt_products(&mut tx, &tax_rules, &product_ids).await {
+        Ok(exempt) => exempt,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Database error"));
+        }
+    };
+    let mut tax_amount = BigDecimal::zero();
+    let mut taxable_amount = BigDecimal::zero();
+    let mut line_taxes = Vec::new();
+    for (i, (line, _, _, item_total)) in order_items.iter().enumerate() {
+        let line_discount: BigDecimal = applied_coupons.iter().filter_map(|coupon| coupon.line_discounts.get(i)).sum();
+        let net = item_total - &line_discount;
+        let exempt = exempt_products.contains(&line.product_id);
+        let line_tax = tax_rules.line_tax(&net, exempt);
+        if !exempt {
+            taxable_amount += if tax_rules.prices_include_tax { &net - &line_tax } else { net };
+        }
+        tax_amount += &line_tax;
+        line_taxes.push((line_tax, exempt, line_discount));
+    }
+
+    let mut final_amount = &total_amount + &shipping_cost - &discount_amount;
+    if !tax_rules.prices_include_tax {
+        final_amount += &tax_amount;
+    }
+
+    // Take the next order number for today
+    let order_number = match order_numbers::next_order_number(&mut tx).await {
+        Ok(order_number) => order_number,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Failed to assign order number"));
+        }
+    };
 
     // Create order
     let order = match sqlx::query_as::<_, Order>(
         "INSERT INTO orders (user_id, order_number, status, total_amount, shipping_cost, discount_amount, 
-         final_amount, payment_method, payment_status, shipping_address, billing_address, notes, created_at, updated_at)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, NOW(), NOW())
+         final_amount, payment_method, payment_status, shipping_address, billing_address, notes,
+         shipping_courier, shipping_service, tax_amount, tax_rate, prices_include_tax, taxable_amount,
+         created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, NOW(), NOW())
          RETURNING *"
     )
     .bind(user_id)
@@ -131,13 +344,19 @@ async fn checkout(
     .bind(OrderStatus::Pending)
     .bind(total_amount)
     .bind(shipping_cost)
-    .bind(if discount_amount > BigDecimal::zero() { Some(&discount_amount) } else { None })
+    .bind(&discount_amount)
     .bind(final_amount)
     .bind(&order_data.payment_method)
     .bind(PaymentStatus::Pending)
     .bind(serde_json::to_value(&order_data.shipping_address).unwrap())
     .bind(serde_json::to_value(&order_data.billing_address).unwrap())
     .bind(&order_data.notes)
+    .bind(&shipping_quote.courier)
+    .bind(&shipping_quote.service)
+    .bind(&tax_amount)
+    .bind(&tax_rules.rate)
+    .bind(tax_rules.prices_include_tax)
+    .bind(&taxable_amount)
     .fetch_one(&mut *tx)
     .await {
         Ok(order) => order,
@@ -147,14 +366,27 @@ async fn checkout(
         }
     };
 
-    // Create order items and update stock
+    if let Err(e) = order_status::record_change(&mut tx, order.id, None, order.status, &Actor::customer(user_id), None).await {
+        eprintln!("Database error: {}", e);
+        return Ok(HttpResponse::InternalServerError().json("Failed to create order"));
+    }
+
+    if let Err(e) = coupons::record_redemptions(&mut tx, order.id, user_id, &applied_coupons, &shipping_discount).await {
+        eprintln!("Database error: {}", e);
+        return Ok(HttpResponse::InternalServerError().json("Failed to redeem coupon"));
+    }
+
+    // Create order items and reserve stock until the order is paid
+    let reserved_until = inventory::reservation_expiry();
     let mut created_items = Vec::new();
-    for (item_data, product, item_total) in order_items {
+    let mut purchased_lines = Vec::new();
+    for ((item_data, product, unit_price, item_total), (line_tax, tax_exempt, line_discount)) in order_items.into_iter().zip(line_taxes) {
         // Create order item
         let order_item = match sqlx::query_as::<_, OrderItem>(
-            "INSERT INTO order_items (order_id, product_id, product_name, product_image, quantity, 
-             size, color, price_at_time, total_price, created_at)
-             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, NOW())
+            "INSERT INTO order_items (order_id, product_id, product_name, product_image, quantity,
+             size, color, unit_price, price_at_time, total_price, promotion_id, tax_amount, tax_exempt,
+             discount_amount, created_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $8, $9, $10, $11, $12, $13, NOW())
              RETURNING *"
         )
         .bind(order.id)
@@ -164,8 +396,12 @@ async fn checkout(
         .bind(item_data.quantity)
         .bind(&item_data.size)
         .bind(&item_data.color)
-        .bind(product.price)
+        .bind(unit_price)
         .bind(item_total)
+        .bind(product.promotion_id)
+        .bind(line_tax)
+        .bind(tax_exempt)
+        .bind(line_discount)
         .fetch_one(&mut *tx)
         .await {
             Ok(item) => item,
@@ -175,27 +411,64 @@ async fn checkout(
             }
         };
 
-        // Update product stock
-        if let Err(e) = sqlx::query!(
-            "UPDATE products SET stock = stock - $1, sold_count = sold_count + $2 WHERE id = $3",
-            item_data.quantity,
-            item_data.quantity,
-            item_data.product_id
-        )
-        .execute(&mut *tx)
-        .await {
-            eprintln!("Database error: {}", e);
-            return Ok(HttpResponse::InternalServerError().json("Failed to update stock"));
+        // Conditional decrement: fails instead of going negative when the same
+        // product appears on several lines and their total exceeds the stock
+        match inventory::reserve_stock(&mut tx, order.id, item_data.product_id, item_data.quantity, reserved_until).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return Ok(HttpResponse::BadRequest().json(format!("Insufficient stock for product {}", item_data.product_id)));
+            }
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                return Ok(HttpResponse::InternalServerError().json("Failed to update stock"));
+            }
+        }
+
+        // Flash sales only sell as many units as their cap allows
+        if let Some(promotion_id) = product.promotion_id {
+            match promotions::claim_promotion_quantity(&mut tx, promotion_id, item_data.quantity).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    return Ok(HttpResponse::Conflict().json(format!(
+                        "Not enough units left at the sale price for product {}",
+                        item_data.product_id
+                    )));
+                }
+                Err(e) => {
+                    eprintln!("Database error: {}", e);
+                    return Ok(HttpResponse::InternalServerError().json("Failed to update promotion"));
+                }
+            }
         }
 
         created_items.push(order_item);
+        purchased_lines.push(item_data);
     }
 
-    // Clear user's cart
-    if let Err(e) = sqlx::query!(
-        "DELETE FROM cart_items ci USING carts c WHERE ci.cart_id = c.id AND c.user_id = $1",
-        user_id
+    // Remove only what was ordered from the cart: the selected cart lines, or
+    // the lines matching explicitly ordered items
+    let cart_item_ids: Vec<i32> = purchased_lines.iter().filter_map(|line| line.cart_item_id).collect();
+    let explicit_lines: Vec<&CheckoutLine> = purchased_lines.iter().filter(|line| line.cart_item_id.is_none()).collect();
+    let product_ids: Vec<i32> = explicit_lines.iter().map(|line| line.product_id).collect();
+    let sizes: Vec<Option<String>> = explicit_lines.iter().map(|line| line.size.clone()).collect();
+    let colors: Vec<Option<String>> = explicit_lines.iter().map(|line| line.color.clone()).collect();
+
+    if let Err(e) = sqlx::query(
+        "DELETE FROM cart_items ci
+         USING carts c
+         WHERE ci.cart_id = c.id AND c.user_id = $1
+           AND (ci.id = ANY($2) OR EXISTS (
+               SELECT 1 FROM UNNEST($3::int[], $4::text[], $5::text[]) AS o(product_id, size, color)
+               WHERE o.product_id = ci.product_id
+                 AND o.size IS NOT DISTINCT FROM ci.size
+                 AND o.color IS NOT DISTINCT FROM ci.color
+           ))"
     )
+    .bind(user_id)
+    .bind(&cart_item_ids)
+    .bind(&product_ids)
+    .bind(&sizes)
+    .bind(&colors)
     .execute(&mut *tx)
     .await {
         eprintln!("Database error: {}", e);
@@ -211,6 +484,8 @@ async fn checkout(
     let order_with_items = OrderWithItems {
         order,
         items: created_items,
+        shipments: Vec::new(),
+        status_history: Vec::new(),
     };
 
     Ok(HttpResponse::Created().json(order_with_items))
@@ -224,12 +499,12 @@ async fn get_user_orders(
     let user_id: i32 = claims.sub.parse().unwrap();
 
     let orders = match sqlx::query_as::<_, OrderSummary>(
-        "SELECT o.id, o.order_number, o.status, o.final_amount as total_amount, 
+        "SELECT o.id, o.order_number, o.status, o.final_amount as total_amount, o.tax_amount,
                 COUNT(oi.id) as item_count, o.created_at
          FROM orders o
          LEFT JOIN order_items oi ON o.id = oi.order_id
          WHERE o.user_id = $1
-         GROUP BY o.id, o.order_number, o.status, o.final_amount, o.created_at
+         GROUP BY o.id, o.order_number, o.status, o.final_amount, o.tax_amount, o.created_at
          ORDER BY o.created_at DESC"
     )
     .bind(user_id)
@@ -284,125 +559,309 @@ async fn get_order_details(
         }
     };
 
-    let order_with_items = OrderWithItems { order, items };
+    let shipments = match sqlx::query_as::<_, Shipment>(
+        "SELECT * FROM shipments WHERE order_id = $1 ORDER BY created_at, id"
+    )
+    .bind(order_id)
+    .fetch_all(pool.get_ref())
+    .await {
+        Ok(shipments) => shipments,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Failed to fetch shipments"));
+        }
+    };
+
+    let status_history = match sqlx::query_as::<_, OrderStatusChange>(
+        "SELECT * FROM order_status_history WHERE order_id = $1 ORDER BY created_at, id"
+    )
+    .bind(order_id)
+    .fetch_all(pool.get_ref())
+    .await {
+        Ok(history) => history,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Failed to fetch order history"));
+        }
+    };
+
+    let order_with_items = OrderWithItems { order, items, shipments, status_history };
 
     Ok(HttpResponse::Ok().json(order_with_items))
 }
 
-// Admin routes
-#[get("/admin/orders")]
-async fn get_all_orders(
+// Customers can cancel until the order is being packed. A paid order gets
+// a pending refund for the full amount.
+#[post("/orders/{id}/cancel")]
+async fn cancel_order(
     pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    request: Option<web::Json<CancelOrderRequest>>,
     claims: web::ReqData<Claims>,
 ) -> Result<impl Responder> {
-    if claims.role != "admin" {
-        return Ok(HttpResponse::Forbidden().json("Admin access required"));
-    }
+    let user_id: i32 = claims.sub.parse().unwrap();
+    let order_id = path.into_inner();
+    let reason = request.as_ref().and_then(|r| r.reason.clone());
 
-    let orders = match sqlx::query!(
-        "SELECT o.id, o.order_number, o.status, o.final_amount, o.created_at,
-                u.name as user_name, u.email as user_email,
-                COUNT(oi.id) as item_count
-         FROM orders o
-         JOIN users u ON o.user_id = u.id
-         LEFT JOIN order_items oi ON o.id = oi.order_id
-         GROUP BY o.id, o.order_number, o.status, o.final_amount, o.created_at, u.name, u.email
-         ORDER BY o.created_at DESC"
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Failed to start transaction"));
+        }
+    };
+
+    let order = match sqlx::query_as::<_, Order>(
+        "SELECT * FROM orders WHERE id = $1 AND user_id = $2 FOR UPDATE"
     )
-    .fetch_all(pool.get_ref())
+    .bind(order_id)
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
     .await {
-        Ok(orders) => orders,
+        Ok(Some(order)) => order,
+        Ok(None) => return Ok(HttpResponse::NotFound().json("Order not found")),
         Err(e) => {
             eprintln!("Database error: {}", e);
-            return Ok(HttpResponse::InternalServerError().json("Failed to fetch orders"));
+            return Ok(HttpResponse::InternalServerError().json("Database error"));
         }
     };
 
-    // Convert to serializable format
-    let serializable_orders: Vec<_> = orders.into_iter().map(|order| {
-        serde_json::json!({
-            "id": order.id,
-            "order_number": order.order_number,
-            "status": order.status,
-            "final_amount": order.final_amount,
-            "created_at": order.created_at,
-            "user_name": order.user_name,
-            "user_email": order.user_email,
-            "item_count": order.item_count.unwrap_or(0)
-        })
-    }).collect();
+    if order.status == OrderStatus::Cancelled {
+        return Ok(HttpResponse::Conflict().json("Order is already cancelled"));
+    }
+    if !cancellation::CUSTOMER_CANCELLABLE.contains(&order.status) {
+        return Ok(HttpResponse::Conflict().json(format!(
+            "A {} order can no longer be cancelled, please contact us",
+            order.status.as_str()
+        )));
+    }
+
+    let (order, refund) = match cancellation::cancel_order(&mut tx, &order, &Actor::customer(user_id), reason.as_deref()).await {
+        Ok(cancelled) => cancelled,
+        Err(e @ TransitionError::NotAllowed { .. }) => return Ok(HttpResponse::Conflict().json(e.to_string())),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Failed to cancel order"));
+        }
+    };
 
-    Ok(HttpResponse::Ok().json(serializable_orders))
+    if let Err(e) = tx.commit().await {
+        eprintln!("Database error: {}", e);
+        return Ok(HttpResponse::InternalServerError().json("Failed to commit transaction"));
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "order": order,
+        "refund": refund,
+    })))
 }
 
-#[put("/admin/orders/{id}/status")]
-async fn update_order_status(
+// Admin routes
+#[get("/orders")]
+async fn get_all_orders(
     pool: web::Data<PgPool>,
-    path: web::Path<i32>,
-    status_data: web::Json<UpdateOrderStatusRequest>,
+    query: web::Query<AdminOrderQuery>,
     claims: web::ReqData<Claims>,
 ) -> Result<impl Responder> {
     if claims.role != "admin" {
         return Ok(HttpResponse::Forbidden().json("Admin access required"));
     }
 
-    let order_id = path.into_inner();
-
-    let mut update_query = "UPDATE orders SET status = $1, updated_at = NOW()".to_string();
-    let mut bind_count = 2;
+    let search = match OrderSearch::from_query(&query) {
+        Ok(search) => search,
+        Err(message) => return Ok(HttpResponse::BadRequest().json(message)),
+    };
+    let (page, per_page) = match order_search::page(&query) {
+        Ok(page) => page,
+        Err(message) => return Ok(HttpResponse::BadRequest().json(message)),
+    };
 
-    // Add shipped_at or delivered_at based on status
-    match status_data.status {
-        OrderStatus::Shipped => {
-            update_query.push_str(", shipped_at = NOW()");
+    let result = async {
+        let count_sql = format!(
+            "SELECT COUNT(*) FROM orders o JOIN users u ON u.id = o.user_id WHERE {}",
+            search.where_sql
+        );
+        let mut count_query = sqlx::query_scalar::<_, i64>(&count_sql);
+        for param in &search.params {
+            count_query = count_query.bind(param);
         }
-        OrderStatus::Delivered => {
-            update_query.push_str(", delivered_at = NOW()");
+        let total = count_query.fetch_one(pool.get_ref()).await?;
+
+        let list_sql = format!(
+            "SELECT o.id, o.order_number, o.status, o.payment_status, o.final_amount, o.created_at,
+                    u.name AS user_name, u.email AS user_email,
+                    (SELECT COUNT(*) FROM order_items oi WHERE oi.order_id = o.id) AS item_count
+             FROM orders o
+             JOIN users u ON u.id = o.user_id
+             WHERE {}
+             ORDER BY {}
+             LIMIT {} OFFSET {}",
+            search.where_sql,
+            search.order_by,
+            per_page,
+            (page - 1) * per_page
+        );
+        let mut list_query = sqlx::query_as::<_, AdminOrderSummary>(&list_sql);
+        for param in &search.params {
+            list_query = list_query.bind(param);
         }
-        _ => {}
+        let orders = list_query.fetch_all(pool.get_ref()).await?;
+
+        Ok::<_, sqlx::Error>((orders, total))
+    }
+    .await;
+
+    match result {
+        Ok((orders, total)) => Ok(HttpResponse::Ok().json(PaginatedResponse::new(orders, page as i32, per_page as i32, total))),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch orders"))
+        }
+    }
+}
+
+// The orders matching the same filters as the list, with their items, as
+// CSV (default) or XLSX
+#[get("/orders/export")]
+async fn export_orders(
+    pool: web::Data<PgPool>,
+    query: web::Query<AdminOrderQuery>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
     }
 
-    if status_data.notes.is_some() {
-        update_query.push_str(&format!(", notes = ${}", bind_count));
-        bind_count += 1;
+    let format = match SheetFormat::parse(query.format.as_deref().unwrap_or("csv")) {
+        Some(format) => format,
+        None => return Ok(HttpResponse::BadRequest().json("Invalid format, expected csv or xlsx")),
+    };
+    let search = match OrderSearch::from_query(&query) {
+        Ok(search) => search,
+        Err(message) => return Ok(HttpResponse::BadRequest().json(message)),
+    };
+
+    let filename = format!("orders-{}.{}", chrono::Utc::now().format("%Y%m%d-%H%M%S"), format.extension());
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)))
+        .streaming(order_search::export(pool.get_ref().clone(), search, format)))
+}
+
+// Look an order up by the number a customer reads out
+#[get("/orders/by-number/{order_number}")]
+async fn get_order_by_number(
+    pool: web::Data<PgPool>,
+    path: web::Path<String>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
     }
 
-    update_query.push_str(&format!(" WHERE id = ${} RETURNING *", bind_count));
+    let order_number = path.into_inner().trim().to_uppercase();
+    let order = match sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE order_number = $1")
+        .bind(&order_number)
+        .fetch_optional(pool.get_ref())
+        .await
+    {
+        Ok(order) => order,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Failed to fetch order"));
+        }
+    };
 
-    let mut query = sqlx::query_as::<_, Order>(&update_query)
-        .bind(&status_data.status);
+    match order {
+        Some(order) => Ok(HttpResponse::Ok().json(order)),
+        // Older orders predate the check digit, so it only explains a miss
+        None if !order_numbers::is_valid_order_number(&order_number) => Ok(HttpResponse::NotFound()
+            .json("Order not found; the check digit doesn't match, so the number was probably misread")),
+        None => Ok(HttpResponse::NotFound().json("Order not found")),
+    }
+}
 
-    if let Some(notes) = &status_data.notes {
-        query = query.bind(notes);
+#[put("/orders/{id}/status")]
+async fn update_order_status(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    status_data: web::Json<UpdateOrderStatusRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
     }
 
-    let order = match query
+    let admin_id: i32 = claims.sub.parse().unwrap();
+    let order_id = path.into_inner();
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Failed to start transaction"));
+        }
+    };
+
+    let current = match sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1 FOR UPDATE")
         .bind(order_id)
-        .fetch_optional(pool.get_ref())
+        .fetch_optional(&mut *tx)
         .await {
             Ok(Some(order)) => order,
             Ok(None) => return Ok(HttpResponse::NotFound().json("Order not found")),
             Err(e) => {
                 eprintln!("Database error: {}", e);
-                return Ok(HttpResponse::InternalServerError().json("Failed to update order status"));
+                return Ok(HttpResponse::InternalServerError().json("Database error"));
             }
         };
 
+    let actor = Actor::admin(admin_id);
+    let notes = status_data.notes.as_deref();
+
+    // Cancelling returns stock, coupons and flash-sale units; any other
+    // move settles the stock held for the order
+    let result = match status_data.status {
+        OrderStatus::Cancelled => cancellation::cancel_order(&mut tx, &current, &actor, notes)
+            .await
+            .map(|(order, _)| order),
+        status => match order_status::transition(&mut tx, &current, status, &actor, notes).await {
+            Ok(order) => inventory::consume_reservations(&mut tx, order_id)
+                .await
+                .map(|_| order)
+                .map_err(TransitionError::from),
+            Err(e) => Err(e),
+        },
+    };
+
+    let order = match result {
+        Ok(order) => order,
+        Err(e @ TransitionError::NotAllowed { .. }) => return Ok(HttpResponse::Conflict().json(e.to_string())),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Failed to update order status"));
+        }
+    };
+
+    if let Err(e) = tx.commit().await {
+        eprintln!("Database error: {}", e);
+        return Ok(HttpResponse::InternalServerError().json("Failed to commit transaction"));
+    }
+
     Ok(HttpResponse::Ok().json(order))
 }
 
+// Mounted inside the protected /api/auth scope
 pub fn init(cfg: &mut web::ServiceConfig) {
-    cfg.service(
-        web::scope("/auth")
-            .wrap(AuthMiddleware)
-            .service(checkout)
-            .service(get_user_orders)
-            .service(get_order_details)
-    )
-    .service(
-        web::scope("/admin")
-            .wrap(AuthMiddleware)
-            .service(get_all_orders)
-            .service(update_order_status)
-    );
+    cfg.service(checkout)
+        .service(get_user_orders)
+        .service(get_order_details)
+        .service(cancel_order);
+}
+
+// Mounted inside the /api/admin scope
+pub fn admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_all_orders)
+        .service(export_orders)
+        .service(get_order_by_number)
+        .service(update_order_status);
 }
\ No newline at end of file
@@ -0,0 +1,433 @@
+use actix_web::{get, post, put, delete, web, HttpResponse, Responder, Result};
+use sqlx::PgPool;
+use bigdecimal::{BigDecimal, Zero};
+use chrono::NaiveDateTime;
+use crate::models::coupon::*;
+use crate::models::user::Claims;
+use crate::services::coupons::{self, CouponError, CouponLine};
+
+const MAX_CODE_LENGTH: usize = 50;
+
+fn normalize_code(code: &str) -> Result<String, String> {
+    let code = code.trim().to_uppercase();
+    if code.len() < 3 || code.len() > MAX_CODE_LENGTH {
+        return Err(format!("Coupon code must be 3 to {} characters", MAX_CODE_LENGTH));
+    }
+    if !code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err("Coupon code may only contain letters, digits, '-' and '_'".to_string());
+    }
+    Ok(code)
+}
+
+// Checks a coupon's settings once create/update values have been merged
+#[allow(clippy::too_many_arguments)]
+fn validate_coupon(
+    discount_type: &str,
+    discount_value: &BigDecimal,
+    min_order_amount: Option<&BigDecimal>,
+    max_discount_amount: Option<&BigDecimal>,
+    usage_limit: Option<i32>,
+    per_user_limit: Option<i32>,
+    valid_from: Option<NaiveDateTime>,
+    valid_until: Option<NaiveDateTime>,
+) -> Result<(), String> {
+    if !DISCOUNT_TYPES.contains(&discount_type) {
+        return Err(format!("discount_type must be one of: {}", DISCOUNT_TYPES.join(", ")));
+    }
+    if discount_type != FREE_SHIPPING && discount_value <= &BigDecimal::zero() {
+        return Err("discount_value must be greater than 0".to_string());
+    }
+    if discount_type == PERCENTAGE && discount_value > &BigDecimal::from(100) {
+        return Err("A percentage discount cannot exceed 100".to_string());
+    }
+    if min_order_amount.is_some_and(|amount| amount < &BigDecimal::zero())
+        || max_discount_amount.is_some_and(|amount| amount <= &BigDecimal::zero())
+    {
+        return Err("Minimum order and maximum discount amounts must be positive".to_string());
+    }
+    if usage_limit.is_some_and(|limit| limit < 1) || per_user_limit.is_some_and(|limit| limit < 1) {
+        return Err("Usage limits must be at least 1".to_string());
+    }
+    if let (Some(from), Some(until)) = (valid_from, valid_until) {
+        if until <= from {
+            return Err("valid_until must be after valid_from".to_string());
+        }
+    }
+    Ok(())
+}
+
+// Coupon lines for the items being previewed, or the user's cart at the
+// prices it holds
+async fn validation_lines(pool: &PgPool, user_id: i32, items: &[crate::models::order::OrderItemRequest]) -> Result<Vec<CouponLine>, sqlx::Error> {
+    let rows = if items.is_empty() {
+        sqlx::query_as::<_, (i32, Option<i32>, BigDecimal)>(
+            "SELECT ci.product_id, p.category_id, ci.price_at_time * ci.quantity
+             FROM cart_items ci
+             JOIN carts c ON c.id = ci.cart_id
+             JOIN products p ON p.id = ci.product_id
+             WHERE c.user_id = $1 AND p.is_active = true
+             ORDER BY ci.id"
+        )
+        .bind(user_id)
+        .fetch_all(pool)
+        .await?
+    } else {
+        let product_ids: Vec<i32> = items.iter().map(|item| item.product_id).collect();
+        let quantities: Vec<i32> = items.iter().map(|item| item.quantity.max(0)).collect();
+        sqlx::query_as::<_, (i32, Option<i32>, BigDecimal)>(
+            "SELECT p.id, p.category_id, pp.current_price * i.quantity
+             FROM UNNEST($1::int[], $2::int[]) AS i(product_id, quantity)
+             JOIN products p ON p.id = i.product_id
+             JOIN product_prices pp ON pp.product_id = p.id
+             WHERE p.is_active = true"
+        )
+        .bind(&product_ids)
+        .bind(&quantities)
+        .fetch_all(pool)
+        .await?
+    };
+
+    Ok(rows
+        .into_iter()
+        .map(|(product_id, category_id, line_total)| CouponLine { product_id, category_id, line_total })
+        .collect())
+}
+
+// Preview the discount the codes would give without redeeming them
+#[post("/coupons/validate")]
+async fn validate_coupons(
+    pool: web::Data<PgPool>,
+    request: web::Json<ValidateCouponRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    let codes = coupons::requested_codes(request.coupon_code.as_deref(), &request.coupon_codes);
+    if codes.is_empty() {
+        return Ok(HttpResponse::BadRequest().json("coupon_code is required"));
+    }
+
+    let lines = match validation_lines(pool.get_ref(), user_id, &request.items).await {
+        Ok(lines) => lines,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Database error"));
+        }
+    };
+    if lines.is_empty() {
+        return Ok(HttpResponse::BadRequest().json("No items to apply the coupon to"));
+    }
+
+    let mut conn = match pool.acquire().await {
+        Ok(conn) => conn,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Database error"));
+        }
+    };
+
+    match coupons::apply_coupons(&mut conn, user_id, &codes, &lines, false).await {
+        Ok(applied) => Ok(HttpResponse::Ok().json(CouponValidation {
+            subtotal: lines.iter().map(|line| &line.line_total).sum(),
+            discount_amount: applied.iter().map(|coupon| &coupon.discount_amount).sum(),
+            free_shipping: applied.iter().any(|coupon| coupon.free_shipping),
+            coupons: applied,
+        })),
+        Err(CouponError::Rejected(message)) => Ok(HttpResponse::BadRequest().json(message)),
+        Err(CouponError::Database(e)) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Database error"))
+        }
+    }
+}
+
+#[get("/coupons")]
+async fn get_coupons(
+    pool: web::Data<PgPool>,
+    query: web::Query<CouponListQuery>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+    let search = query.search.as_ref().map(|s| format!("%{}%", s.trim()));
+
+    match sqlx::query_as::<_, Coupon>(
+        "SELECT * FROM coupons
+         WHERE ($1::boolean IS NULL OR is_active = $1)
+           AND ($2::text IS NULL OR code ILIKE $2 OR description ILIKE $2)
+         ORDER BY created_at DESC, id DESC
+         LIMIT $3 OFFSET $4"
+    )
+    .bind(query.is_active)
+    .bind(search)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool.get_ref())
+    .await {
+        Ok(coupons) => Ok(HttpResponse::Ok().json(coupons)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch coupons"))
+        }
+    }
+}
+
+#[get("/coupons/{id}")]
+async fn get_coupon(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let coupon_id = path.into_inner();
+
+    let coupon = match sqlx::query_as::<_, Coupon>("SELECT * FROM coupons WHERE id = $1")
+        .bind(coupon_id)
+        .fetch_optional(pool.get_ref())
+        .await {
+            Ok(Some(coupon)) => coupon,
+            Ok(None) => return Ok(HttpResponse::NotFound().json("Coupon not found")),
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                return Ok(HttpResponse::InternalServerError().json("Failed to fetch coupon"));
+            }
+        };
+
+    let redemptions = match sqlx::query_as::<_, CouponRedemption>(
+        "SELECT * FROM coupon_redemptions WHERE coupon_id = $1 ORDER BY created_at DESC, id DESC LIMIT 100"
+    )
+    .bind(coupon_id)
+    .fetch_all(pool.get_ref())
+    .await {
+        Ok(redemptions) => redemptions,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Failed to fetch coupon redemptions"));
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "coupon": coupon,
+        "redemptions": redemptions,
+    })))
+}
+
+#[post("/coupons")]
+async fn create_coupon(
+    pool: web::Data<PgPool>,
+    coupon_data: web::Json<CreateCouponRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let code = match normalize_code(&coupon_data.code) {
+        Ok(code) => code,
+        Err(message) => return Ok(HttpResponse::BadRequest().json(message)),
+    };
+    let discount_type = coupon_data.discount_type.trim().to_lowercase();
+    let discount_value = if discount_type == FREE_SHIPPING { BigDecimal::zero() } else { coupon_data.discount_value.clone() };
+
+    if let Err(message) = validate_coupon(
+        &discount_type,
+        &discount_value,
+        coupon_data.min_order_amount.as_ref(),
+        coupon_data.max_discount_amount.as_ref(),
+        coupon_data.usage_limit,
+        coupon_data.per_user_limit,
+        coupon_data.valid_from,
+        coupon_data.valid_until,
+    ) {
+        return Ok(HttpResponse::BadRequest().json(message));
+    }
+
+    match sqlx::query_as::<_, Coupon>(
+        "INSERT INTO coupons (code, description, discount_type, discount_value, min_order_amount, max_discount_amount,
+         usage_limit, per_user_limit, valid_from, valid_until, product_ids, category_ids, first_order_only, is_active,
+         created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, NOW(), NOW())
+         ON CONFLICT (code) DO NOTHING
+         RETURNING *"
+    )
+    .bind(&code)
+    .bind(&coupon_data.description)
+    .bind(&discount_type)
+    .bind(&discount_value)
+    .bind(&coupon_data.min_order_amount)
+    .bind(&coupon_data.max_discount_amount)
+    .bind(coupon_data.usage_limit)
+    .bind(coupon_data.per_user_limit)
+    .bind(coupon_data.valid_from)
+    .bind(coupon_data.valid_until)
+    .bind(&coupon_data.product_ids)
+    .bind(&coupon_data.category_ids)
+    .bind(coupon_data.first_order_only)
+    .bind(coupon_data.is_active.unwrap_or(true))
+    .fetch_optional(pool.get_ref())
+    .await {
+        Ok(Some(coupon)) => Ok(HttpResponse::Created().json(coupon)),
+        Ok(None) => Ok(HttpResponse::Conflict().json(format!("Coupon code {} already exists", code))),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to create coupon"))
+        }
+    }
+}
+
+#[put("/coupons/{id}")]
+async fn update_coupon(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    coupon_data: web::Json<UpdateCouponRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let coupon_id = path.into_inner();
+
+    let existing = match sqlx::query_as::<_, Coupon>("SELECT * FROM coupons WHERE id = $1")
+        .bind(coupon_id)
+        .fetch_optional(pool.get_ref())
+        .await {
+            Ok(Some(coupon)) => coupon,
+            Ok(None) => return Ok(HttpResponse::NotFound().json("Coupon not found")),
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                return Ok(HttpResponse::InternalServerError().json("Failed to fetch coupon"));
+            }
+        };
+
+    // Validate the coupon as it will be after the update
+    let discount_type = coupon_data.discount_type.as_ref().map(|t| t.trim().to_lowercase()).unwrap_or(existing.discount_type);
+    let discount_value = if discount_type == FREE_SHIPPING {
+        BigDecimal::zero()
+    } else {
+        coupon_data.discount_value.clone().unwrap_or(existing.discount_value)
+    };
+    if let Err(message) = validate_coupon(
+        &discount_type,
+        &discount_value,
+        coupon_data.min_order_amount.as_ref().or(existing.min_order_amount.as_ref()),
+        coupon_data.max_discount_amount.as_ref().or(existing.max_discount_amount.as_ref()),
+        coupon_data.usage_limit.or(existing.usage_limit),
+        coupon_data.per_user_limit.or(existing.per_user_limit),
+        coupon_data.valid_from.or(existing.valid_from),
+        coupon_data.valid_until.or(existing.valid_until),
+    ) {
+        return Ok(HttpResponse::BadRequest().json(message));
+    }
+
+    match sqlx::query_as::<_, Coupon>(
+        "UPDATE coupons SET
+         description = COALESCE($1, description),
+         discount_type = $2,
+         discount_value = $3,
+         min_order_amount = COALESCE($4, min_order_amount),
+         max_discount_amount = COALESCE($5, max_discount_amount),
+         usage_limit = COALESCE($6, usage_limit),
+         per_user_limit = COALESCE($7, per_user_limit),
+         valid_from = COALESCE($8, valid_from),
+         valid_until = COALESCE($9, valid_until),
+         product_ids = COALESCE($10, product_ids),
+         category_ids = COALESCE($11, category_ids),
+         first_order_only = COALESCE($12, first_order_only),
+         is_active = COALESCE($13, is_active),
+         updated_at = NOW()
+         WHERE id = $14
+         RETURNING *"
+    )
+    .bind(&coupon_data.description)
+    .bind(&discount_type)
+    .bind(&discount_value)
+    .bind(&coupon_data.min_order_amount)
+    .bind(&coupon_data.max_discount_amount)
+    .bind(coupon_data.usage_limit)
+    .bind(coupon_data.per_user_limit)
+    .bind(coupon_data.valid_from)
+    .bind(coupon_data.valid_until)
+    .bind(&coupon_data.product_ids)
+    .bind(&coupon_data.category_ids)
+    .bind(coupon_data.first_order_only)
+    .bind(coupon_data.is_active)
+    .bind(coupon_id)
+    .fetch_one(pool.get_ref())
+    .await {
+        Ok(coupon) => Ok(HttpResponse::Ok().json(coupon)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to update coupon"))
+        }
+    }
+}
+
+// Coupons that were already redeemed are deactivated instead of deleted so
+// the redemption history stays intact
+#[delete("/coupons/{id}")]
+async fn delete_coupon(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let coupon_id = path.into_inner();
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+
+        let deleted = sqlx::query(
+            "DELETE FROM coupons
+             WHERE id = $1 AND NOT EXISTS (SELECT 1 FROM coupon_redemptions WHERE coupon_id = $1)"
+        )
+        .bind(coupon_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let outcome = if deleted.rows_affected() > 0 {
+            Some("Coupon deleted")
+        } else {
+            let deactivated = sqlx::query("UPDATE coupons SET is_active = false, updated_at = NOW() WHERE id = $1")
+                .bind(coupon_id)
+                .execute(&mut *tx)
+                .await?;
+            (deactivated.rows_affected() > 0).then_some("Coupon has been redeemed and was deactivated instead")
+        };
+
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(outcome)
+    }
+    .await;
+
+    match result {
+        Ok(Some(message)) => Ok(HttpResponse::Ok().json(message)),
+        Ok(None) => Ok(HttpResponse::NotFound().json("Coupon not found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to delete coupon"))
+        }
+    }
+}
+
+pub fn user_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(validate_coupons);
+}
+
+pub fn admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_coupons)
+        .service(get_coupon)
+        .service(create_coupon)
+        .service(update_coupon)
+        .service(delete_coupon);
+}
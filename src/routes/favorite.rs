@@ -1,10 +1,26 @@
-use actix_web::{get, post, delete, web, HttpResponse, Responder, Result};
+use actix_web::{get, post, put, delete, web, HttpResponse, Responder, Result};
 use sqlx::PgPool;
 use bigdecimal::BigDecimal;
-use crate::models::product::Favorite;
+use crate::models::product::{Favorite, FavoriteNoteRequest};
+use crate::routes::wishlist::clean_note;
 use crate::models::user::Claims;
 use crate::middleware::AuthMiddleware;
 
+#[derive(sqlx::FromRow)]
+struct FavoriteProductRow {
+    id: i32,
+    product_id: i32,
+    note: Option<String>,
+    created_at: Option<chrono::NaiveDateTime>,
+    name: String,
+    image_url: Option<String>,
+    price: BigDecimal,
+    original_price: Option<BigDecimal>,
+    average_rating: BigDecimal,
+    review_count: i32,
+    rating_histogram: Vec<i32>,
+}
+
 #[get("")]
 async fn get_favorites(
     pool: web::Data<PgPool>,
@@ -12,17 +28,16 @@ async fn get_favorites(
 ) -> Result<impl Responder> {
     let user_id: i32 = claims.sub.parse().unwrap();
 
-    let favorites = match sqlx::query!(
-        "SELECT f.id, f.product_id, f.created_at, p.name, p.image_url, p.price, p.original_price, 
-                COALESCE(AVG(r.rating), 0) as rating, COUNT(r.id) as reviews_count
+    let favorites = match sqlx::query_as::<_, FavoriteProductRow>(
+        "SELECT f.id, f.product_id, f.note, f.created_at, p.name, p.image_url, pp.current_price AS price, p.original_price,
+                p.average_rating, p.review_count, p.rating_histogram
          FROM favorites f
          JOIN products p ON f.product_id = p.id
-         LEFT JOIN reviews r ON p.id = r.product_id
-         WHERE f.user_id = $1 AND p.is_active = true
-         GROUP BY f.id, f.product_id, f.created_at, p.name, p.image_url, p.price, p.original_price
-         ORDER BY f.created_at DESC",
-        user_id
+         JOIN product_prices pp ON pp.product_id = p.id
+         WHERE f.user_id = $1 AND f.wishlist_id IS NULL AND p.is_active = true
+         ORDER BY f.created_at DESC"
     )
+    .bind(user_id)
     .fetch_all(pool.get_ref())
     .await {
         Ok(favorites) => favorites,
@@ -38,6 +53,7 @@ async fn get_favorites(
             serde_json::json!({
                 "id": f.id,
                 "product_id": f.product_id,
+                "note": f.note,
                 "created_at": f.created_at,
                 "product": {
                     "id": f.product_id,
@@ -45,8 +61,9 @@ async fn get_favorites(
                     "image_url": f.image_url,
                     "price": f.price,
                     "original_price": f.original_price,
-                    "rating": f.rating.unwrap_or_else(|| bigdecimal::BigDecimal::from(0)),
-                    "reviews_count": f.reviews_count.unwrap_or(0)
+                    "rating": f.average_rating,
+                    "reviews_count": f.review_count,
+                    "rating_histogram": f.rating_histogram
                 }
             })
         })
@@ -65,10 +82,8 @@ async fn add_to_favorites(
     let product_id = path.into_inner();
 
     // Check if product exists and is active
-    let product_exists = match sqlx::query!(
-        "SELECT id FROM products WHERE id = $1 AND is_active = true",
-        product_id
-    )
+    let product_exists = match sqlx::query_as::<_, (i32,)>("SELECT id FROM products WHERE id = $1 AND is_active = true")
+    .bind(product_id)
     .fetch_optional(pool.get_ref())
     .await {
         Ok(result) => result,
@@ -83,11 +98,11 @@ async fn add_to_favorites(
     }
 
     // Check if already in favorites
-    let existing_favorite = match sqlx::query!(
-        "SELECT id FROM favorites WHERE user_id = $1 AND product_id = $2",
-        user_id,
-        product_id
+    let existing_favorite = match sqlx::query_as::<_, (i32,)>(
+        "SELECT id FROM favorites WHERE user_id = $1 AND product_id = $2 AND wishlist_id IS NULL"
     )
+    .bind(user_id)
+    .bind(product_id)
     .fetch_optional(pool.get_ref())
     .await {
         Ok(result) => result,
@@ -130,11 +145,11 @@ async fn remove_from_favorites(
     let user_id: i32 = claims.sub.parse().unwrap();
     let product_id = path.into_inner();
 
-    let result = match sqlx::query!(
-        "DELETE FROM favorites WHERE user_id = $1 AND product_id = $2",
-        user_id,
-        product_id
+    let result = match sqlx::query(
+        "DELETE FROM favorites WHERE user_id = $1 AND product_id = $2 AND wishlist_id IS NULL"
     )
+    .bind(user_id)
+    .bind(product_id)
     .execute(pool.get_ref())
     .await {
         Ok(result) => result,
@@ -151,6 +166,40 @@ async fn remove_from_favorites(
     }
 }
 
+#[put("/{product_id}/note")]
+async fn update_favorite_note(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    note_data: web::Json<FavoriteNoteRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+    let product_id = path.into_inner();
+
+    let note = match clean_note(&note_data.note) {
+        Ok(note) => note,
+        Err(response) => return Ok(response),
+    };
+
+    match sqlx::query_as::<_, Favorite>(
+        "UPDATE favorites SET note = $1
+         WHERE user_id = $2 AND product_id = $3 AND wishlist_id IS NULL
+         RETURNING *"
+    )
+    .bind(note)
+    .bind(user_id)
+    .bind(product_id)
+    .fetch_optional(pool.get_ref())
+    .await {
+        Ok(Some(favorite)) => Ok(HttpResponse::Ok().json(favorite)),
+        Ok(None) => Ok(HttpResponse::NotFound().json("Product not in favorites")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to update note"))
+        }
+    }
+}
+
 #[get("/check/{product_id}")]
 async fn check_favorite_status(
     pool: web::Data<PgPool>,
@@ -160,11 +209,11 @@ async fn check_favorite_status(
     let user_id: i32 = claims.sub.parse().unwrap();
     let product_id = path.into_inner();
 
-    let favorite = match sqlx::query!(
-        "SELECT id FROM favorites WHERE user_id = $1 AND product_id = $2",
-        user_id,
-        product_id
+    let favorite = match sqlx::query_as::<_, (i32,)>(
+        "SELECT id FROM favorites WHERE user_id = $1 AND product_id = $2 AND wishlist_id IS NULL"
     )
+    .bind(user_id)
+    .bind(product_id)
     .fetch_optional(pool.get_ref())
     .await {
         Ok(result) => result,
@@ -185,10 +234,8 @@ async fn clear_favorites(
 ) -> Result<impl Responder> {
     let user_id: i32 = claims.sub.parse().unwrap();
 
-    match sqlx::query!(
-        "DELETE FROM favorites WHERE user_id = $1",
-        user_id
-    )
+    match sqlx::query("DELETE FROM favorites WHERE user_id = $1 AND wishlist_id IS NULL")
+    .bind(user_id)
     .execute(pool.get_ref())
     .await {
         Ok(_) => (),
@@ -207,8 +254,10 @@ pub fn init(cfg: &mut web::ServiceConfig) {
             .wrap(AuthMiddleware)
             .service(get_favorites)
             .service(add_to_favorites)
+            // Before /{product_id} so "clear" isn't taken for an id
+            .service(clear_favorites)
             .service(remove_from_favorites)
+            .service(update_favorite_note)
             .service(check_favorite_status)
-            .service(clear_favorites)
     );
 }
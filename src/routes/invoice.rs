@@ -0,0 +1,82 @@
+use actix_web::{get, http::header, web, HttpResponse, Responder, Result};
+use sqlx::PgPool;
+use crate::models::order::Order;
+use crate::models::user::Claims;
+use crate::services::invoices;
+
+// Look up (or issue) the invoice of an order and send it as a PDF.
+// `owner` limits the lookup to a customer's own orders.
+async fn invoice_pdf(pool: &PgPool, order_id: i32, owner: Option<i32>, issued_by: i32) -> HttpResponse {
+    let result = async {
+        let mut tx = pool.begin().await?;
+
+        let order = sqlx::query_as::<_, Order>(
+            "SELECT * FROM orders WHERE id = $1 AND ($2::INTEGER IS NULL OR user_id = $2) FOR UPDATE"
+        )
+        .bind(order_id)
+        .bind(owner)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let invoice = match &order {
+            Some(order) => invoices::invoice_for_order(&mut tx, order, Some(issued_by)).await?,
+            None => None,
+        };
+
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>((order.is_some(), invoice))
+    }
+    .await;
+
+    match result {
+        Ok((_, Some(invoice))) => HttpResponse::Ok()
+            .content_type("application/pdf")
+            .insert_header((
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}.pdf\"", invoice.invoice_number),
+            ))
+            .body(invoices::render_pdf(&invoice)),
+        Ok((true, None)) => HttpResponse::Conflict().json("An invoice is available once the order has been confirmed"),
+        Ok((false, None)) => HttpResponse::NotFound().json("Order not found"),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            HttpResponse::InternalServerError().json("Failed to create invoice")
+        }
+    }
+}
+
+#[get("/orders/{id}/invoice.pdf")]
+async fn get_user_invoice(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    Ok(invoice_pdf(pool.get_ref(), path.into_inner(), Some(user_id), user_id).await)
+}
+
+// Admin routes
+#[get("/orders/{id}/invoice.pdf")]
+async fn get_invoice(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let admin_id: i32 = claims.sub.parse().unwrap();
+    Ok(invoice_pdf(pool.get_ref(), path.into_inner(), None, admin_id).await)
+}
+
+// Mounted inside the /api/auth scope
+pub fn user_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_user_invoice);
+}
+
+// Mounted inside the /api/admin scope
+pub fn admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_invoice);
+}
@@ -2,7 +2,21 @@ pub mod auth;
 pub mod user;
 pub mod admin;
 pub mod product;
+pub mod product_image;
+pub mod product_attribute;
+pub mod category;
+pub mod sitemap;
+pub mod review;
 pub mod favorite;
+pub mod product_alert;
+pub mod wishlist;
 pub mod cart;
 pub mod checkout;
+pub mod coupon;
+pub mod promotion;
+pub mod shipping;
+pub mod shipment;
+pub mod refund;
+pub mod returns;
+pub mod invoice;
 pub mod notification;
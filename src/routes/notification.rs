@@ -430,11 +430,12 @@ pub fn init(cfg: &mut web::ServiceConfig) {
             .service(get_notifications)
             .service(get_notification_stats)
             .service(create_notification)
-            .service(update_notification)
+            // Fixed paths before /{id} so they are not parsed as an id
             .service(mark_multiple_notifications)
-            .service(delete_notification)
             .service(mark_all_as_read)
             .service(get_notification_preferences)
             .service(update_notification_preferences)
+            .service(update_notification)
+            .service(delete_notification)
     );
 }
\ No newline at end of file
@@ -2,21 +2,40 @@ use actix_web::{get, post, put, delete, web, HttpResponse, Responder, Result};
 use sqlx::PgPool;
 use crate::models::product::*;
 use crate::models::user::Claims;
-use crate::middleware::auth;
+use crate::routes::category;
+use crate::services::{product_alerts, slugs};
+use crate::utils::slug::slugify;
 use crate::utils::error;
 
+// Storefront reads carry the selling price resolved by product_prices
+const STOREFRONT_SELECT: &str = "SELECT p.*, pp.current_price, pp.promotion_id, pp.promotion_ends_at, pp.promotion_remaining
+     FROM products p
+     JOIN product_prices pp ON pp.product_id = p.id";
+
 #[get("/products")]
 async fn get_products(
     pool: web::Data<PgPool>,
     query: web::Query<ProductFilter>,
+    sort: web::Query<ProductSort>,
 ) -> Result<impl Responder> {
-    let mut sql = "SELECT * FROM products WHERE is_active = true".to_string();
+    let mut sql = format!("{} WHERE is_active = true", STOREFRONT_SELECT);
     let mut params = Vec::new();
     let mut param_count = 1;
 
     // Apply filters
+    // A parent category also matches the products of its subcategories
     if let Some(category) = &query.category {
-        sql.push_str(&format!(" AND category = ${}", param_count));
+        sql.push_str(&format!(
+            " AND (category_id IN (
+                WITH RECURSIVE tree AS (
+                    SELECT id FROM categories WHERE slug = ${0} OR LOWER(name) = LOWER(${0})
+                    UNION ALL
+                    SELECT c.id FROM categories c JOIN tree t ON c.parent_id = t.id
+                )
+                SELECT id FROM tree
+            ) OR category = ${0})",
+            param_count
+        ));
         params.push(category.clone());
         param_count += 1;
     }
@@ -28,24 +47,62 @@ async fn get_products(
     }
 
     if let Some(min_price) = &query.min_price {
-        sql.push_str(&format!(" AND price >= ${}", param_count));
+        sql.push_str(&format!(" AND current_price >= ${}::numeric", param_count));
         params.push(min_price.to_string());
         param_count += 1;
     }
 
     if let Some(max_price) = &query.max_price {
-        sql.push_str(&format!(" AND price <= ${}", param_count));
+        sql.push_str(&format!(" AND current_price <= ${}::numeric", param_count));
         params.push(max_price.to_string());
         param_count += 1;
     }
 
+    if let Some(technique) = &query.technique {
+        sql.push_str(&format!(" AND technique = ${}", param_count));
+        params.push(technique.to_lowercase());
+        param_count += 1;
+    }
+
+    // Free-text attributes match case-insensitively
+    let text_attributes = [
+        ("fabric", &query.fabric),
+        ("origin_region", &query.origin_region),
+        ("motif", &query.motif),
+        ("dye_type", &query.dye_type),
+    ];
+    for (column, value) in text_attributes {
+        if let Some(value) = value {
+            sql.push_str(&format!(" AND {} ILIKE ${}", column, param_count));
+            params.push(value.clone());
+            param_count += 1;
+        }
+    }
+
     if query.in_stock_only.unwrap_or(false) {
         sql.push_str(" AND stock_quantity > 0");
     }
 
-    sql.push_str(" ORDER BY created_at DESC");
+    let direction = match sort.direction.as_deref().unwrap_or("desc") {
+        "asc" => "ASC",
+        "desc" => "DESC",
+        _ => return Ok(HttpResponse::BadRequest().json("Invalid sort_order, expected asc or desc")),
+    };
+    let order_by = match sort.field.as_deref().unwrap_or("created_at") {
+        "created_at" => format!("created_at {}", direction),
+        "price" => format!("current_price {}, created_at DESC", direction),
+        "rating" => format!("average_rating {0}, review_count {0}, created_at DESC", direction),
+        "sold_count" => format!("COALESCE(sold_count, 0) {}, created_at DESC", direction),
+        _ => return Ok(HttpResponse::BadRequest().json("Invalid sort_by, expected one of: price, rating, created_at, sold_count")),
+    };
+    sql.push_str(&format!(" ORDER BY {}", order_by));
+
+    let mut products_query = sqlx::query_as::<_, Product>(&sql);
+    for param in &params {
+        products_query = products_query.bind(param);
+    }
 
-    let products = match sqlx::query_as::<_, Product>(&sql)
+    let products = match products_query
         .fetch_all(pool.get_ref())
         .await 
     {
@@ -60,17 +117,38 @@ async fn get_products(
     Ok(HttpResponse::Ok().json(ApiResponse::success(products, "Products retrieved successfully")))
 }
 
-#[get("/products/{id}")]
-async fn get_product_by_id(
+// Product plus its gallery and features, as returned by the detail endpoints
+async fn product_detail_response(pool: &PgPool, product: Product) -> HttpResponse {
+    let images = match crate::routes::product_image::fetch_product_images(pool, product.id).await {
+        Ok(images) => images,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return HttpResponse::InternalServerError().json("Failed to fetch product images");
+        }
+    };
+
+    let features = match crate::routes::product_attribute::fetch_product_features(pool, product.id).await {
+        Ok(features) => features,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return HttpResponse::InternalServerError().json("Failed to fetch product features");
+        }
+    };
+
+    HttpResponse::Ok().json(ProductDetail { product, images, features })
+}
+
+#[get("/products/slug/{slug}")]
+async fn get_product_by_slug(
     pool: web::Data<PgPool>,
-    path: web::Path<i32>,
+    path: web::Path<String>,
 ) -> Result<impl Responder> {
-    let product_id = path.into_inner();
+    let slug = path.into_inner();
 
     let product = match sqlx::query_as::<_, Product>(
-        "SELECT * FROM products WHERE id = $1 AND is_active = true"
+        &format!("{} WHERE slug = $1 AND is_active = true", STOREFRONT_SELECT)
     )
-    .bind(product_id)
+    .bind(&slug)
     .fetch_optional(pool.get_ref())
     .await {
         Ok(product) => product,
@@ -79,93 +157,101 @@ async fn get_product_by_id(
             return Ok(HttpResponse::InternalServerError().json("Failed to fetch product"));
         }
     };
-    
-    match product {
-        Some(product) => Ok(HttpResponse::Ok().json(product)),
-        None => Ok(HttpResponse::NotFound().json("Product not found")),
+
+    if let Some(product) = product {
+        return Ok(product_detail_response(pool.get_ref(), product).await);
     }
-}
 
-#[get("/products/{id}/reviews")]
-async fn get_product_reviews(
-    pool: web::Data<PgPool>,
-    path: web::Path<i32>,
-) -> Result<impl Responder> {
-    let product_id = path.into_inner();
+    // The slug may have been renamed: point the client at the current one
+    let current_slug = async {
+        match slugs::find_redirect(pool.get_ref(), slugs::PRODUCT, &slug).await? {
+            Some(product_id) => sqlx::query_as::<_, (Option<String>,)>(
+                "SELECT slug FROM products WHERE id = $1 AND is_active = true"
+            )
+            .bind(product_id)
+            .fetch_optional(pool.get_ref())
+            .await
+            .map(|row| row.and_then(|(slug,)| slug)),
+            None => Ok(None),
+        }
+    }
+    .await;
 
-    let reviews = match sqlx::query_as::<_, Review>(
-        "SELECT r.*, u.name as user_name 
-         FROM reviews r 
-         JOIN users u ON r.user_id = u.id 
-         WHERE r.product_id = $1 
-         ORDER BY r.created_at DESC"
-    )
-    .bind(product_id)
-    .fetch_all(pool.get_ref())
-    .await {
-        Ok(reviews) => reviews,
+    match current_slug {
+        Ok(Some(current_slug)) => Ok(slug_redirect("/api/products/slug", &current_slug)),
+        Ok(None) => Ok(HttpResponse::NotFound().json("Product not found")),
         Err(e) => {
             eprintln!("Database error: {}", e);
-            return Ok(HttpResponse::InternalServerError().json("Failed to fetch reviews"));
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch product"))
         }
-    };
+    }
+}
 
-    Ok(HttpResponse::Ok().json(reviews))
+// 301 with the new location in both the header and the body, so API
+// clients can update the browser URL
+pub(crate) fn slug_redirect(prefix: &str, slug: &str) -> HttpResponse {
+    let location = format!("{}/{}", prefix, slug);
+    HttpResponse::MovedPermanently()
+        .insert_header(("Location", location.clone()))
+        .json(serde_json::json!({
+            "redirect": true,
+            "slug": slug,
+            "location": location,
+        }))
 }
 
-#[post("/products/{id}/reviews")]
-async fn create_review(
+#[get("/products/{id}")]
+async fn get_product_by_id(
     pool: web::Data<PgPool>,
     path: web::Path<i32>,
-    review_data: web::Json<CreateReviewRequest>,
-    claims: web::ReqData<Claims>,
 ) -> Result<impl Responder> {
     let product_id = path.into_inner();
-    let user_id: i32 = claims.sub.parse().unwrap();
 
-    // Check if user has already reviewed this product
-    let existing_review = match sqlx::query!(
-        "SELECT id FROM reviews WHERE product_id = $1 AND user_id = $2",
-        product_id,
-        user_id
+    let product = match sqlx::query_as::<_, Product>(
+        &format!("{} WHERE id = $1 AND is_active = true", STOREFRONT_SELECT)
     )
+    .bind(product_id)
     .fetch_optional(pool.get_ref())
     .await {
-        Ok(result) => result,
+        Ok(product) => product,
         Err(e) => {
             eprintln!("Database error: {}", e);
-            return Ok(HttpResponse::InternalServerError().json("Database error"));
+            return Ok(HttpResponse::InternalServerError().json("Failed to fetch product"));
         }
     };
-
-    if existing_review.is_some() {
-        return Ok(HttpResponse::BadRequest().json("You have already reviewed this product"));
+    
+    match product {
+        Some(product) => Ok(product_detail_response(pool.get_ref(), product).await),
+        None => Ok(HttpResponse::NotFound().json("Product not found")),
     }
+}
 
-    // Create new review
-    let review = match sqlx::query_as::<_, Review>(
-        "INSERT INTO reviews (product_id, user_id, rating, comment, created_at) 
-         VALUES ($1, $2, $3, $4, NOW()) 
-         RETURNING id, product_id, user_id, '' as user_name, rating, comment, false as is_verified, created_at"
-    )
-    .bind(product_id)
-    .bind(user_id)
-    .bind(review_data.rating)
-    .bind(&review_data.comment)
-    .fetch_one(pool.get_ref())
-    .await {
-        Ok(review) => review,
-        Err(e) => {
-            eprintln!("Database error: {}", e);
-            return Ok(HttpResponse::InternalServerError().json("Failed to create review"));
-        }
-    };
+fn base_slug(name: &str) -> String {
+    let slug = slugify(name);
+    if slug.is_empty() { "produk".to_string() } else { slug }
+}
 
-    Ok(HttpResponse::Created().json(review))
+async fn slug_in_use(pool: &PgPool, slug: &str, except_id: Option<i32>) -> Result<bool, sqlx::Error> {
+    let existing = sqlx::query_as::<_, (i32,)>("SELECT id FROM products WHERE slug = $1 AND id IS DISTINCT FROM $2")
+        .bind(slug)
+        .bind(except_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(existing.is_some())
+}
+
+fn validate_technique(technique: &Option<String>) -> Result<(), String> {
+    match technique {
+        Some(t) if !BATIK_TECHNIQUES.contains(&t.to_lowercase().as_str()) => Err(format!(
+            "Invalid technique, expected one of: {}",
+            BATIK_TECHNIQUES.join(", ")
+        )),
+        _ => Ok(()),
+    }
 }
 
 // Admin routes
-#[post("/admin/products")]
+#[post("/products")]
 async fn create_product(
     pool: web::Data<PgPool>,
     product_data: web::Json<CreateProductRequest>,
@@ -175,32 +261,82 @@ async fn create_product(
         return Ok(HttpResponse::Forbidden().json("Admin access required"));
     }
 
-    let product = match sqlx::query_as::<_, Product>(
-        "INSERT INTO products (name, description, short_description, price, discount_price, sku, stock_quantity, category, brand, weight, dimensions, is_active, is_featured, stock, image_url, additional_images, original_price, size_options, color_options, created_at, updated_at)
-         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, NOW(), NOW())
-         RETURNING *"
-    )
-    .bind(&product_data.name)
-    .bind(&product_data.description)
-    .bind(&product_data.short_description)
-    .bind(&product_data.price)
-    .bind(&product_data.discount_price)
-    .bind(&product_data.sku)
-    .bind(product_data.stock_quantity)
-    .bind(&product_data.category)
-    .bind(&product_data.brand)
-    .bind(&product_data.weight)
-    .bind(&product_data.dimensions)
-    .bind(product_data.is_active.unwrap_or(true))
-    .bind(product_data.is_featured.unwrap_or(false))
-    .bind(product_data.stock)
-    .bind(&product_data.image_url)
-    .bind(&product_data.additional_images)
-    .bind(&product_data.original_price)
-    .bind(&product_data.size_options)
-    .bind(&product_data.color_options)
-    .fetch_one(pool.get_ref())
-    .await {
+    if let Err(message) = validate_technique(&product_data.technique) {
+        return Ok(HttpResponse::BadRequest().json(message));
+    }
+
+    let category = match category::resolve_category(pool.get_ref(), product_data.category_id, product_data.category.as_deref()).await {
+        Ok(Some(category)) => category,
+        Ok(None) => return Ok(HttpResponse::BadRequest().json("Unknown category")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Database error"));
+        }
+    };
+
+    let slug = match product_data.slug.as_deref().map(slugify) {
+        Some(slug) if slug.is_empty() => return Ok(HttpResponse::BadRequest().json("Slug cannot be empty")),
+        Some(slug) => match slug_in_use(pool.get_ref(), &slug, None).await {
+            Ok(false) => Some(slug),
+            Ok(true) => return Ok(HttpResponse::BadRequest().json("Slug is already used by another product")),
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                return Ok(HttpResponse::InternalServerError().json("Database error"));
+            }
+        },
+        None => None,
+    };
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+
+        let slug = match slug {
+            Some(slug) => slug,
+            None => slugs::unique_product_slug(&mut tx, &base_slug(&product_data.name), None).await?,
+        };
+
+        let product = sqlx::query_as::<_, Product>(
+            "INSERT INTO products (name, description, short_description, price, discount_price, sku, stock_quantity, category, brand, weight, dimensions, is_active, is_featured, stock, image_url, additional_images, original_price, size_options, color_options, technique, fabric, origin_region, motif, dye_type, category_id, slug, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22, $23, $24, $25, $26, NOW(), NOW())
+             RETURNING *"
+        )
+        .bind(&product_data.name)
+        .bind(&product_data.description)
+        .bind(&product_data.short_description)
+        .bind(&product_data.price)
+        .bind(&product_data.discount_price)
+        .bind(&product_data.sku)
+        .bind(product_data.stock_quantity)
+        .bind(&category.name)
+        .bind(&product_data.brand)
+        .bind(&product_data.weight)
+        .bind(&product_data.dimensions)
+        .bind(product_data.is_active.unwrap_or(true))
+        .bind(product_data.is_featured.unwrap_or(false))
+        .bind(product_data.stock)
+        .bind(&product_data.image_url)
+        .bind(&product_data.additional_images)
+        .bind(&product_data.original_price)
+        .bind(&product_data.size_options)
+        .bind(&product_data.color_options)
+        .bind(product_data.technique.as_ref().map(|t| t.to_lowercase()))
+        .bind(&product_data.fabric)
+        .bind(&product_data.origin_region)
+        .bind(&product_data.motif)
+        .bind(&product_data.dye_type)
+        .bind(category.id)
+        .bind(&slug)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        slugs::record_slug_change(&mut tx, slugs::PRODUCT, product.id, None, &slug).await?;
+
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(product)
+    }
+    .await;
+
+    let product = match result {
         Ok(product) => product,
         Err(e) => {
             eprintln!("Database error: {}", e);
@@ -211,7 +347,7 @@ async fn create_product(
     Ok(HttpResponse::Created().json(product))
 }
 
-#[put("/admin/products/{id}")]
+#[put("/products/{id}")]
 async fn update_product(
     pool: web::Data<PgPool>,
     path: web::Path<i32>,
@@ -222,55 +358,123 @@ async fn update_product(
         return Ok(HttpResponse::Forbidden().json("Admin access required"));
     }
 
+    if let Err(message) = validate_technique(&product_data.technique) {
+        return Ok(HttpResponse::BadRequest().json(message));
+    }
+
+    let category = if product_data.category_id.is_some() || product_data.category.is_some() {
+        match category::resolve_category(pool.get_ref(), product_data.category_id, product_data.category.as_deref()).await {
+            Ok(Some(category)) => Some(category),
+            Ok(None) => return Ok(HttpResponse::BadRequest().json("Unknown category")),
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                return Ok(HttpResponse::InternalServerError().json("Database error"));
+            }
+        }
+    } else {
+        None
+    };
+
     let product_id = path.into_inner();
 
-    let product = match sqlx::query_as::<_, Product>(
-        "UPDATE products SET 
-         name = COALESCE($1, name),
-         description = COALESCE($2, description),
-         short_description = COALESCE($3, short_description),
-         price = COALESCE($4, price),
-         discount_price = COALESCE($5, discount_price),
-         sku = COALESCE($6, sku),
-         stock_quantity = COALESCE($7, stock_quantity),
-         category = COALESCE($8, category),
-         brand = COALESCE($9, brand),
-         weight = COALESCE($10, weight),
-         dimensions = COALESCE($11, dimensions),
-         is_active = COALESCE($12, is_active),
-         is_featured = COALESCE($13, is_featured),
-         stock = COALESCE($14, stock),
-         image_url = COALESCE($15, image_url),
-         additional_images = COALESCE($16, additional_images),
-         original_price = COALESCE($17, original_price),
-         size_options = COALESCE($18, size_options),
-         color_options = COALESCE($19, color_options),
-         updated_at = NOW()
-         WHERE id = $20
-         RETURNING *"
-    )
-    .bind(&product_data.name)
-    .bind(&product_data.description)
-    .bind(&product_data.short_description)
-    .bind(&product_data.price)
-    .bind(&product_data.discount_price)
-    .bind(&product_data.sku)
-    .bind(product_data.stock_quantity)
-    .bind(&product_data.category)
-    .bind(&product_data.brand)
-    .bind(&product_data.weight)
-    .bind(&product_data.dimensions)
-    .bind(product_data.is_active)
-    .bind(product_data.is_featured)
-    .bind(product_data.stock)
-    .bind(&product_data.image_url)
-    .bind(&product_data.additional_images)
-    .bind(&product_data.original_price)
-    .bind(&product_data.size_options)
-    .bind(&product_data.color_options)
-    .bind(product_id)
-    .fetch_optional(pool.get_ref())
-    .await {
+    let slug = match product_data.slug.as_deref().map(slugify) {
+        Some(slug) if slug.is_empty() => return Ok(HttpResponse::BadRequest().json("Slug cannot be empty")),
+        Some(slug) => match slug_in_use(pool.get_ref(), &slug, Some(product_id)).await {
+            Ok(false) => Some(slug),
+            Ok(true) => return Ok(HttpResponse::BadRequest().json("Slug is already used by another product")),
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                return Ok(HttpResponse::InternalServerError().json("Database error"));
+            }
+        },
+        None => None,
+    };
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+
+        let before = product_alerts::snapshot_for_update(&mut tx, product_id).await?;
+        let before = match before {
+            Some(before) => before,
+            None => return Ok(None),
+        };
+
+        let product = sqlx::query_as::<_, Product>(
+            "UPDATE products SET 
+             name = COALESCE($1, name),
+             description = COALESCE($2, description),
+             short_description = COALESCE($3, short_description),
+             price = COALESCE($4, price),
+             discount_price = COALESCE($5, discount_price),
+             sku = COALESCE($6, sku),
+             stock_quantity = COALESCE($7, stock_quantity),
+             category = COALESCE($8, category),
+             brand = COALESCE($9, brand),
+             weight = COALESCE($10, weight),
+             dimensions = COALESCE($11, dimensions),
+             is_active = COALESCE($12, is_active),
+             is_featured = COALESCE($13, is_featured),
+             stock = COALESCE($14, stock),
+             image_url = COALESCE($15, image_url),
+             additional_images = COALESCE($16, additional_images),
+             original_price = COALESCE($17, original_price),
+             size_options = COALESCE($18, size_options),
+             color_options = COALESCE($19, color_options),
+             technique = COALESCE($20, technique),
+             fabric = COALESCE($21, fabric),
+             origin_region = COALESCE($22, origin_region),
+             motif = COALESCE($23, motif),
+             dye_type = COALESCE($24, dye_type),
+             category_id = COALESCE($25, category_id),
+             slug = COALESCE($26, slug),
+             updated_at = NOW()
+             WHERE id = $27
+             RETURNING *"
+        )
+        .bind(&product_data.name)
+        .bind(&product_data.description)
+        .bind(&product_data.short_description)
+        .bind(&product_data.price)
+        .bind(&product_data.discount_price)
+        .bind(&product_data.sku)
+        .bind(product_data.stock_quantity)
+        .bind(category.as_ref().map(|c| &c.name))
+        .bind(&product_data.brand)
+        .bind(&product_data.weight)
+        .bind(&product_data.dimensions)
+        .bind(product_data.is_active)
+        .bind(product_data.is_featured)
+        .bind(product_data.stock)
+        .bind(&product_data.image_url)
+        .bind(&product_data.additional_images)
+        .bind(&product_data.original_price)
+        .bind(&product_data.size_options)
+        .bind(&product_data.color_options)
+        .bind(product_data.technique.as_ref().map(|t| t.to_lowercase()))
+        .bind(&product_data.fabric)
+        .bind(&product_data.origin_region)
+        .bind(&product_data.motif)
+        .bind(&product_data.dye_type)
+        .bind(category.as_ref().map(|c| c.id))
+        .bind(&slug)
+        .bind(product_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        // Links to the previous slug keep working
+        if let Some(slug) = &slug {
+            slugs::record_slug_change(&mut tx, slugs::PRODUCT, product_id, before.slug.as_deref(), slug).await?;
+        }
+
+        // Price drops and restocks notify users who favorited or subscribed
+        product_alerts::notify_product_changes(&mut tx, &before, &product).await?;
+
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(Some(product))
+    }
+    .await;
+
+    let product = match result {
         Ok(result) => result,
         Err(e) => {
             eprintln!("Database error: {}", e);
@@ -284,7 +488,7 @@ async fn update_product(
     }
 }
 
-#[delete("/admin/products/{id}")]
+#[delete("/products/{id}")]
 async fn delete_product(
     pool: web::Data<PgPool>,
     path: web::Path<i32>,
@@ -318,18 +522,13 @@ async fn delete_product(
 
 pub fn init(cfg: &mut web::ServiceConfig) {
     cfg.service(get_products)
-        .service(get_product_by_id)
-        .service(get_product_reviews)
-        .service(
-            web::scope("/auth")
-                .wrap(auth::AuthMiddleware)
-                .service(create_review)
-        )
-        .service(
-            web::scope("/admin")
-                .wrap(auth::AuthMiddleware)
-                .service(create_product)
-                .service(update_product)
-                .service(delete_product)
-        );
+        .service(get_product_by_slug)
+        .service(get_product_by_id);
+}
+
+// Mounted inside the authenticated /api/admin scope
+pub fn admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(create_product)
+        .service(update_product)
+        .service(delete_product);
 }
\ No newline at end of file
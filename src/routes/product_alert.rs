@@ -0,0 +1,145 @@
+use actix_web::{get, post, delete, web, HttpResponse, Responder, Result};
+use sqlx::PgPool;
+use crate::models::product::{ProductAlertQuery, ProductAlertRequest, ProductAlertSubscription};
+use crate::models::user::Claims;
+use crate::services::product_alerts::ALERT_TYPES;
+
+fn validate_alert_type(alert_type: &str) -> Result<(), String> {
+    if ALERT_TYPES.contains(&alert_type) {
+        Ok(())
+    } else {
+        Err(format!("Unknown alert type '{}', expected one of: {}", alert_type, ALERT_TYPES.join(", ")))
+    }
+}
+
+#[get("/product-alerts")]
+async fn get_product_alerts(
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    match sqlx::query_as::<_, ProductAlertSubscription>(
+        "SELECT * FROM product_alert_subscriptions WHERE user_id = $1 ORDER BY created_at DESC, id DESC"
+    )
+    .bind(user_id)
+    .fetch_all(pool.get_ref())
+    .await {
+        Ok(subscriptions) => Ok(HttpResponse::Ok().json(subscriptions)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch product alerts"))
+        }
+    }
+}
+
+// Subscribe to price-drop and/or back-in-stock alerts without favoriting
+#[post("/products/{id}/alerts")]
+async fn subscribe_product_alerts(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    alert_data: web::Json<ProductAlertRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let product_id = path.into_inner();
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    let alert_types = match &alert_data.alert_types {
+        Some(alert_types) if alert_types.is_empty() => {
+            return Ok(HttpResponse::BadRequest().json("alert_types cannot be empty"));
+        }
+        Some(alert_types) => alert_types.clone(),
+        None => ALERT_TYPES.iter().map(|t| t.to_string()).collect(),
+    };
+    for alert_type in &alert_types {
+        if let Err(message) = validate_alert_type(alert_type) {
+            return Ok(HttpResponse::BadRequest().json(message));
+        }
+    }
+
+    let product_exists = match sqlx::query_as::<_, (i32,)>("SELECT id FROM products WHERE id = $1 AND is_active = true")
+        .bind(product_id)
+        .fetch_optional(pool.get_ref())
+        .await {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                return Ok(HttpResponse::InternalServerError().json("Database error"));
+            }
+        };
+
+    if product_exists.is_none() {
+        return Ok(HttpResponse::NotFound().json("Product not found"));
+    }
+
+    let result = sqlx::query(
+        "INSERT INTO product_alert_subscriptions (user_id, product_id, alert_type, created_at)
+         SELECT $1, $2, alert_type, NOW() FROM UNNEST($3::varchar[]) AS alert_type
+         ON CONFLICT (user_id, product_id, alert_type) DO NOTHING"
+    )
+    .bind(user_id)
+    .bind(product_id)
+    .bind(&alert_types)
+    .execute(pool.get_ref())
+    .await;
+
+    if let Err(e) = result {
+        eprintln!("Database error: {}", e);
+        return Ok(HttpResponse::InternalServerError().json("Failed to subscribe to product alerts"));
+    }
+
+    match sqlx::query_as::<_, ProductAlertSubscription>(
+        "SELECT * FROM product_alert_subscriptions WHERE user_id = $1 AND product_id = $2 ORDER BY id"
+    )
+    .bind(user_id)
+    .bind(product_id)
+    .fetch_all(pool.get_ref())
+    .await {
+        Ok(subscriptions) => Ok(HttpResponse::Created().json(subscriptions)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch product alerts"))
+        }
+    }
+}
+
+// Unsubscribe from one alert type, or from all alerts for the product
+#[delete("/products/{id}/alerts")]
+async fn unsubscribe_product_alerts(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    query: web::Query<ProductAlertQuery>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let product_id = path.into_inner();
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    if let Some(alert_type) = &query.alert_type {
+        if let Err(message) = validate_alert_type(alert_type) {
+            return Ok(HttpResponse::BadRequest().json(message));
+        }
+    }
+
+    match sqlx::query(
+        "DELETE FROM product_alert_subscriptions
+         WHERE user_id = $1 AND product_id = $2 AND ($3::varchar IS NULL OR alert_type = $3)"
+    )
+    .bind(user_id)
+    .bind(product_id)
+    .bind(&query.alert_type)
+    .execute(pool.get_ref())
+    .await {
+        Ok(result) if result.rows_affected() > 0 => Ok(HttpResponse::Ok().json("Unsubscribed from product alerts")),
+        Ok(_) => Ok(HttpResponse::NotFound().json("No product alerts found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to unsubscribe from product alerts"))
+        }
+    }
+}
+
+pub fn user_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_product_alerts)
+        .service(subscribe_product_alerts)
+        .service(unsubscribe_product_alerts);
+}
@@ -0,0 +1,204 @@
+use actix_web::{get, post, put, delete, web, HttpResponse, Responder, Result};
+use sqlx::PgPool;
+use crate::models::product::{AttributeValueCount, ProductAttributeFacets, ProductFeature, ProductFeatureRequest};
+use crate::models::user::Claims;
+
+pub(crate) async fn fetch_product_features(pool: &PgPool, product_id: i32) -> Result<Vec<ProductFeature>, sqlx::Error> {
+    sqlx::query_as::<_, ProductFeature>(
+        "SELECT * FROM product_features WHERE product_id = $1 ORDER BY id"
+    )
+    .bind(product_id)
+    .fetch_all(pool)
+    .await
+}
+
+async fn attribute_values(pool: &PgPool, column: &str) -> Result<Vec<AttributeValueCount>, sqlx::Error> {
+    // `column` is always one of the fixed attribute names below
+    sqlx::query_as::<_, AttributeValueCount>(&format!(
+        "SELECT {0} AS value, COUNT(*) AS product_count
+         FROM products
+         WHERE is_active = true AND {0} IS NOT NULL
+         GROUP BY {0}
+         ORDER BY product_count DESC, value",
+        column
+    ))
+    .fetch_all(pool)
+    .await
+}
+
+// Filter values for the catalogue sidebar
+#[get("/products/attributes")]
+async fn get_product_attributes(pool: web::Data<PgPool>) -> Result<impl Responder> {
+    let facets = async {
+        Ok::<_, sqlx::Error>(ProductAttributeFacets {
+            techniques: attribute_values(pool.get_ref(), "technique").await?,
+            fabrics: attribute_values(pool.get_ref(), "fabric").await?,
+            origin_regions: attribute_values(pool.get_ref(), "origin_region").await?,
+            motifs: attribute_values(pool.get_ref(), "motif").await?,
+            dye_types: attribute_values(pool.get_ref(), "dye_type").await?,
+        })
+    }
+    .await;
+
+    match facets {
+        Ok(facets) => Ok(HttpResponse::Ok().json(facets)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch product attributes"))
+        }
+    }
+}
+
+#[get("/products/{id}/features")]
+async fn get_product_features(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+) -> Result<impl Responder> {
+    let product_id = path.into_inner();
+
+    match fetch_product_features(pool.get_ref(), product_id).await {
+        Ok(features) => Ok(HttpResponse::Ok().json(features)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch product features"))
+        }
+    }
+}
+
+fn validate_feature(feature: &ProductFeatureRequest) -> Result<(), &'static str> {
+    if feature.feature_name.trim().is_empty() || feature.feature_value.trim().is_empty() {
+        return Err("Feature name and value are required");
+    }
+    if feature.feature_name.len() > 100 || feature.feature_value.len() > 255 {
+        return Err("Feature name or value is too long");
+    }
+    Ok(())
+}
+
+// Admin routes
+#[post("/products/{id}/features")]
+async fn create_product_feature(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    feature_data: web::Json<ProductFeatureRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    if let Err(message) = validate_feature(&feature_data) {
+        return Ok(HttpResponse::BadRequest().json(message));
+    }
+
+    let product_id = path.into_inner();
+
+    let product_exists = match sqlx::query_as::<_, (i32,)>("SELECT id FROM products WHERE id = $1")
+        .bind(product_id)
+        .fetch_optional(pool.get_ref())
+        .await {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                return Ok(HttpResponse::InternalServerError().json("Database error"));
+            }
+        };
+
+    if product_exists.is_none() {
+        return Ok(HttpResponse::NotFound().json("Product not found"));
+    }
+
+    match sqlx::query_as::<_, ProductFeature>(
+        "INSERT INTO product_features (product_id, feature_name, feature_value, created_at)
+         VALUES ($1, $2, $3, NOW())
+         RETURNING *"
+    )
+    .bind(product_id)
+    .bind(feature_data.feature_name.trim())
+    .bind(feature_data.feature_value.trim())
+    .fetch_one(pool.get_ref())
+    .await {
+        Ok(feature) => Ok(HttpResponse::Created().json(feature)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to create product feature"))
+        }
+    }
+}
+
+#[put("/products/{id}/features/{feature_id}")]
+async fn update_product_feature(
+    pool: web::Data<PgPool>,
+    path: web::Path<(i32, i32)>,
+    feature_data: web::Json<ProductFeatureRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    if let Err(message) = validate_feature(&feature_data) {
+        return Ok(HttpResponse::BadRequest().json(message));
+    }
+
+    let (product_id, feature_id) = path.into_inner();
+
+    match sqlx::query_as::<_, ProductFeature>(
+        "UPDATE product_features SET feature_name = $1, feature_value = $2
+         WHERE id = $3 AND product_id = $4
+         RETURNING *"
+    )
+    .bind(feature_data.feature_name.trim())
+    .bind(feature_data.feature_value.trim())
+    .bind(feature_id)
+    .bind(product_id)
+    .fetch_optional(pool.get_ref())
+    .await {
+        Ok(Some(feature)) => Ok(HttpResponse::Ok().json(feature)),
+        Ok(None) => Ok(HttpResponse::NotFound().json("Product feature not found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to update product feature"))
+        }
+    }
+}
+
+#[delete("/products/{id}/features/{feature_id}")]
+async fn delete_product_feature(
+    pool: web::Data<PgPool>,
+    path: web::Path<(i32, i32)>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let (product_id, feature_id) = path.into_inner();
+
+    match sqlx::query("DELETE FROM product_features WHERE id = $1 AND product_id = $2")
+        .bind(feature_id)
+        .bind(product_id)
+        .execute(pool.get_ref())
+        .await {
+            Ok(result) if result.rows_affected() > 0 => Ok(HttpResponse::Ok().json("Product feature deleted successfully")),
+            Ok(_) => Ok(HttpResponse::NotFound().json("Product feature not found")),
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                Ok(HttpResponse::InternalServerError().json("Failed to delete product feature"))
+            }
+        }
+}
+
+// Must be configured before product::init so /products/attributes
+// is not captured by /products/{id}
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_product_attributes)
+        .service(get_product_features);
+}
+
+// Mounted inside the /api/admin scope
+pub fn admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(create_product_feature)
+        .service(update_product_feature)
+        .service(delete_product_feature);
+}
@@ -0,0 +1,375 @@
+use actix_multipart::Multipart;
+use actix_web::{get, post, put, delete, web, HttpResponse, Responder, Result};
+use sqlx::{PgConnection, PgPool};
+use crate::models::product::{ProductImage, ReorderProductImagesRequest, UpdateProductImageRequest};
+use crate::models::user::Claims;
+use crate::services::images;
+use crate::services::storage::{self, BlobStore};
+use crate::utils::multipart::read_multipart;
+
+// Mirror the image gallery into products.image_url / additional_images,
+// which the storefront and order snapshots still read
+pub(crate) async fn sync_product_image_columns(conn: &mut PgConnection, product_id: i32) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE products SET
+             image_url = (SELECT image_url FROM product_images WHERE product_id = $1
+                          ORDER BY is_primary DESC, sort_order, id LIMIT 1),
+             additional_images = ARRAY(SELECT image_url FROM product_images
+                                       WHERE product_id = $1 AND NOT is_primary
+                                       ORDER BY sort_order, id),
+             updated_at = NOW()
+         WHERE id = $1"
+    )
+    .bind(product_id)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+pub(crate) async fn fetch_product_images(pool: &PgPool, product_id: i32) -> Result<Vec<ProductImage>, sqlx::Error> {
+    sqlx::query_as::<_, ProductImage>(
+        "SELECT * FROM product_images WHERE product_id = $1 ORDER BY is_primary DESC, sort_order, id"
+    )
+    .bind(product_id)
+    .fetch_all(pool)
+    .await
+}
+
+#[get("/products/{id}/images")]
+async fn get_product_images(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+) -> Result<impl Responder> {
+    let product_id = path.into_inner();
+
+    match fetch_product_images(pool.get_ref(), product_id).await {
+        Ok(images) => Ok(HttpResponse::Ok().json(images)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch product images"))
+        }
+    }
+}
+
+// Admin routes
+#[post("/products/{id}/images")]
+async fn upload_product_images(
+    pool: web::Data<PgPool>,
+    store: web::Data<dyn BlobStore>,
+    path: web::Path<i32>,
+    payload: Multipart,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let product_id = path.into_inner();
+
+    let product_exists = match sqlx::query_as::<_, (i32,)>("SELECT id FROM products WHERE id = $1")
+        .bind(product_id)
+        .fetch_optional(pool.get_ref())
+        .await {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                return Ok(HttpResponse::InternalServerError().json("Database error"));
+            }
+        };
+
+    if product_exists.is_none() {
+        return Ok(HttpResponse::NotFound().json("Product not found"));
+    }
+
+    let (files, fields) = match read_multipart(payload, "file", 10).await {
+        Ok(parts) => parts,
+        Err(msg) => return Ok(HttpResponse::BadRequest().json(msg)),
+    };
+
+    if files.is_empty() {
+        return Ok(HttpResponse::BadRequest().json("No image file provided (field name: file)"));
+    }
+
+    let alt_text = fields.get("alt_text").filter(|v| !v.is_empty()).cloned();
+    let make_primary = fields.get("is_primary").map(|v| v == "true" || v == "1").unwrap_or(false);
+
+    // Store every file first so a bad upload doesn't leave half a gallery behind
+    let mut stored = Vec::new();
+    for file in files {
+        match images::store_upload(store.get_ref(), &format!("products/{}", product_id), file.bytes).await {
+            Ok(image) => stored.push((file.file_name, image)),
+            Err(msg) => {
+                for (_, image) in &stored {
+                    storage::delete_all(store.get_ref(), &image.keys).await;
+                }
+                return Ok(HttpResponse::BadRequest().json(msg));
+            }
+        }
+    }
+
+    let all_keys: Vec<String> = stored.iter().flat_map(|(_, image)| image.keys.clone()).collect();
+
+    let result: std::result::Result<Vec<ProductImage>, sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+
+        // Serialize gallery edits for this product
+        sqlx::query("SELECT id FROM products WHERE id = $1 FOR UPDATE")
+            .bind(product_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let (has_primary, next_order): (bool, i32) = sqlx::query_as(
+            "SELECT COALESCE(BOOL_OR(is_primary), false), COALESCE(MAX(sort_order), 0) + 1
+             FROM product_images WHERE product_id = $1"
+        )
+        .bind(product_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        let primary_first = make_primary || !has_primary;
+        if primary_first {
+            sqlx::query("UPDATE product_images SET is_primary = false WHERE product_id = $1 AND is_primary")
+                .bind(product_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        let mut created = Vec::new();
+        for (index, (file_name, stored_image)) in stored.into_iter().enumerate() {
+            let image = sqlx::query_as::<_, ProductImage>(
+                "INSERT INTO product_images (product_id, image_url, webp_url, thumbnail_url, alt_text, is_primary,
+                 sort_order, width, height, storage_keys, created_at, updated_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, NOW(), NOW())
+                 RETURNING *"
+            )
+            .bind(product_id)
+            .bind(stored_image.url)
+            .bind(stored_image.webp_url)
+            .bind(stored_image.thumbnail_url)
+            .bind(alt_text.clone().or(file_name))
+            .bind(primary_first && index == 0)
+            .bind(next_order + index as i32)
+            .bind(stored_image.width)
+            .bind(stored_image.height)
+            .bind(stored_image.keys)
+            .fetch_one(&mut *tx)
+            .await?;
+            created.push(image);
+        }
+
+        sync_product_image_columns(&mut tx, product_id).await?;
+        tx.commit().await?;
+
+        Ok(created)
+    }
+    .await;
+
+    match result {
+        Ok(created) => Ok(HttpResponse::Created().json(created)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            storage::delete_all(store.get_ref(), &all_keys).await;
+            Ok(HttpResponse::InternalServerError().json("Failed to save product images"))
+        }
+    }
+}
+
+#[put("/products/{id}/images/order")]
+async fn reorder_product_images(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    order_data: web::Json<ReorderProductImagesRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let product_id = path.into_inner();
+
+    let result: std::result::Result<Option<()>, sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+
+        let existing: Vec<(i32,)> = sqlx::query_as(
+            "SELECT id FROM product_images WHERE product_id = $1 ORDER BY id FOR UPDATE"
+        )
+        .bind(product_id)
+        .fetch_all(&mut *tx)
+        .await?;
+
+        // The new order must list every image of the product exactly once
+        let mut requested = order_data.image_ids.clone();
+        requested.sort_unstable();
+        let existing: Vec<i32> = existing.into_iter().map(|(id,)| id).collect();
+        if requested != existing {
+            return Ok(None);
+        }
+
+        sqlx::query(
+            "UPDATE product_images pi SET sort_order = o.position
+             FROM UNNEST($1::INTEGER[]) WITH ORDINALITY AS o(id, position)
+             WHERE pi.id = o.id AND pi.product_id = $2"
+        )
+        .bind(&order_data.image_ids)
+        .bind(product_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sync_product_image_columns(&mut tx, product_id).await?;
+        tx.commit().await?;
+
+        Ok(Some(()))
+    }
+    .await;
+
+    match result {
+        Ok(Some(())) => match fetch_product_images(pool.get_ref(), product_id).await {
+            Ok(images) => Ok(HttpResponse::Ok().json(images)),
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                Ok(HttpResponse::InternalServerError().json("Failed to fetch product images"))
+            }
+        },
+        Ok(None) => Ok(HttpResponse::BadRequest().json("image_ids must contain every image of the product exactly once")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to reorder product images"))
+        }
+    }
+}
+
+#[put("/products/{id}/images/{image_id}")]
+async fn update_product_image(
+    pool: web::Data<PgPool>,
+    path: web::Path<(i32, i32)>,
+    image_data: web::Json<UpdateProductImageRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let (product_id, image_id) = path.into_inner();
+
+    if image_data.is_primary == Some(false) {
+        return Ok(HttpResponse::BadRequest().json("Choose another image as primary instead of unsetting it"));
+    }
+
+    let result: std::result::Result<Option<ProductImage>, sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+
+        if image_data.is_primary == Some(true) {
+            sqlx::query(
+                "UPDATE product_images SET is_primary = false
+                 WHERE product_id = $1 AND is_primary AND id <> $2
+                   AND EXISTS (SELECT 1 FROM product_images WHERE id = $2 AND product_id = $1)"
+            )
+            .bind(product_id)
+            .bind(image_id)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let image = sqlx::query_as::<_, ProductImage>(
+            "UPDATE product_images SET
+             alt_text = COALESCE($1, alt_text),
+             is_primary = COALESCE($2, is_primary),
+             updated_at = NOW()
+             WHERE id = $3 AND product_id = $4
+             RETURNING *"
+        )
+        .bind(&image_data.alt_text)
+        .bind(image_data.is_primary)
+        .bind(image_id)
+        .bind(product_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if image.is_some() {
+            sync_product_image_columns(&mut tx, product_id).await?;
+            tx.commit().await?;
+        }
+
+        Ok(image)
+    }
+    .await;
+
+    match result {
+        Ok(Some(image)) => Ok(HttpResponse::Ok().json(image)),
+        Ok(None) => Ok(HttpResponse::NotFound().json("Product image not found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to update product image"))
+        }
+    }
+}
+
+#[delete("/products/{id}/images/{image_id}")]
+async fn delete_product_image(
+    pool: web::Data<PgPool>,
+    store: web::Data<dyn BlobStore>,
+    path: web::Path<(i32, i32)>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let (product_id, image_id) = path.into_inner();
+
+    let result: std::result::Result<Option<ProductImage>, sqlx::Error> = async {
+        let mut tx = pool.begin().await?;
+
+        let deleted = sqlx::query_as::<_, ProductImage>(
+            "DELETE FROM product_images WHERE id = $1 AND product_id = $2 RETURNING *"
+        )
+        .bind(image_id)
+        .bind(product_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some(image) = &deleted {
+            // Promote the next image so the product keeps a cover
+            if image.is_primary {
+                sqlx::query(
+                    "UPDATE product_images SET is_primary = true
+                     WHERE id = (SELECT id FROM product_images WHERE product_id = $1 ORDER BY sort_order, id LIMIT 1)"
+                )
+                .bind(product_id)
+                .execute(&mut *tx)
+                .await?;
+            }
+
+            sync_product_image_columns(&mut tx, product_id).await?;
+            tx.commit().await?;
+        }
+
+        Ok(deleted)
+    }
+    .await;
+
+    match result {
+        Ok(Some(image)) => {
+            storage::delete_all(store.get_ref(), &image.storage_keys).await;
+            Ok(HttpResponse::Ok().json("Product image deleted successfully"))
+        }
+        Ok(None) => Ok(HttpResponse::NotFound().json("Product image not found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to delete product image"))
+        }
+    }
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_product_images);
+}
+
+// Mounted inside the /api/admin scope
+pub fn admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(upload_product_images)
+        .service(reorder_product_images)
+        .service(update_product_image)
+        .service(delete_product_image);
+}
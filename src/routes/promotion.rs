@@ -0,0 +1,331 @@
+use actix_web::{get, post, put, delete, web, HttpResponse, Responder, Result};
+use sqlx::PgPool;
+use bigdecimal::{BigDecimal, Zero};
+use chrono::NaiveDateTime;
+use crate::models::promotion::*;
+use crate::models::user::Claims;
+
+// Checks a promotion as it will be stored, after create/update values are merged
+#[allow(clippy::too_many_arguments)]
+fn validate_promotion(
+    name: &str,
+    discount_type: &str,
+    discount_value: &BigDecimal,
+    product_ids: &[i32],
+    category_ids: &[i32],
+    brands: &[String],
+    starts_at: NaiveDateTime,
+    ends_at: NaiveDateTime,
+) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Promotion name is required".to_string());
+    }
+    if !DISCOUNT_TYPES.contains(&discount_type) {
+        return Err(format!("discount_type must be one of: {}", DISCOUNT_TYPES.join(", ")));
+    }
+    if discount_value <= &BigDecimal::zero() {
+        return Err("discount_value must be greater than 0".to_string());
+    }
+    if discount_type == PERCENTAGE && discount_value > &BigDecimal::from(100) {
+        return Err("A percentage discount cannot exceed 100".to_string());
+    }
+    if product_ids.is_empty() && category_ids.is_empty() && brands.is_empty() {
+        return Err("A promotion needs at least one product, category or brand".to_string());
+    }
+    if ends_at <= starts_at {
+        return Err("ends_at must be after starts_at".to_string());
+    }
+    Ok(())
+}
+
+fn clean_brands(brands: &Option<Vec<String>>) -> Option<Vec<String>> {
+    brands.as_ref().map(|brands| {
+        brands
+            .iter()
+            .map(|brand| brand.trim().to_lowercase())
+            .filter(|brand| !brand.is_empty())
+            .collect()
+    })
+}
+
+// Promotions running right now, for sale banners and countdowns
+#[get("/promotions/active")]
+async fn get_active_promotions(pool: web::Data<PgPool>) -> Result<impl Responder> {
+    match sqlx::query_as::<_, Promotion>(
+        "SELECT * FROM promotions
+         WHERE is_active = true AND starts_at <= NOW() AND ends_at > NOW()
+           AND (quantity_limit IS NULL OR sold_quantity < quantity_limit)
+         ORDER BY ends_at, id"
+    )
+    .fetch_all(pool.get_ref())
+    .await {
+        Ok(promotions) => Ok(HttpResponse::Ok().json(promotions)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch promotions"))
+        }
+    }
+}
+
+#[get("/promotions")]
+async fn get_promotions(
+    pool: web::Data<PgPool>,
+    query: web::Query<PromotionListQuery>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let status_filter = match query.status.as_deref() {
+        None => "",
+        Some("scheduled") => " WHERE starts_at > NOW()",
+        Some("running") => " WHERE starts_at <= NOW() AND ends_at > NOW()",
+        Some("ended") => " WHERE ends_at <= NOW()",
+        Some(_) => return Ok(HttpResponse::BadRequest().json("status must be one of: scheduled, running, ended")),
+    };
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    match sqlx::query_as::<_, Promotion>(&format!(
+        "SELECT * FROM promotions{} ORDER BY starts_at DESC, id DESC LIMIT $1 OFFSET $2",
+        status_filter
+    ))
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool.get_ref())
+    .await {
+        Ok(promotions) => Ok(HttpResponse::Ok().json(promotions)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch promotions"))
+        }
+    }
+}
+
+#[get("/promotions/{id}")]
+async fn get_promotion(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    match sqlx::query_as::<_, Promotion>("SELECT * FROM promotions WHERE id = $1")
+        .bind(path.into_inner())
+        .fetch_optional(pool.get_ref())
+        .await {
+            Ok(Some(promotion)) => Ok(HttpResponse::Ok().json(promotion)),
+            Ok(None) => Ok(HttpResponse::NotFound().json("Promotion not found")),
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                Ok(HttpResponse::InternalServerError().json("Failed to fetch promotion"))
+            }
+        }
+}
+
+#[post("/promotions")]
+async fn create_promotion(
+    pool: web::Data<PgPool>,
+    promotion_data: web::Json<CreatePromotionRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let discount_type = promotion_data.discount_type.trim().to_lowercase();
+    let brands = clean_brands(&promotion_data.brands);
+
+    if let Err(message) = validate_promotion(
+        &promotion_data.name,
+        &discount_type,
+        &promotion_data.discount_value,
+        promotion_data.product_ids.as_deref().unwrap_or_default(),
+        promotion_data.category_ids.as_deref().unwrap_or_default(),
+        brands.as_deref().unwrap_or_default(),
+        promotion_data.starts_at,
+        promotion_data.ends_at,
+    ) {
+        return Ok(HttpResponse::BadRequest().json(message));
+    }
+    if promotion_data.quantity_limit.is_some_and(|limit| limit < 1) {
+        return Ok(HttpResponse::BadRequest().json("quantity_limit must be at least 1"));
+    }
+
+    match sqlx::query_as::<_, Promotion>(
+        "INSERT INTO promotions (name, description, discount_type, discount_value, product_ids, category_ids, brands,
+         starts_at, ends_at, quantity_limit, is_active, created_at, updated_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, NOW(), NOW())
+         RETURNING *"
+    )
+    .bind(promotion_data.name.trim())
+    .bind(&promotion_data.description)
+    .bind(&discount_type)
+    .bind(&promotion_data.discount_value)
+    .bind(&promotion_data.product_ids)
+    .bind(&promotion_data.category_ids)
+    .bind(&brands)
+    .bind(promotion_data.starts_at)
+    .bind(promotion_data.ends_at)
+    .bind(promotion_data.quantity_limit)
+    .bind(promotion_data.is_active.unwrap_or(true))
+    .fetch_one(pool.get_ref())
+    .await {
+        Ok(promotion) => Ok(HttpResponse::Created().json(promotion)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to create promotion"))
+        }
+    }
+}
+
+#[put("/promotions/{id}")]
+async fn update_promotion(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    promotion_data: web::Json<UpdatePromotionRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let promotion_id = path.into_inner();
+
+    let existing = match sqlx::query_as::<_, Promotion>("SELECT * FROM promotions WHERE id = $1")
+        .bind(promotion_id)
+        .fetch_optional(pool.get_ref())
+        .await {
+            Ok(Some(promotion)) => promotion,
+            Ok(None) => return Ok(HttpResponse::NotFound().json("Promotion not found")),
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                return Ok(HttpResponse::InternalServerError().json("Failed to fetch promotion"));
+            }
+        };
+
+    let discount_type = promotion_data.discount_type.as_ref().map(|t| t.trim().to_lowercase());
+    let brands = clean_brands(&promotion_data.brands);
+
+    if let Err(message) = validate_promotion(
+        promotion_data.name.as_deref().unwrap_or(&existing.name),
+        discount_type.as_deref().unwrap_or(&existing.discount_type),
+        promotion_data.discount_value.as_ref().unwrap_or(&existing.discount_value),
+        promotion_data.product_ids.as_ref().or(existing.product_ids.as_ref()).map(Vec::as_slice).unwrap_or_default(),
+        promotion_data.category_ids.as_ref().or(existing.category_ids.as_ref()).map(Vec::as_slice).unwrap_or_default(),
+        brands.as_ref().or(existing.brands.as_ref()).map(Vec::as_slice).unwrap_or_default(),
+        promotion_data.starts_at.unwrap_or(existing.starts_at),
+        promotion_data.ends_at.unwrap_or(existing.ends_at),
+    ) {
+        return Ok(HttpResponse::BadRequest().json(message));
+    }
+    if promotion_data.quantity_limit.is_some_and(|limit| limit < existing.sold_quantity.max(1)) {
+        return Ok(HttpResponse::BadRequest().json(format!(
+            "quantity_limit must be at least {}, the units already sold",
+            existing.sold_quantity.max(1)
+        )));
+    }
+
+    match sqlx::query_as::<_, Promotion>(
+        "UPDATE promotions SET
+         name = COALESCE($1, name),
+         description = COALESCE($2, description),
+         discount_type = COALESCE($3, discount_type),
+         discount_value = COALESCE($4, discount_value),
+         product_ids = COALESCE($5, product_ids),
+         category_ids = COALESCE($6, category_ids),
+         brands = COALESCE($7, brands),
+         starts_at = COALESCE($8, starts_at),
+         ends_at = COALESCE($9, ends_at),
+         quantity_limit = COALESCE($10, quantity_limit),
+         is_active = COALESCE($11, is_active),
+         updated_at = NOW()
+         WHERE id = $12
+         RETURNING *"
+    )
+    .bind(promotion_data.name.as_deref().map(str::trim))
+    .bind(&promotion_data.description)
+    .bind(&discount_type)
+    .bind(&promotion_data.discount_value)
+    .bind(&promotion_data.product_ids)
+    .bind(&promotion_data.category_ids)
+    .bind(&brands)
+    .bind(promotion_data.starts_at)
+    .bind(promotion_data.ends_at)
+    .bind(promotion_data.quantity_limit)
+    .bind(promotion_data.is_active)
+    .bind(promotion_id)
+    .fetch_one(pool.get_ref())
+    .await {
+        Ok(promotion) => Ok(HttpResponse::Ok().json(promotion)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to update promotion"))
+        }
+    }
+}
+
+// Promotions that already sold units are deactivated instead of deleted so
+// order lines keep pointing at them
+#[delete("/promotions/{id}")]
+async fn delete_promotion(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let promotion_id = path.into_inner();
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+
+        let deleted = sqlx::query(
+            "DELETE FROM promotions
+             WHERE id = $1 AND NOT EXISTS (SELECT 1 FROM order_items WHERE promotion_id = $1)"
+        )
+        .bind(promotion_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let outcome = if deleted.rows_affected() > 0 {
+            Some("Promotion deleted")
+        } else {
+            let deactivated = sqlx::query("UPDATE promotions SET is_active = false, updated_at = NOW() WHERE id = $1")
+                .bind(promotion_id)
+                .execute(&mut *tx)
+                .await?;
+            (deactivated.rows_affected() > 0).then_some("Promotion has sales and was deactivated instead")
+        };
+
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(outcome)
+    }
+    .await;
+
+    match result {
+        Ok(Some(message)) => Ok(HttpResponse::Ok().json(message)),
+        Ok(None) => Ok(HttpResponse::NotFound().json("Promotion not found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to delete promotion"))
+        }
+    }
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_active_promotions);
+}
+
+pub fn admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_promotions)
+        .service(get_promotion)
+        .service(create_promotion)
+        .service(update_promotion)
+        .service(delete_promotion);
+}
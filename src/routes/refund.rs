@@ -0,0 +1,115 @@
+use actix_web::{get, put, web, HttpResponse, Responder, Result};
+use sqlx::PgPool;
+use crate::models::refund::*;
+use crate::models::user::Claims;
+
+// Admin routes
+#[get("/refunds")]
+async fn get_refunds(
+    pool: web::Data<PgPool>,
+    query: web::Query<RefundListQuery>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    if query.status.as_deref().is_some_and(|s| s != PENDING && s != COMPLETED) {
+        return Ok(HttpResponse::BadRequest().json("status must be one of: pending, completed"));
+    }
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    match sqlx::query_as::<_, Refund>(
+        "SELECT * FROM refunds WHERE ($1::VARCHAR IS NULL OR status = $1)
+         ORDER BY created_at DESC, id DESC LIMIT $2 OFFSET $3"
+    )
+    .bind(&query.status)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(pool.get_ref())
+    .await {
+        Ok(refunds) => Ok(HttpResponse::Ok().json(refunds)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch refunds"))
+        }
+    }
+}
+
+// Record that the money was sent back. Once the completed refunds cover
+// the order total, or all refunds of a fully returned order are paid, its
+// payment status becomes Refunded.
+#[put("/refunds/{id}/complete")]
+async fn complete_refund(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    request: web::Json<CompleteRefundRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let admin_id: i32 = claims.sub.parse().unwrap();
+    let reference = request.reference.trim();
+    if reference.is_empty() {
+        return Ok(HttpResponse::BadRequest().json("reference is required"));
+    }
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+
+        let refund = sqlx::query_as::<_, Refund>(
+            "UPDATE refunds SET status = $2, reference = $3, processed_by = $4, processed_at = NOW()
+             WHERE id = $1 AND status = $5
+             RETURNING *"
+        )
+        .bind(path.into_inner())
+        .bind(COMPLETED)
+        .bind(reference)
+        .bind(admin_id)
+        .bind(PENDING)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some(refund) = &refund {
+            sqlx::query(
+                "UPDATE orders o SET payment_status = 'refunded', updated_at = NOW()
+                 WHERE o.id = $1 AND (
+                     o.final_amount <= (
+                         SELECT COALESCE(SUM(amount), 0) FROM refunds
+                         WHERE order_id = o.id AND status = $2
+                     )
+                     -- Every item was returned; shipping may have been kept
+                     OR (o.status = 'refunded' AND NOT EXISTS (
+                         SELECT 1 FROM refunds WHERE order_id = o.id AND status <> $2
+                     ))
+                 )"
+            )
+            .bind(refund.order_id)
+            .bind(COMPLETED)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(refund)
+    }
+    .await;
+
+    match result {
+        Ok(Some(refund)) => Ok(HttpResponse::Ok().json(refund)),
+        Ok(None) => Ok(HttpResponse::NotFound().json("No pending refund with this id")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to complete refund"))
+        }
+    }
+}
+
+// Mounted inside the /api/admin scope
+pub fn admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_refunds)
+        .service(complete_refund);
+}
@@ -0,0 +1,667 @@
+use actix_multipart::Multipart;
+use actix_web::{get, post, put, web, HttpResponse, Responder, Result};
+use bigdecimal::{BigDecimal, Zero};
+use chrono::Utc;
+use sqlx::{PgConnection, PgPool};
+use crate::models::order::{Order, OrderStatus};
+use crate::models::refund::Refund;
+use crate::models::returns::*;
+use crate::models::user::Claims;
+use crate::services::{images, order_status, return_rules, returns, storage};
+use crate::services::return_rules::{RefundError, ReturnLineError};
+use crate::services::order_status::{Actor, TransitionError};
+use crate::services::storage::BlobStore;
+use crate::utils::multipart::read_multipart;
+
+async fn fetch_return(
+    conn: &mut PgConnection,
+    return_id: i32,
+    user_id: Option<i32>,
+) -> Result<Option<ReturnRequest>, sqlx::Error> {
+    let request = sqlx::query_as::<_, ReturnRequest>(
+        "SELECT * FROM return_requests WHERE id = $1 AND ($2::INTEGER IS NULL OR user_id = $2)"
+    )
+    .bind(return_id)
+    .bind(user_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    match request {
+        Some(request) => {
+            let mut requests = [request];
+            returns::load_return_details(conn, &mut requests).await?;
+            let [request] = requests;
+            Ok(Some(request))
+        }
+        None => Ok(None),
+    }
+}
+
+async fn list_returns(
+    pool: &PgPool,
+    user_id: Option<i32>,
+    query: &ReturnListQuery,
+) -> Result<Vec<ReturnRequest>, sqlx::Error> {
+    let limit = query.limit.unwrap_or(50).clamp(1, 200);
+    let offset = query.offset.unwrap_or(0).max(0);
+
+    let mut conn = pool.acquire().await?;
+    let mut requests = sqlx::query_as::<_, ReturnRequest>(
+        "SELECT * FROM return_requests
+         WHERE ($1::INTEGER IS NULL OR user_id = $1) AND ($2::VARCHAR IS NULL OR status = $2)
+         ORDER BY created_at DESC, id DESC LIMIT $3 OFFSET $4"
+    )
+    .bind(user_id)
+    .bind(&query.status)
+    .bind(limit)
+    .bind(offset)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    returns::load_return_details(&mut conn, &mut requests).await?;
+    Ok(requests)
+}
+
+// Current status of a return, used to tell a missing return apart from one
+// that is not in the expected status after a guarded UPDATE matched nothing
+async fn return_status(conn: &mut PgConnection, return_id: i32) -> Result<Option<String>, sqlx::Error> {
+    sqlx::query_scalar::<_, String>("SELECT status FROM return_requests WHERE id = $1")
+        .bind(return_id)
+        .fetch_optional(conn)
+        .await
+}
+
+// Upload photos of the defect before submitting the return. The returned
+// ids go into `photo_ids` of the return request.
+#[post("/returns/photos")]
+async fn upload_return_photos(
+    pool: web::Data<PgPool>,
+    store: web::Data<dyn BlobStore>,
+    payload: Multipart,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    let (files, _) = match read_multipart(payload, "file", MAX_RETURN_PHOTOS).await {
+        Ok(parts) => parts,
+        Err(msg) => return Ok(HttpResponse::BadRequest().json(msg)),
+    };
+
+    if files.is_empty() {
+        return Ok(HttpResponse::BadRequest().json("No image file provided (field name: file)"));
+    }
+
+    // Photos uploaded earlier but never attached to a return
+    match sqlx::query_as::<_, (Vec<String>,)>(
+        "DELETE FROM return_photos
+         WHERE user_id = $1 AND return_id IS NULL AND created_at < NOW() - INTERVAL '1 day'
+         RETURNING storage_keys"
+    )
+    .bind(user_id)
+    .fetch_all(pool.get_ref())
+    .await {
+        Ok(abandoned) => {
+            let keys: Vec<String> = abandoned.into_iter().flat_map(|(keys,)| keys).collect();
+            storage::delete_all(store.get_ref(), &keys).await;
+        }
+        Err(e) => eprintln!("Failed to clean up unused return photos: {}", e),
+    }
+
+    let mut stored = Vec::new();
+    for file in files {
+        match images::store_upload(store.get_ref(), &format!("returns/{}", user_id), file.bytes).await {
+            Ok(image) => stored.push(image),
+            Err(msg) => {
+                for image in &stored {
+                    storage::delete_all(store.get_ref(), &image.keys).await;
+                }
+                return Ok(HttpResponse::BadRequest().json(msg));
+            }
+        }
+    }
+
+    let all_keys: Vec<String> = stored.iter().flat_map(|image| image.keys.clone()).collect();
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+
+        let mut created = Vec::new();
+        for image in stored {
+            let photo = sqlx::query_as::<_, ReturnPhoto>(
+                "INSERT INTO return_photos (user_id, image_url, webp_url, thumbnail_url, width, height, storage_keys, created_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, NOW())
+                 RETURNING *"
+            )
+            .bind(user_id)
+            .bind(image.url)
+            .bind(image.webp_url)
+            .bind(image.thumbnail_url)
+            .bind(image.width)
+            .bind(image.height)
+            .bind(image.keys)
+            .fetch_one(&mut *tx)
+            .await?;
+            created.push(photo);
+        }
+
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(created)
+    }
+    .await;
+
+    match result {
+        Ok(created) => Ok(HttpResponse::Created().json(created)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            storage::delete_all(store.get_ref(), &all_keys).await;
+            Ok(HttpResponse::InternalServerError().json("Failed to save return photos"))
+        }
+    }
+}
+
+// Ask to send back some of the lines of a delivered order
+#[post("/orders/{id}/returns")]
+async fn create_return(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    request: web::Json<CreateReturnRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+    let order_id = path.into_inner();
+
+    if !REASONS.contains(&request.reason.as_str()) {
+        return Ok(HttpResponse::BadRequest().json(format!("reason must be one of: {}", REASONS.join(", "))));
+    }
+    if request.items.is_empty() {
+        return Ok(HttpResponse::BadRequest().json("Select at least one item to return"));
+    }
+    if request.items.iter().any(|item| item.quantity <= 0) {
+        return Ok(HttpResponse::BadRequest().json("Quantities must be positive"));
+    }
+    let mut line_ids: Vec<i32> = request.items.iter().map(|item| item.order_item_id).collect();
+    line_ids.sort_unstable();
+    line_ids.dedup();
+    if line_ids.len() != request.items.len() {
+        return Ok(HttpResponse::BadRequest().json("Each order item can only be listed once"));
+    }
+
+    // Drop repeated ids but keep the submitted order for sort_order
+    let mut photo_ids: Vec<i32> = Vec::new();
+    for id in request.photo_ids.clone().unwrap_or_default() {
+        if !photo_ids.contains(&id) {
+            photo_ids.push(id);
+        }
+    }
+    if photo_ids.len() > MAX_RETURN_PHOTOS {
+        return Ok(HttpResponse::BadRequest().json(format!("A return can have at most {} photos", MAX_RETURN_PHOTOS)));
+    }
+    if photo_ids.is_empty() && !REASONS_WITHOUT_PHOTOS.contains(&request.reason.as_str()) {
+        return Ok(HttpResponse::BadRequest().json("Add at least one photo showing the problem"));
+    }
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Failed to start transaction"));
+        }
+    };
+
+    // Locking the order serializes return requests for it
+    let order = match sqlx::query_as::<_, Order>(
+        "SELECT * FROM orders WHERE id = $1 AND user_id = $2 FOR UPDATE"
+    )
+    .bind(order_id)
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await {
+        Ok(Some(order)) => order,
+        Ok(None) => return Ok(HttpResponse::NotFound().json("Order not found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Database error"));
+        }
+    };
+
+    if order.status != OrderStatus::Delivered {
+        return Ok(HttpResponse::Conflict().json("Only delivered orders can be returned"));
+    }
+    let window_days = return_rules::return_window_days();
+    if !return_rules::within_return_window(order.delivered_at, Utc::now().naive_utc(), window_days) {
+        return Ok(HttpResponse::Conflict().json(format!(
+            "Returns are only possible within {} days of delivery",
+            window_days
+        )));
+    }
+
+    let lines: Vec<(i32, i32)> = request.items.iter().map(|item| (item.order_item_id, item.quantity)).collect();
+    match return_rules::check_return_lines(&mut tx, order_id, &lines).await {
+        Ok(Ok(())) => {}
+        Ok(Err(ReturnLineError::NotInOrder(order_item_id))) => {
+            return Ok(HttpResponse::BadRequest().json(format!("Item {} is not part of this order", order_item_id)));
+        }
+        Ok(Err(ReturnLineError::TooMany { order_item_id, available })) => {
+            return Ok(HttpResponse::BadRequest().json(format!(
+                "Only {} of item {} can still be returned",
+                available, order_item_id
+            )));
+        }
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Database error"));
+        }
+    }
+
+    if !photo_ids.is_empty() {
+        match sqlx::query_as::<_, (i64,)>(
+            "SELECT COUNT(*) FROM return_photos WHERE id = ANY($1) AND user_id = $2 AND return_id IS NULL"
+        )
+        .bind(&photo_ids)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await {
+            Ok((count,)) if count as usize == photo_ids.len() => {}
+            Ok(_) => return Ok(HttpResponse::BadRequest().json("Unknown or already used photo ids")),
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                return Ok(HttpResponse::InternalServerError().json("Database error"));
+            }
+        }
+    }
+
+    let result = async {
+        let (return_id,): (i32,) = sqlx::query_as(
+            "INSERT INTO return_requests (order_id, user_id, reason, description)
+             VALUES ($1, $2, $3, $4)
+             RETURNING id"
+        )
+        .bind(order_id)
+        .bind(user_id)
+        .bind(&request.reason)
+        .bind(request.description.as_deref().map(str::trim).filter(|d| !d.is_empty()))
+        .fetch_one(&mut *tx)
+        .await?;
+
+        for item in &request.items {
+            sqlx::query("INSERT INTO return_items (return_id, order_item_id, quantity) VALUES ($1, $2, $3)")
+                .bind(return_id)
+                .bind(item.order_item_id)
+                .bind(item.quantity)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        // Repeats the unattached check, since another return may have taken
+        // a photo since it was checked
+        let attached = sqlx::query(
+            "UPDATE return_photos rp SET return_id = $1, sort_order = ids.position
+             FROM UNNEST($2::INTEGER[]) WITH ORDINALITY AS ids(id, position)
+             WHERE rp.id = ids.id AND rp.return_id IS NULL AND rp.user_id = $3"
+        )
+        .bind(return_id)
+        .bind(&photo_ids)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+        if attached.rows_affected() as usize != photo_ids.len() {
+            return Ok(None);
+        }
+
+        let created = fetch_return(&mut tx, return_id, None).await?.ok_or(sqlx::Error::RowNotFound)?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(Some(created))
+    }
+    .await;
+
+    match result {
+        Ok(Some(created)) => Ok(HttpResponse::Created().json(created)),
+        Ok(None) => Ok(HttpResponse::BadRequest().json("Unknown or already used photo ids")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to create return"))
+        }
+    }
+}
+
+#[get("/returns")]
+async fn get_user_returns(
+    pool: web::Data<PgPool>,
+    query: web::Query<ReturnListQuery>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    match list_returns(pool.get_ref(), Some(user_id), &query).await {
+        Ok(requests) => Ok(HttpResponse::Ok().json(requests)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch returns"))
+        }
+    }
+}
+
+#[get("/returns/{id}")]
+async fn get_user_return(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    let result = async {
+        let mut conn = pool.acquire().await?;
+        fetch_return(&mut conn, path.into_inner(), Some(user_id)).await
+    }
+    .await;
+
+    match result {
+        Ok(Some(request)) => Ok(HttpResponse::Ok().json(request)),
+        Ok(None) => Ok(HttpResponse::NotFound().json("Return not found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch return"))
+        }
+    }
+}
+
+// Admin routes
+#[get("/returns")]
+async fn get_returns(
+    pool: web::Data<PgPool>,
+    query: web::Query<ReturnListQuery>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+    if query.status.as_deref().is_some_and(|s| !STATUSES.contains(&s)) {
+        return Ok(HttpResponse::BadRequest().json(format!("status must be one of: {}", STATUSES.join(", "))));
+    }
+
+    match list_returns(pool.get_ref(), None, &query).await {
+        Ok(requests) => Ok(HttpResponse::Ok().json(requests)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch returns"))
+        }
+    }
+}
+
+#[get("/returns/{id}")]
+async fn get_return(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let result = async {
+        let mut conn = pool.acquire().await?;
+        fetch_return(&mut conn, path.into_inner(), None).await
+    }
+    .await;
+
+    match result {
+        Ok(Some(request)) => Ok(HttpResponse::Ok().json(request)),
+        Ok(None) => Ok(HttpResponse::NotFound().json("Return not found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch return"))
+        }
+    }
+}
+
+#[put("/returns/{id}/review")]
+async fn review_return(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    request: web::Json<ReviewReturnRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let admin_id: i32 = claims.sub.parse().unwrap();
+    let return_id = path.into_inner();
+    let note = request.note.as_deref().map(str::trim).filter(|n| !n.is_empty());
+    if !request.approve && note.is_none() {
+        return Ok(HttpResponse::BadRequest().json("Give the buyer a reason when rejecting a return"));
+    }
+    let to = if request.approve { APPROVED } else { REJECTED };
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+
+        let updated = sqlx::query_as::<_, ReturnRequest>(
+            "UPDATE return_requests
+             SET status = $3, admin_note = COALESCE($4, admin_note), reviewed_by = $5, reviewed_at = NOW()
+             WHERE id = $1 AND status = $2
+             RETURNING *"
+        )
+        .bind(return_id)
+        .bind(PENDING)
+        .bind(to)
+        .bind(note)
+        .bind(admin_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        if let Some(updated) = &updated {
+            let (title, message) = if request.approve {
+                (
+                    format!("Return #{} approved", updated.id),
+                    "Please send the items back to us; we'll refund you once they arrive.".to_string(),
+                )
+            } else {
+                (format!("Return #{} rejected", updated.id), note.unwrap_or_default().to_string())
+            };
+            returns::notify_buyer(&mut tx, updated, &title, &message).await?;
+        }
+
+        let updated = match updated {
+            Some(updated) => Ok(updated),
+            None => Err(return_status(&mut tx, return_id).await?),
+        };
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(updated)
+    }
+    .await;
+
+    match result {
+        Ok(Ok(updated)) => Ok(HttpResponse::Ok().json(updated)),
+        Ok(Err(None)) => Ok(HttpResponse::NotFound().json("Return not found")),
+        Ok(Err(Some(status))) => Ok(HttpResponse::Conflict().json(format!("Return is already {}", status))),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to review return"))
+        }
+    }
+}
+
+// The parcel came back. Its units go back on sale unless `restock` is false,
+// e.g. when the item is too damaged to sell again.
+#[put("/returns/{id}/receive")]
+async fn receive_return(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    request: web::Json<ReceiveReturnRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let return_id = path.into_inner();
+    let restock = request.restock.unwrap_or(true);
+    let note = request.note.as_deref().map(str::trim).filter(|n| !n.is_empty());
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+
+        let updated = sqlx::query_as::<_, ReturnRequest>(
+            "UPDATE return_requests
+             SET status = $3, restocked = $4, admin_note = COALESCE($5, admin_note), received_at = NOW()
+             WHERE id = $1 AND status = $2
+             RETURNING *"
+        )
+        .bind(return_id)
+        .bind(APPROVED)
+        .bind(RECEIVED)
+        .bind(restock)
+        .bind(note)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let updated = match updated {
+            Some(updated) => {
+                if restock {
+                    return_rules::restock_return(&mut tx, return_id).await?;
+                }
+                returns::notify_buyer(
+                    &mut tx,
+                    &updated,
+                    &format!("Return #{} received", updated.id),
+                    "We've received your items and will process your refund shortly.",
+                )
+                .await?;
+                Ok(updated)
+            }
+            None => Err(return_status(&mut tx, return_id).await?),
+        };
+
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(updated)
+    }
+    .await;
+
+    match result {
+        Ok(Ok(updated)) => Ok(HttpResponse::Ok().json(updated)),
+        Ok(Err(None)) => Ok(HttpResponse::NotFound().json("Return not found")),
+        Ok(Err(Some(status))) => Ok(HttpResponse::Conflict().json(format!(
+            "Only approved returns can be received (this one is {})",
+            status
+        ))),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to receive return"))
+        }
+    }
+}
+
+// Record a refund for a received return. The amount defaults to the value
+// of the returned units and can be lowered for a partial refund. Once every
+// unit of a delivered order has been refunded the order becomes Refunded.
+#[post("/returns/{id}/refund")]
+async fn refund_return(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    request: web::Json<RefundReturnRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let admin_id: i32 = claims.sub.parse().unwrap();
+    let return_id = path.into_inner();
+    if request.amount.as_ref().is_some_and(|amount| *amount <= BigDecimal::zero()) {
+        return Ok(HttpResponse::BadRequest().json("amount must be positive"));
+    }
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Failed to start transaction"));
+        }
+    };
+
+    let refunded = match return_rules::refund_return(&mut tx, return_id, request.amount.as_ref(), admin_id).await {
+        Ok(Ok(refunded)) => refunded,
+        Ok(Err(RefundError::NotFound)) => return Ok(HttpResponse::NotFound().json("Return not found")),
+        Ok(Err(RefundError::NotReceived(status))) => {
+            return Ok(HttpResponse::Conflict().json(format!(
+                "Only received returns can be refunded (this one is {})",
+                status
+            )));
+        }
+        Ok(Err(RefundError::AmountTooHigh(refundable))) => {
+            return Ok(HttpResponse::BadRequest().json(format!(
+                "amount exceeds what is left to refund on this order ({})",
+                refundable
+            )));
+        }
+        Ok(Err(RefundError::AlreadyRefunded)) => {
+            return Ok(HttpResponse::Conflict().json("This order has already been refunded in full"));
+        }
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Failed to refund return"));
+        }
+    };
+
+    let result = async {
+        let refund = sqlx::query_as::<_, Refund>("SELECT * FROM refunds WHERE id = $1")
+            .bind(refunded.refund_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        let updated = fetch_return(&mut tx, return_id, None).await?.ok_or(sqlx::Error::RowNotFound)?;
+
+        let order = sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1")
+            .bind(refunded.order_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        let order = if refunded.order_fully_refunded {
+            let note = format!("All items returned (return #{})", return_id);
+            order_status::transition(&mut tx, &order, OrderStatus::Refunded, &Actor::admin(admin_id), Some(&note)).await?
+        } else {
+            order
+        };
+
+        returns::notify_buyer(
+            &mut tx,
+            &updated,
+            &format!("Return #{} refunded", updated.id),
+            &format!("A refund of Rp {} is on its way to you.", refunded.amount),
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok::<_, TransitionError>(serde_json::json!({
+            "return": updated,
+            "refund": refund,
+            "order": order,
+        }))
+    }
+    .await;
+
+    match result {
+        Ok(body) => Ok(HttpResponse::Created().json(body)),
+        Err(e @ TransitionError::NotAllowed { .. }) => Ok(HttpResponse::Conflict().json(e.to_string())),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to refund return"))
+        }
+    }
+}
+
+// Mounted inside the /api/auth scope
+pub fn user_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(upload_return_photos)
+        .service(create_return)
+        .service(get_user_returns)
+        .service(get_user_return);
+}
+
+// Mounted inside the /api/admin scope
+pub fn admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_returns)
+        .service(get_return)
+        .service(review_return)
+        .service(receive_return)
+        .service(refund_return);
+}
@@ -0,0 +1,741 @@
+use actix_multipart::Multipart;
+use actix_web::{get, post, put, delete, web, HttpResponse, Responder, Result};
+use serde_json::json;
+use sqlx::{PgConnection, PgPool};
+use crate::models::product::{
+    CreateReviewRequest, ModerateReviewRequest, Review, ReviewModerationQuery, ReviewPhoto, ReviewQuery,
+    UpdateReviewRequest, MAX_REVIEW_PHOTOS,
+};
+use crate::models::user::Claims;
+use crate::services::images;
+use crate::services::storage::{self, BlobStore};
+use crate::utils::multipart::read_multipart;
+
+const REVIEW_SELECT: &str = "SELECT r.*, u.name AS user_name FROM reviews r JOIN users u ON r.user_id = u.id";
+
+// Fill in `photos` for a page of reviews with a single query
+async fn load_review_photos(conn: &mut PgConnection, reviews: &mut [Review]) -> Result<(), sqlx::Error> {
+    let review_ids: Vec<i32> = reviews.iter().map(|r| r.id).collect();
+    if review_ids.is_empty() {
+        return Ok(());
+    }
+
+    let photos = sqlx::query_as::<_, ReviewPhoto>(
+        "SELECT * FROM review_photos WHERE review_id = ANY($1) ORDER BY sort_order, id"
+    )
+    .bind(&review_ids)
+    .fetch_all(conn)
+    .await?;
+
+    for photo in photos {
+        if let Some(review) = reviews.iter_mut().find(|r| Some(r.id) == photo.review_id) {
+            review.photos.push(photo);
+        }
+    }
+
+    Ok(())
+}
+
+pub(crate) async fn fetch_review(conn: &mut PgConnection, review_id: i32) -> Result<Option<Review>, sqlx::Error> {
+    let review = sqlx::query_as::<_, Review>(&format!("{} WHERE r.id = $1", REVIEW_SELECT))
+        .bind(review_id)
+        .fetch_optional(&mut *conn)
+        .await?;
+
+    match review {
+        Some(review) => {
+            let mut reviews = [review];
+            load_review_photos(conn, &mut reviews).await?;
+            let [review] = reviews;
+            Ok(Some(review))
+        }
+        None => Ok(None),
+    }
+}
+
+// Recompute the rating summary on the product from its approved reviews.
+// Call inside the transaction that changed the reviews.
+pub(crate) async fn refresh_product_rating(conn: &mut PgConnection, product_id: i32) -> Result<(), sqlx::Error> {
+    // Lock first so concurrent review changes are applied one after another
+    sqlx::query("SELECT id FROM products WHERE id = $1 FOR UPDATE")
+        .bind(product_id)
+        .execute(&mut *conn)
+        .await?;
+
+    sqlx::query(
+        "UPDATE products SET
+             average_rating = COALESCE(s.average_rating, 0),
+             review_count = s.review_count,
+             rating_histogram = s.rating_histogram
+         FROM (
+             SELECT ROUND(AVG(rating), 2) AS average_rating,
+                    COUNT(*)::INTEGER AS review_count,
+                    ARRAY[
+                        COUNT(*) FILTER (WHERE rating = 1)::INTEGER,
+                        COUNT(*) FILTER (WHERE rating = 2)::INTEGER,
+                        COUNT(*) FILTER (WHERE rating = 3)::INTEGER,
+                        COUNT(*) FILTER (WHERE rating = 4)::INTEGER,
+                        COUNT(*) FILTER (WHERE rating = 5)::INTEGER
+                    ] AS rating_histogram
+             FROM reviews
+             WHERE product_id = $1 AND status = 'approved'
+         ) s
+         WHERE id = $1"
+    )
+    .bind(product_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+fn validate_rating(rating: i32) -> Result<(), &'static str> {
+    if (1..=5).contains(&rating) {
+        Ok(())
+    } else {
+        Err("Rating must be between 1 and 5")
+    }
+}
+
+#[get("/products/{id}/reviews")]
+async fn get_product_reviews(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    query: web::Query<ReviewQuery>,
+) -> Result<impl Responder> {
+    let product_id = path.into_inner();
+
+    let order_by = match query.sort.as_deref().unwrap_or("newest") {
+        "newest" => "r.created_at DESC",
+        "helpful" => "r.helpful_count DESC, r.created_at DESC",
+        "rating_high" => "r.rating DESC, r.created_at DESC",
+        "rating_low" => "r.rating ASC, r.created_at DESC",
+        _ => return Ok(HttpResponse::BadRequest().json("Invalid sort, expected one of: newest, helpful, rating_high, rating_low")),
+    };
+
+    let photo_filter = if query.with_photos.unwrap_or(false) {
+        " AND EXISTS (SELECT 1 FROM review_photos rp WHERE rp.review_id = r.id)"
+    } else {
+        ""
+    };
+
+    // Only moderated reviews are public
+    let reviews = async {
+        let mut conn = pool.acquire().await?;
+
+        let mut reviews = sqlx::query_as::<_, Review>(&format!(
+            "{} WHERE r.product_id = $1 AND r.status = 'approved'{} ORDER BY {}",
+            REVIEW_SELECT, photo_filter, order_by
+        ))
+        .bind(product_id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+        load_review_photos(&mut conn, &mut reviews).await?;
+        Ok::<_, sqlx::Error>(reviews)
+    }
+    .await;
+
+    let reviews = match reviews {
+        Ok(reviews) => reviews,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Failed to fetch reviews"));
+        }
+    };
+
+    Ok(HttpResponse::Ok().json(reviews))
+}
+
+#[post("/products/{id}/reviews")]
+async fn create_review(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    review_data: web::Json<CreateReviewRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let product_id = path.into_inner();
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    if let Err(message) = validate_rating(review_data.rating) {
+        return Ok(HttpResponse::BadRequest().json(message));
+    }
+
+    let product_exists = match sqlx::query_as::<_, (i32,)>("SELECT id FROM products WHERE id = $1 AND is_active = true")
+        .bind(product_id)
+        .fetch_optional(pool.get_ref())
+        .await {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                return Ok(HttpResponse::InternalServerError().json("Database error"));
+            }
+        };
+
+    if product_exists.is_none() {
+        return Ok(HttpResponse::NotFound().json("Product not found"));
+    }
+
+    // Check if user has already reviewed this product
+    let existing_review = match sqlx::query_as::<_, (i32,)>("SELECT id FROM reviews WHERE product_id = $1 AND user_id = $2")
+        .bind(product_id)
+        .bind(user_id)
+        .fetch_optional(pool.get_ref())
+        .await {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                return Ok(HttpResponse::InternalServerError().json("Database error"));
+            }
+        };
+
+    if existing_review.is_some() {
+        return Ok(HttpResponse::BadRequest().json("You have already reviewed this product"));
+    }
+
+    // Drop repeated ids but keep the submitted order for sort_order
+    let mut photo_ids: Vec<i32> = Vec::new();
+    for id in review_data.photo_ids.clone().unwrap_or_default() {
+        if !photo_ids.contains(&id) {
+            photo_ids.push(id);
+        }
+    }
+    if photo_ids.len() > MAX_REVIEW_PHOTOS {
+        return Ok(HttpResponse::BadRequest().json(format!("A review can have at most {} photos", MAX_REVIEW_PHOTOS)));
+    }
+
+    if !photo_ids.is_empty() {
+        let available = sqlx::query_as::<_, (i64,)>(
+            "SELECT COUNT(*) FROM review_photos WHERE id = ANY($1) AND user_id = $2 AND review_id IS NULL"
+        )
+        .bind(&photo_ids)
+        .bind(user_id)
+        .fetch_one(pool.get_ref())
+        .await;
+
+        match available {
+            Ok((count,)) if count as usize == photo_ids.len() => {}
+            Ok(_) => return Ok(HttpResponse::BadRequest().json("Unknown or already used photo ids")),
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                return Ok(HttpResponse::InternalServerError().json("Database error"));
+            }
+        }
+    }
+
+    // New reviews are verified when the user received the product, and wait
+    // in the moderation queue before they are shown
+    let result = async {
+        let mut tx = pool.begin().await?;
+
+        let (review_id,): (i32,) = sqlx::query_as(
+            "INSERT INTO reviews (product_id, user_id, rating, comment, is_verified, status, created_at, updated_at)
+             VALUES ($1, $2, $3, $4, EXISTS (
+                 SELECT 1 FROM orders o JOIN order_items oi ON oi.order_id = o.id
+                 WHERE o.user_id = $2 AND oi.product_id = $1 AND o.status = 'delivered'
+             ), 'pending', NOW(), NOW())
+             RETURNING id"
+        )
+        .bind(product_id)
+        .bind(user_id)
+        .bind(review_data.rating)
+        .bind(&review_data.comment)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        // Photos keep the order they were listed in
+        sqlx::query(
+            "UPDATE review_photos rp SET review_id = $1, sort_order = ids.position
+             FROM UNNEST($2::INTEGER[]) WITH ORDINALITY AS ids(id, position)
+             WHERE rp.id = ids.id AND rp.user_id = $3 AND rp.review_id IS NULL"
+        )
+        .bind(review_id)
+        .bind(&photo_ids)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let review = fetch_review(&mut tx, review_id).await?;
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(review)
+    }
+    .await;
+
+    match result {
+        Ok(Some(review)) => Ok(HttpResponse::Created().json(review)),
+        Ok(None) => Ok(HttpResponse::InternalServerError().json("Failed to create review")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to create review"))
+        }
+    }
+}
+
+// Load a review for its author; the error response is ready to return
+async fn own_review(pool: &PgPool, review_id: i32, user_id: i32) -> Result<Review, HttpResponse> {
+    let mut conn = match pool.acquire().await {
+        Ok(conn) => conn,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Err(HttpResponse::InternalServerError().json("Database error"));
+        }
+    };
+
+    match fetch_review(&mut conn, review_id).await {
+        Ok(Some(review)) if review.user_id == user_id => Ok(review),
+        Ok(Some(_)) => Err(HttpResponse::Forbidden().json("You can only change your own review")),
+        Ok(None) => Err(HttpResponse::NotFound().json("Review not found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Err(HttpResponse::InternalServerError().json("Database error"))
+        }
+    }
+}
+
+#[put("/reviews/{id}")]
+async fn update_review(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    review_data: web::Json<UpdateReviewRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let review_id = path.into_inner();
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    if let Some(rating) = review_data.rating {
+        if let Err(message) = validate_rating(rating) {
+            return Ok(HttpResponse::BadRequest().json(message));
+        }
+    }
+
+    let review = match own_review(pool.get_ref(), review_id, user_id).await {
+        Ok(review) => review,
+        Err(response) => return Ok(response),
+    };
+
+    // Edited text has to be moderated again
+    let result = async {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query(
+            "UPDATE reviews SET
+             rating = COALESCE($1, rating),
+             comment = COALESCE($2, comment),
+             status = 'pending',
+             moderation_reason = NULL,
+             moderated_by = NULL,
+             moderated_at = NULL,
+             updated_at = NOW()
+             WHERE id = $3"
+        )
+        .bind(review_data.rating)
+        .bind(&review_data.comment)
+        .bind(review_id)
+        .execute(&mut *tx)
+        .await?;
+
+        refresh_product_rating(&mut tx, review.product_id).await?;
+        let review = fetch_review(&mut tx, review_id).await?;
+
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(review)
+    }
+    .await;
+
+    match result {
+        Ok(Some(review)) => Ok(HttpResponse::Ok().json(review)),
+        Ok(None) => Ok(HttpResponse::NotFound().json("Review not found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to update review"))
+        }
+    }
+}
+
+#[delete("/reviews/{id}")]
+async fn delete_review(
+    pool: web::Data<PgPool>,
+    store: web::Data<dyn BlobStore>,
+    path: web::Path<i32>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let review_id = path.into_inner();
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    let review = match own_review(pool.get_ref(), review_id, user_id).await {
+        Ok(review) => review,
+        Err(response) => return Ok(response),
+    };
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+
+        sqlx::query("DELETE FROM reviews WHERE id = $1 AND user_id = $2")
+            .bind(review_id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        refresh_product_rating(&mut tx, review.product_id).await?;
+
+        tx.commit().await
+    }
+    .await;
+
+    match result {
+        Ok(()) => {
+            // The photo rows went with the review; remove the files too
+            let keys: Vec<String> = review.photos.into_iter().flat_map(|photo| photo.storage_keys).collect();
+            storage::delete_all(store.get_ref(), &keys).await;
+            Ok(HttpResponse::Ok().json("Review deleted successfully"))
+        }
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to delete review"))
+        }
+    }
+}
+
+// Upload photos for a review that is about to be submitted. The returned
+// ids go into `photo_ids` of the create request.
+#[post("/reviews/photos")]
+async fn upload_review_photos(
+    pool: web::Data<PgPool>,
+    store: web::Data<dyn BlobStore>,
+    payload: Multipart,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    let (files, _) = match read_multipart(payload, "file", MAX_REVIEW_PHOTOS).await {
+        Ok(parts) => parts,
+        Err(msg) => return Ok(HttpResponse::BadRequest().json(msg)),
+    };
+
+    if files.is_empty() {
+        return Ok(HttpResponse::BadRequest().json("No image file provided (field name: file)"));
+    }
+
+    // Photos uploaded earlier but never attached to a review
+    match sqlx::query_as::<_, (Vec<String>,)>(
+        "DELETE FROM review_photos
+         WHERE user_id = $1 AND review_id IS NULL AND created_at < NOW() - INTERVAL '1 day'
+         RETURNING storage_keys"
+    )
+    .bind(user_id)
+    .fetch_all(pool.get_ref())
+    .await {
+        Ok(abandoned) => {
+            let keys: Vec<String> = abandoned.into_iter().flat_map(|(keys,)| keys).collect();
+            storage::delete_all(store.get_ref(), &keys).await;
+        }
+        Err(e) => eprintln!("Failed to clean up unused review photos: {}", e),
+    }
+
+    let mut stored = Vec::new();
+    for file in files {
+        match images::store_upload(store.get_ref(), &format!("reviews/{}", user_id), file.bytes).await {
+            Ok(image) => stored.push(image),
+            Err(msg) => {
+                for image in &stored {
+                    storage::delete_all(store.get_ref(), &image.keys).await;
+                }
+                return Ok(HttpResponse::BadRequest().json(msg));
+            }
+        }
+    }
+
+    let all_keys: Vec<String> = stored.iter().flat_map(|image| image.keys.clone()).collect();
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+
+        let mut created = Vec::new();
+        for image in stored {
+            let photo = sqlx::query_as::<_, ReviewPhoto>(
+                "INSERT INTO review_photos (user_id, image_url, webp_url, thumbnail_url, width, height, storage_keys, created_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, NOW())
+                 RETURNING *"
+            )
+            .bind(user_id)
+            .bind(image.url)
+            .bind(image.webp_url)
+            .bind(image.thumbnail_url)
+            .bind(image.width)
+            .bind(image.height)
+            .bind(image.keys)
+            .fetch_one(&mut *tx)
+            .await?;
+            created.push(photo);
+        }
+
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(created)
+    }
+    .await;
+
+    match result {
+        Ok(created) => Ok(HttpResponse::Created().json(created)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            storage::delete_all(store.get_ref(), &all_keys).await;
+            Ok(HttpResponse::InternalServerError().json("Failed to save review photos"))
+        }
+    }
+}
+
+#[post("/reviews/{id}/helpful")]
+async fn mark_review_helpful(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let review_id = path.into_inner();
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    let review = match sqlx::query_as::<_, (i32, String)>("SELECT user_id, status FROM reviews WHERE id = $1")
+        .bind(review_id)
+        .fetch_optional(pool.get_ref())
+        .await {
+            Ok(review) => review,
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                return Ok(HttpResponse::InternalServerError().json("Database error"));
+            }
+        };
+
+    match review {
+        Some((_, status)) if status != "approved" => return Ok(HttpResponse::NotFound().json("Review not found")),
+        Some((author_id, _)) if author_id == user_id => {
+            return Ok(HttpResponse::BadRequest().json("You cannot vote on your own review"));
+        }
+        Some(_) => {}
+        None => return Ok(HttpResponse::NotFound().json("Review not found")),
+    }
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+
+        let inserted = sqlx::query(
+            "INSERT INTO review_helpful_votes (review_id, user_id, created_at) VALUES ($1, $2, NOW())
+             ON CONFLICT (review_id, user_id) DO NOTHING"
+        )
+        .bind(review_id)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await?;
+
+        let (helpful_count,): (i32,) = sqlx::query_as(
+            "UPDATE reviews SET helpful_count = helpful_count + $1 WHERE id = $2 RETURNING helpful_count"
+        )
+        .bind(inserted.rows_affected() as i32)
+        .bind(review_id)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(helpful_count)
+    }
+    .await;
+
+    match result {
+        Ok(helpful_count) => Ok(HttpResponse::Ok().json(json!({ "review_id": review_id, "helpful_count": helpful_count }))),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to record vote"))
+        }
+    }
+}
+
+#[delete("/reviews/{id}/helpful")]
+async fn unmark_review_helpful(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let review_id = path.into_inner();
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+
+        let deleted = sqlx::query("DELETE FROM review_helpful_votes WHERE review_id = $1 AND user_id = $2")
+            .bind(review_id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        let helpful_count = sqlx::query_as::<_, (i32,)>(
+            "UPDATE reviews SET helpful_count = GREATEST(helpful_count - $1, 0) WHERE id = $2 RETURNING helpful_count"
+        )
+        .bind(deleted.rows_affected() as i32)
+        .bind(review_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(helpful_count.map(|(count,)| count))
+    }
+    .await;
+
+    match result {
+        Ok(Some(helpful_count)) => Ok(HttpResponse::Ok().json(json!({ "review_id": review_id, "helpful_count": helpful_count }))),
+        Ok(None) => Ok(HttpResponse::NotFound().json("Review not found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to remove vote"))
+        }
+    }
+}
+
+// Admin routes
+#[get("/reviews")]
+async fn get_moderation_queue(
+    pool: web::Data<PgPool>,
+    query: web::Query<ReviewModerationQuery>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let status = query.status.as_deref().unwrap_or("pending");
+    if !["pending", "approved", "hidden"].contains(&status) {
+        return Ok(HttpResponse::BadRequest().json("Invalid status, expected one of: pending, approved, hidden"));
+    }
+
+    // Oldest first so the queue is worked through in order; with photos, so
+    // moderators see what they are judging
+    let reviews = async {
+        let mut conn = pool.acquire().await?;
+
+        let mut reviews = sqlx::query_as::<_, Review>(&format!("{} WHERE r.status = $1 ORDER BY r.created_at ASC", REVIEW_SELECT))
+            .bind(status)
+            .fetch_all(&mut *conn)
+            .await?;
+
+        load_review_photos(&mut conn, &mut reviews).await?;
+        Ok::<_, sqlx::Error>(reviews)
+    }
+    .await;
+
+    match reviews {
+        Ok(reviews) => Ok(HttpResponse::Ok().json(reviews)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch reviews"))
+        }
+    }
+}
+
+#[put("/reviews/{id}/moderation")]
+async fn moderate_review(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    moderation: web::Json<ModerateReviewRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let review_id = path.into_inner();
+    let admin_id: i32 = claims.sub.parse().unwrap();
+    let reason = moderation.reason.as_deref().map(str::trim).filter(|r| !r.is_empty());
+
+    let status = match moderation.action.as_str() {
+        "approve" => "approved",
+        "hide" if reason.is_none() => return Ok(HttpResponse::BadRequest().json("A reason is required when hiding a review")),
+        "hide" => "hidden",
+        _ => return Ok(HttpResponse::BadRequest().json("Invalid action, expected approve or hide")),
+    };
+
+    let result = async {
+        let mut tx = pool.begin().await?;
+
+        let product_id = sqlx::query_as::<_, (i32,)>(
+            "UPDATE reviews SET status = $1, moderation_reason = $2, moderated_by = $3, moderated_at = NOW()
+             WHERE id = $4
+             RETURNING product_id"
+        )
+        .bind(status)
+        .bind(reason)
+        .bind(admin_id)
+        .bind(review_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let Some((product_id,)) = product_id else {
+            return Ok(None);
+        };
+
+        refresh_product_rating(&mut tx, product_id).await?;
+        let review = fetch_review(&mut tx, review_id).await?;
+
+        tx.commit().await?;
+        Ok::<_, sqlx::Error>(review)
+    }
+    .await;
+
+    match result {
+        Ok(Some(review)) => Ok(HttpResponse::Ok().json(review)),
+        Ok(None) => Ok(HttpResponse::NotFound().json("Review not found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to moderate review"))
+        }
+    }
+}
+
+#[delete("/reviews/{id}/photos/{photo_id}")]
+async fn remove_review_photo(
+    pool: web::Data<PgPool>,
+    store: web::Data<dyn BlobStore>,
+    path: web::Path<(i32, i32)>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let (review_id, photo_id) = path.into_inner();
+
+    match sqlx::query_as::<_, (Vec<String>,)>(
+        "DELETE FROM review_photos WHERE id = $1 AND review_id = $2 RETURNING storage_keys"
+    )
+    .bind(photo_id)
+    .bind(review_id)
+    .fetch_optional(pool.get_ref())
+    .await {
+        Ok(Some((keys,))) => {
+            storage::delete_all(store.get_ref(), &keys).await;
+            Ok(HttpResponse::Ok().json("Review photo removed"))
+        }
+        Ok(None) => Ok(HttpResponse::NotFound().json("Review photo not found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to remove review photo"))
+        }
+    }
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_product_reviews);
+}
+
+// Mounted inside the authenticated /api scope
+pub fn user_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(create_review)
+        .service(upload_review_photos)
+        .service(update_review)
+        .service(delete_review)
+        .service(mark_review_helpful)
+        .service(unmark_review_helpful);
+}
+
+// Mounted inside the /api/admin scope
+pub fn admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_moderation_queue)
+        .service(moderate_review)
+        .service(remove_review_photo);
+}
@@ -0,0 +1,330 @@
+use actix_web::{get, post, put, web, HttpResponse, Responder, Result};
+use sqlx::PgPool;
+use bigdecimal::BigDecimal;
+use crate::models::order::{Order, OrderStatus};
+use crate::models::shipping::{CreateShipmentRequest, Shipment, UpdateShipmentRequest};
+use crate::models::user::Claims;
+use crate::services::{inventory, order_status, shipping};
+use crate::services::order_status::{Actor, TransitionError};
+use crate::services::tracking::{self, ShipmentTracker, TrackingError};
+
+// Shipments of an order, each with its checkpoint history
+async fn tracked_shipments(
+    pool: &PgPool,
+    tracker: &dyn ShipmentTracker,
+    order_id: i32,
+) -> Result<Vec<serde_json::Value>, sqlx::Error> {
+    let shipments = sqlx::query_as::<_, Shipment>(
+        "SELECT * FROM shipments WHERE order_id = $1 ORDER BY created_at, id"
+    )
+    .bind(order_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut tracked = Vec::with_capacity(shipments.len());
+    for shipment in shipments {
+        let (tracking, message) = match tracking::shipment_tracking(pool, tracker, shipment.id).await {
+            Ok(tracking) => (Some(tracking), None),
+            Err(TrackingError::Database(e)) => return Err(e),
+            Err(TrackingError::NotFound(message)) => (None, Some(message)),
+            Err(TrackingError::Unavailable) => (None, Some("Track this waybill on the courier's website".to_string())),
+            #[cfg(feature = "rajaongkir")]
+            Err(e) => {
+                eprintln!("Tracking error for shipment {}: {}", shipment.id, e);
+                (None, Some("Tracking is temporarily unavailable".to_string()))
+            }
+        };
+        tracked.push(serde_json::json!({
+            "shipment": shipment,
+            "tracking": tracking,
+            "tracking_message": message,
+        }));
+    }
+
+    Ok(tracked)
+}
+
+#[get("/orders/{id}/tracking")]
+async fn get_order_tracking(
+    pool: web::Data<PgPool>,
+    tracker: web::Data<dyn ShipmentTracker>,
+    path: web::Path<i32>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+    let order_id = path.into_inner();
+
+    match sqlx::query_scalar::<_, i32>("SELECT id FROM orders WHERE id = $1 AND user_id = $2")
+        .bind(order_id)
+        .bind(user_id)
+        .fetch_optional(pool.get_ref())
+        .await
+    {
+        Ok(Some(_)) => {}
+        Ok(None) => return Ok(HttpResponse::NotFound().json("Order not found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Database error"));
+        }
+    }
+
+    match tracked_shipments(pool.get_ref(), tracker.get_ref(), order_id).await {
+        Ok(shipments) => Ok(HttpResponse::Ok().json(shipments)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch shipments"))
+        }
+    }
+}
+
+// Admin routes
+#[get("/orders/{id}/shipments")]
+async fn get_order_shipments(
+    pool: web::Data<PgPool>,
+    tracker: web::Data<dyn ShipmentTracker>,
+    path: web::Path<i32>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    match tracked_shipments(pool.get_ref(), tracker.get_ref(), path.into_inner()).await {
+        Ok(shipments) => Ok(HttpResponse::Ok().json(shipments)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch shipments"))
+        }
+    }
+}
+
+// Register a parcel with its waybill. The first shipment marks the order
+// Shipped, settles its stock reservations and tells the buyer the waybill.
+#[post("/orders/{id}/shipments")]
+async fn create_shipment(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    request: web::Json<CreateShipmentRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let admin_id: i32 = claims.sub.parse().unwrap();
+    let order_id = path.into_inner();
+    let waybill = request.waybill.trim().to_string();
+    if waybill.is_empty() {
+        return Ok(HttpResponse::BadRequest().json("waybill is required"));
+    }
+    if request.weight_grams.is_some_and(|w| w <= 0) {
+        return Ok(HttpResponse::BadRequest().json("weight_grams must be positive"));
+    }
+    if request.cost.as_ref().is_some_and(|c| *c < BigDecimal::from(0)) {
+        return Ok(HttpResponse::BadRequest().json("cost cannot be negative"));
+    }
+
+    let mut tx = match pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Failed to start transaction"));
+        }
+    };
+
+    let order = match sqlx::query_as::<_, Order>("SELECT * FROM orders WHERE id = $1 FOR UPDATE")
+        .bind(order_id)
+        .fetch_optional(&mut *tx)
+        .await
+    {
+        Ok(Some(order)) => order,
+        Ok(None) => return Ok(HttpResponse::NotFound().json("Order not found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Database error"));
+        }
+    };
+
+    // Further parcels can be added to a shipped order; otherwise the order
+    // has to be allowed to move to Shipped
+    if order.status != OrderStatus::Shipped
+        && !order_status::allowed_transitions(order.status).contains(&OrderStatus::Shipped)
+    {
+        let error = TransitionError::NotAllowed { from: order.status, to: OrderStatus::Shipped };
+        return Ok(HttpResponse::Conflict().json(error.to_string()));
+    }
+
+    // The customer's choice at checkout, unless the admin handed the parcel
+    // to a different courier
+    let courier = match request.courier.as_deref().or(order.shipping_courier.as_deref()) {
+        Some(courier) if !courier.trim().is_empty() => courier.trim().to_lowercase(),
+        _ => return Ok(HttpResponse::BadRequest().json("courier is required")),
+    };
+    let service = match &request.service {
+        Some(service) => Some(service.trim().to_string()),
+        None if order.shipping_courier.as_deref() == Some(courier.as_str()) => order.shipping_service.clone(),
+        None => None,
+    };
+
+    let weight_grams = match request.weight_grams {
+        Some(weight) => weight,
+        None => {
+            let lines = match sqlx::query_as::<_, (Option<BigDecimal>, i32)>(
+                "SELECT p.weight, oi.quantity FROM order_items oi
+                 LEFT JOIN products p ON p.id = oi.product_id
+                 WHERE oi.order_id = $1"
+            )
+            .bind(order_id)
+            .fetch_all(&mut *tx)
+            .await
+            {
+                Ok(lines) => lines,
+                Err(e) => {
+                    eprintln!("Database error: {}", e);
+                    return Ok(HttpResponse::InternalServerError().json("Database error"));
+                }
+            };
+            match shipping::parcel_weight_grams(lines.iter().map(|(weight, quantity)| (weight.as_ref(), *quantity))) {
+                Some(total) => total.max(1),
+                None => return Ok(HttpResponse::BadRequest().json("The order is too heavy to weigh, send weight_grams")),
+            }
+        }
+    };
+    let cost = request.cost.clone().unwrap_or_else(|| order.shipping_cost.clone());
+
+    let shipment = match sqlx::query_as::<_, Shipment>(
+        "INSERT INTO shipments (order_id, courier, service, waybill, weight_grams, cost, notes)
+         VALUES ($1, $2, $3, $4, $5, $6, $7)
+         RETURNING *"
+    )
+    .bind(order_id)
+    .bind(&courier)
+    .bind(&service)
+    .bind(&waybill)
+    .bind(weight_grams)
+    .bind(&cost)
+    .bind(&request.notes)
+    .fetch_one(&mut *tx)
+    .await
+    {
+        Ok(shipment) => shipment,
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            return Ok(HttpResponse::Conflict().json("This waybill is already registered"));
+        }
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Failed to create shipment"));
+        }
+    };
+
+    let result = async {
+        let order = if order.status == OrderStatus::Shipped {
+            order
+        } else {
+            let note = format!("{} waybill {}", shipment.courier.to_uppercase(), shipment.waybill);
+            let order = order_status::transition(&mut tx, &order, OrderStatus::Shipped, &Actor::admin(admin_id), Some(&note)).await?;
+            inventory::consume_reservations(&mut tx, order_id).await?;
+            order
+        };
+
+        let carrier = match &shipment.service {
+            Some(service) => format!("{} {}", shipment.courier.to_uppercase(), service),
+            None => shipment.courier.to_uppercase(),
+        };
+        sqlx::query(
+            "INSERT INTO notifications (user_id, title, message, type, reference_id, reference_type,
+                                        action_url, metadata, created_at, updated_at)
+             VALUES ($1, $2, $3, 'order', $4, 'order', $5, $6, NOW(), NOW())"
+        )
+        .bind(order.user_id)
+        .bind(format!("Order {} has shipped", order.order_number))
+        .bind(format!("Your parcel is on its way with {}, waybill {}.", carrier, shipment.waybill))
+        .bind(order_id)
+        .bind(format!("/orders/{}", order_id))
+        .bind(serde_json::json!({
+            "shipment_id": shipment.id,
+            "courier": shipment.courier,
+            "waybill": shipment.waybill,
+        }))
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok::<_, TransitionError>(order)
+    }
+    .await;
+
+    match result {
+        Ok(order) => Ok(HttpResponse::Created().json(serde_json::json!({
+            "shipment": shipment,
+            "order": order,
+        }))),
+        Err(e @ TransitionError::NotAllowed { .. }) => Ok(HttpResponse::Conflict().json(e.to_string())),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to mark order as shipped"))
+        }
+    }
+}
+
+#[put("/shipments/{id}")]
+async fn update_shipment(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    request: web::Json<UpdateShipmentRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    if claims.role != "admin" {
+        return Ok(HttpResponse::Forbidden().json("Admin access required"));
+    }
+
+    let waybill = request.waybill.as_deref().map(str::trim);
+    let courier = request.courier.as_deref().map(|c| c.trim().to_lowercase());
+    if waybill == Some("") || courier.as_deref() == Some("") {
+        return Ok(HttpResponse::BadRequest().json("waybill and courier cannot be empty"));
+    }
+
+    // A different waybill or courier makes the cached history meaningless
+    match sqlx::query_as::<_, Shipment>(
+        "UPDATE shipments SET
+            waybill = COALESCE($2, waybill),
+            courier = COALESCE($3, courier),
+            service = COALESCE($4, service),
+            notes = COALESCE($5, notes),
+            tracking = CASE WHEN $2 IS NULL AND $3 IS NULL THEN tracking END,
+            tracked_at = CASE WHEN $2 IS NULL AND $3 IS NULL THEN tracked_at END,
+            delivered_at = CASE WHEN $2 IS NULL AND $3 IS NULL THEN delivered_at END
+         WHERE id = $1
+         RETURNING *"
+    )
+    .bind(path.into_inner())
+    .bind(waybill)
+    .bind(&courier)
+    .bind(&request.service)
+    .bind(&request.notes)
+    .fetch_optional(pool.get_ref())
+    .await
+    {
+        Ok(Some(shipment)) => Ok(HttpResponse::Ok().json(shipment)),
+        Ok(None) => Ok(HttpResponse::NotFound().json("Shipment not found")),
+        Err(sqlx::Error::Database(e)) if e.is_unique_violation() => {
+            Ok(HttpResponse::Conflict().json("This waybill is already registered"))
+        }
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to update shipment"))
+        }
+    }
+}
+
+// Mounted inside the protected /api/auth scope
+pub fn user_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_order_tracking);
+}
+
+// Mounted inside the /api/admin scope
+pub fn admin_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_order_shipments)
+        .service(create_shipment)
+        .service(update_shipment);
+}
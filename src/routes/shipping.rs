@@ -0,0 +1,78 @@
+use actix_web::{post, web, HttpResponse, Responder, Result};
+use sqlx::PgPool;
+use bigdecimal::BigDecimal;
+use crate::models::shipping::ShippingQuoteRequest;
+use crate::services::shipping::{self, ShipmentRequest, ShippingError, ShippingProvider};
+
+// Courier services and prices for delivering the items to an address
+#[post("/shipping/quote")]
+async fn quote_shipping(
+    pool: web::Data<PgPool>,
+    shipping_provider: web::Data<dyn ShippingProvider>,
+    request: web::Json<ShippingQuoteRequest>,
+) -> Result<impl Responder> {
+    if request.province.trim().is_empty() {
+        return Ok(HttpResponse::BadRequest().json("province is required"));
+    }
+
+    let weight_grams = match request.weight_grams {
+        Some(weight) if weight <= 0 => return Ok(HttpResponse::BadRequest().json("weight_grams must be positive")),
+        Some(weight) => weight,
+        None if request.items.is_empty() => return Ok(HttpResponse::BadRequest().json("Send items or weight_grams")),
+        None if request.items.iter().any(|item| item.quantity < 1) => {
+            return Ok(HttpResponse::BadRequest().json("Quantity must be at least 1"));
+        }
+        None => {
+            let product_ids: Vec<i32> = request.items.iter().map(|item| item.product_id).collect();
+            let weights = match sqlx::query_as::<_, (i32, Option<BigDecimal>)>(
+                "SELECT id, weight FROM products WHERE id = ANY($1) AND is_active = true"
+            )
+            .bind(&product_ids)
+            .fetch_all(pool.get_ref())
+            .await {
+                Ok(weights) => weights,
+                Err(e) => {
+                    eprintln!("Database error: {}", e);
+                    return Ok(HttpResponse::InternalServerError().json("Database error"));
+                }
+            };
+
+            let mut lines = Vec::new();
+            for item in &request.items {
+                let Some((_, weight)) = weights.iter().find(|(id, _)| *id == item.product_id) else {
+                    return Ok(HttpResponse::BadRequest().json(format!("Product {} not found", item.product_id)));
+                };
+                lines.push((weight.as_ref(), item.quantity));
+            }
+            match shipping::parcel_weight_grams(lines) {
+                Some(total) => total,
+                None => return Ok(HttpResponse::BadRequest().json("The items are too heavy to quote")),
+            }
+        }
+    };
+
+    let parcel = ShipmentRequest {
+        province: request.province.clone(),
+        city: request.city.clone(),
+        postal_code: request.postal_code.clone(),
+        weight_grams,
+    };
+
+    match shipping_provider.quote(&parcel).await {
+        Ok(quotes) => Ok(HttpResponse::Ok().json(serde_json::json!({
+            "weight_grams": weight_grams,
+            "billable_kg": shipping::billable_kg(weight_grams),
+            "quotes": quotes,
+        }))),
+        Err(ShippingError::Unserviceable(message)) => Ok(HttpResponse::BadRequest().json(message)),
+        #[cfg(feature = "rajaongkir")]
+        Err(e) => {
+            eprintln!("Shipping error: {}", e);
+            Ok(HttpResponse::BadGateway().json("Shipping rates are unavailable, please try again"))
+        }
+    }
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(quote_shipping);
+}
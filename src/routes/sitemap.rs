@@ -0,0 +1,80 @@
+use actix_web::{get, web, HttpResponse, Responder, Result};
+use chrono::NaiveDateTime;
+use sqlx::PgPool;
+use std::env;
+
+// Storefront base URL used in the sitemap
+fn site_url() -> String {
+    env::var("FRONTEND_URL")
+        .unwrap_or_else(|_| "http://localhost:5174".to_string())
+        .trim_end_matches('/')
+        .to_string()
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn push_url(xml: &mut String, loc: &str, lastmod: Option<NaiveDateTime>) {
+    xml.push_str("  <url>\n");
+    xml.push_str(&format!("    <loc>{}</loc>\n", xml_escape(loc)));
+    if let Some(lastmod) = lastmod {
+        xml.push_str(&format!("    <lastmod>{}</lastmod>\n", lastmod.format("%Y-%m-%d")));
+    }
+    xml.push_str("  </url>\n");
+}
+
+// Active products live at /{slug}, categories at /kategori/{slug}
+#[get("/sitemap.xml")]
+async fn sitemap(pool: web::Data<PgPool>) -> Result<impl Responder> {
+    let products = match sqlx::query_as::<_, (String, NaiveDateTime)>(
+        "SELECT slug, updated_at FROM products
+         WHERE is_active = true AND slug IS NOT NULL
+         ORDER BY id"
+    )
+    .fetch_all(pool.get_ref())
+    .await {
+        Ok(products) => products,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Failed to build sitemap"));
+        }
+    };
+
+    let categories = match sqlx::query_as::<_, (String, Option<NaiveDateTime>)>(
+        "SELECT slug, updated_at FROM categories WHERE is_active = true ORDER BY id"
+    )
+    .fetch_all(pool.get_ref())
+    .await {
+        Ok(categories) => categories,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Failed to build sitemap"));
+        }
+    };
+
+    let base = site_url();
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+    push_url(&mut xml, &format!("{}/", base), None);
+    for (slug, updated_at) in categories {
+        push_url(&mut xml, &format!("{}/kategori/{}", base, slug), updated_at);
+    }
+    for (slug, updated_at) in products {
+        push_url(&mut xml, &format!("{}/{}", base, slug), Some(updated_at));
+    }
+    xml.push_str("</urlset>\n");
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/xml; charset=utf-8")
+        .body(xml))
+}
+
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(sitemap);
+}
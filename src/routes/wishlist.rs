@@ -0,0 +1,462 @@
+use actix_web::{get, post, put, delete, web, HttpResponse, Responder, Result};
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use crate::models::user::Claims;
+use crate::models::wishlist::*;
+
+const WISHLIST_SELECT: &str =
+    "SELECT w.*, (SELECT COUNT(*) FROM favorites f WHERE f.wishlist_id = w.id) AS item_count
+     FROM wishlists w";
+
+pub(crate) async fn load_wishlist_items(pool: &PgPool, wishlist_id: i32) -> Result<Vec<WishlistItem>, sqlx::Error> {
+    sqlx::query_as::<_, WishlistItem>(
+        "SELECT f.id, f.product_id, f.note, f.size, f.color, f.quantity, f.created_at,
+                p.name AS product_name, p.slug AS product_slug, p.image_url AS product_image,
+                pp.current_price AS price, COALESCE(p.stock, 0) AS stock_available, p.is_active
+         FROM favorites f
+         JOIN products p ON p.id = f.product_id
+         JOIN product_prices pp ON pp.product_id = p.id
+         WHERE f.wishlist_id = $1
+         ORDER BY f.created_at DESC, f.id DESC"
+    )
+    .bind(wishlist_id)
+    .fetch_all(pool)
+    .await
+}
+
+// The user's saved-for-later list, created on first use
+pub(crate) async fn saved_for_later_list(conn: &mut PgConnection, user_id: i32) -> Result<Wishlist, sqlx::Error> {
+    sqlx::query_as::<_, Wishlist>(
+        "INSERT INTO wishlists (user_id, name, kind)
+         VALUES ($1, 'Saved for later', $2)
+         ON CONFLICT (user_id) WHERE kind = 'saved_for_later' DO UPDATE SET updated_at = NOW()
+         RETURNING *"
+    )
+    .bind(user_id)
+    .bind(SAVED_FOR_LATER)
+    .fetch_one(conn)
+    .await
+}
+
+pub(crate) fn clean_note(note: &Option<String>) -> Result<Option<String>, HttpResponse> {
+    let note = note.as_deref().map(str::trim).filter(|note| !note.is_empty());
+    match note {
+        Some(note) if note.len() > MAX_NOTE_LENGTH => Err(HttpResponse::BadRequest().json("Note is too long")),
+        note => Ok(note.map(str::to_string)),
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), &'static str> {
+    if name.trim().is_empty() {
+        return Err("Wishlist name is required");
+    }
+    if name.trim().len() > 100 {
+        return Err("Wishlist name is too long");
+    }
+    Ok(())
+}
+
+async fn name_taken(pool: &PgPool, user_id: i32, name: &str, except_id: Option<i32>) -> Result<bool, sqlx::Error> {
+    let existing = sqlx::query_as::<_, (i32,)>(
+        "SELECT id FROM wishlists
+         WHERE user_id = $1 AND kind = 'wishlist' AND LOWER(name) = LOWER($2) AND id IS DISTINCT FROM $3"
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(except_id)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(existing.is_some())
+}
+
+// A named wishlist owned by the user
+async fn own_wishlist(pool: &PgPool, wishlist_id: i32, user_id: i32) -> Result<Wishlist, HttpResponse> {
+    match sqlx::query_as::<_, Wishlist>(&format!(
+        "{} WHERE w.id = $1 AND w.user_id = $2 AND w.kind = 'wishlist'",
+        WISHLIST_SELECT
+    ))
+    .bind(wishlist_id)
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await {
+        Ok(Some(wishlist)) => Ok(wishlist),
+        Ok(None) => Err(HttpResponse::NotFound().json("Wishlist not found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Err(HttpResponse::InternalServerError().json("Database error"))
+        }
+    }
+}
+
+#[get("/wishlists")]
+async fn get_wishlists(
+    pool: web::Data<PgPool>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    match sqlx::query_as::<_, Wishlist>(&format!(
+        "{} WHERE w.user_id = $1 AND w.kind = 'wishlist' ORDER BY w.created_at, w.id",
+        WISHLIST_SELECT
+    ))
+    .bind(user_id)
+    .fetch_all(pool.get_ref())
+    .await {
+        Ok(wishlists) => Ok(HttpResponse::Ok().json(wishlists)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch wishlists"))
+        }
+    }
+}
+
+#[post("/wishlists")]
+async fn create_wishlist(
+    pool: web::Data<PgPool>,
+    wishlist_data: web::Json<CreateWishlistRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    if let Err(message) = validate_name(&wishlist_data.name) {
+        return Ok(HttpResponse::BadRequest().json(message));
+    }
+    let name = wishlist_data.name.trim();
+
+    match name_taken(pool.get_ref(), user_id, name, None).await {
+        Ok(true) => return Ok(HttpResponse::BadRequest().json("You already have a wishlist with this name")),
+        Ok(false) => {}
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Database error"));
+        }
+    }
+
+    match sqlx::query_as::<_, Wishlist>(
+        "INSERT INTO wishlists (user_id, name, kind) VALUES ($1, $2, $3) RETURNING *"
+    )
+    .bind(user_id)
+    .bind(name)
+    .bind(WISHLIST)
+    .fetch_one(pool.get_ref())
+    .await {
+        Ok(wishlist) => Ok(HttpResponse::Created().json(wishlist)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to create wishlist"))
+        }
+    }
+}
+
+#[get("/wishlists/{id}")]
+async fn get_wishlist(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    let wishlist = match own_wishlist(pool.get_ref(), path.into_inner(), user_id).await {
+        Ok(wishlist) => wishlist,
+        Err(response) => return Ok(response),
+    };
+
+    match load_wishlist_items(pool.get_ref(), wishlist.id).await {
+        Ok(items) => Ok(HttpResponse::Ok().json(WishlistWithItems { wishlist, items })),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch wishlist items"))
+        }
+    }
+}
+
+// Rename, and turn the public share link on or off. Sharing again after
+// turning it off issues a new link.
+#[put("/wishlists/{id}")]
+async fn update_wishlist(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    wishlist_data: web::Json<UpdateWishlistRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    let wishlist = match own_wishlist(pool.get_ref(), path.into_inner(), user_id).await {
+        Ok(wishlist) => wishlist,
+        Err(response) => return Ok(response),
+    };
+
+    let name = match &wishlist_data.name {
+        Some(name) => {
+            if let Err(message) = validate_name(name) {
+                return Ok(HttpResponse::BadRequest().json(message));
+            }
+            match name_taken(pool.get_ref(), user_id, name.trim(), Some(wishlist.id)).await {
+                Ok(true) => return Ok(HttpResponse::BadRequest().json("You already have a wishlist with this name")),
+                Ok(false) => name.trim().to_string(),
+                Err(e) => {
+                    eprintln!("Database error: {}", e);
+                    return Ok(HttpResponse::InternalServerError().json("Database error"));
+                }
+            }
+        }
+        None => wishlist.name.clone(),
+    };
+
+    let share_token = match wishlist_data.shared {
+        Some(true) => wishlist.share_token.or_else(|| Some(Uuid::new_v4())),
+        Some(false) => None,
+        None => wishlist.share_token,
+    };
+
+    match sqlx::query_as::<_, Wishlist>(
+        "UPDATE wishlists SET name = $1, share_token = $2 WHERE id = $3 RETURNING *"
+    )
+    .bind(&name)
+    .bind(share_token)
+    .bind(wishlist.id)
+    .fetch_one(pool.get_ref())
+    .await {
+        Ok(wishlist) => Ok(HttpResponse::Ok().json(wishlist)),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to update wishlist"))
+        }
+    }
+}
+
+#[delete("/wishlists/{id}")]
+async fn delete_wishlist(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    // Items go with the list (ON DELETE CASCADE)
+    match sqlx::query("DELETE FROM wishlists WHERE id = $1 AND user_id = $2 AND kind = 'wishlist'")
+        .bind(path.into_inner())
+        .bind(user_id)
+        .execute(pool.get_ref())
+        .await {
+            Ok(result) if result.rows_affected() > 0 => Ok(HttpResponse::Ok().json("Wishlist deleted successfully")),
+            Ok(_) => Ok(HttpResponse::NotFound().json("Wishlist not found")),
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                Ok(HttpResponse::InternalServerError().json("Failed to delete wishlist"))
+            }
+        }
+}
+
+#[post("/wishlists/{id}/items")]
+async fn add_wishlist_item(
+    pool: web::Data<PgPool>,
+    path: web::Path<i32>,
+    item_data: web::Json<AddWishlistItemRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+
+    let wishlist = match own_wishlist(pool.get_ref(), path.into_inner(), user_id).await {
+        Ok(wishlist) => wishlist,
+        Err(response) => return Ok(response),
+    };
+
+    let note = match clean_note(&item_data.note) {
+        Ok(note) => note,
+        Err(response) => return Ok(response),
+    };
+    let quantity = item_data.quantity.unwrap_or(1);
+    if quantity < 1 {
+        return Ok(HttpResponse::BadRequest().json("Quantity must be at least 1"));
+    }
+
+    let product_exists = match sqlx::query_as::<_, (i32,)>("SELECT id FROM products WHERE id = $1 AND is_active = true")
+        .bind(item_data.product_id)
+        .fetch_optional(pool.get_ref())
+        .await {
+            Ok(result) => result,
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                return Ok(HttpResponse::InternalServerError().json("Database error"));
+            }
+        };
+
+    if product_exists.is_none() {
+        return Ok(HttpResponse::NotFound().json("Product not found"));
+    }
+
+    let existing = match sqlx::query_as::<_, (i32,)>(
+        "SELECT id FROM favorites
+         WHERE wishlist_id = $1 AND product_id = $2
+           AND size IS NOT DISTINCT FROM $3 AND color IS NOT DISTINCT FROM $4"
+    )
+    .bind(wishlist.id)
+    .bind(item_data.product_id)
+    .bind(&item_data.size)
+    .bind(&item_data.color)
+    .fetch_optional(pool.get_ref())
+    .await {
+        Ok(result) => result,
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Database error"));
+        }
+    };
+
+    if existing.is_some() {
+        return Ok(HttpResponse::BadRequest().json("Product already in this wishlist"));
+    }
+
+    match sqlx::query(
+        "INSERT INTO favorites (user_id, product_id, wishlist_id, note, size, color, quantity, created_at)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, NOW())"
+    )
+    .bind(user_id)
+    .bind(item_data.product_id)
+    .bind(wishlist.id)
+    .bind(&note)
+    .bind(&item_data.size)
+    .bind(&item_data.color)
+    .bind(quantity)
+    .execute(pool.get_ref())
+    .await {
+        Ok(_) => Ok(HttpResponse::Created().json("Item added to wishlist")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to add item to wishlist"))
+        }
+    }
+}
+
+#[put("/wishlists/{id}/items/{item_id}")]
+async fn update_wishlist_item(
+    pool: web::Data<PgPool>,
+    path: web::Path<(i32, i32)>,
+    item_data: web::Json<UpdateWishlistItemRequest>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+    let (wishlist_id, item_id) = path.into_inner();
+
+    let wishlist = match own_wishlist(pool.get_ref(), wishlist_id, user_id).await {
+        Ok(wishlist) => wishlist,
+        Err(response) => return Ok(response),
+    };
+
+    let note = match item_data.note.as_ref().map(clean_note) {
+        Some(Ok(note)) => Some(note),
+        Some(Err(response)) => return Ok(response),
+        None => None,
+    };
+    if item_data.quantity.is_some_and(|quantity| quantity < 1) {
+        return Ok(HttpResponse::BadRequest().json("Quantity must be at least 1"));
+    }
+
+    // Only the fields that were sent are changed
+    match sqlx::query(
+        "UPDATE favorites SET note = CASE WHEN $1 THEN $2 ELSE note END, quantity = COALESCE($3, quantity)
+         WHERE id = $4 AND wishlist_id = $5"
+    )
+    .bind(note.is_some())
+    .bind(note.flatten())
+    .bind(item_data.quantity)
+    .bind(item_id)
+    .bind(wishlist.id)
+    .execute(pool.get_ref())
+    .await {
+        Ok(result) if result.rows_affected() > 0 => Ok(HttpResponse::Ok().json("Wishlist item updated")),
+        Ok(_) => Ok(HttpResponse::NotFound().json("Wishlist item not found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to update wishlist item"))
+        }
+    }
+}
+
+#[delete("/wishlists/{id}/items/{item_id}")]
+async fn remove_wishlist_item(
+    pool: web::Data<PgPool>,
+    path: web::Path<(i32, i32)>,
+    claims: web::ReqData<Claims>,
+) -> Result<impl Responder> {
+    let user_id: i32 = claims.sub.parse().unwrap();
+    let (wishlist_id, item_id) = path.into_inner();
+
+    let wishlist = match own_wishlist(pool.get_ref(), wishlist_id, user_id).await {
+        Ok(wishlist) => wishlist,
+        Err(response) => return Ok(response),
+    };
+
+    match sqlx::query("DELETE FROM favorites WHERE id = $1 AND wishlist_id = $2")
+        .bind(item_id)
+        .bind(wishlist.id)
+        .execute(pool.get_ref())
+        .await {
+            Ok(result) if result.rows_affected() > 0 => Ok(HttpResponse::Ok().json("Item removed from wishlist")),
+            Ok(_) => Ok(HttpResponse::NotFound().json("Wishlist item not found")),
+            Err(e) => {
+                eprintln!("Database error: {}", e);
+                Ok(HttpResponse::InternalServerError().json("Failed to remove wishlist item"))
+            }
+        }
+}
+
+// Public, read-only view of a shared wishlist
+#[get("/wishlists/shared/{token}")]
+async fn get_shared_wishlist(
+    pool: web::Data<PgPool>,
+    path: web::Path<String>,
+) -> Result<impl Responder> {
+    let Ok(token) = Uuid::parse_str(&path.into_inner()) else {
+        return Ok(HttpResponse::NotFound().json("Wishlist not found"));
+    };
+
+    let wishlist = match sqlx::query_as::<_, (i32, String, String)>(
+        "SELECT w.id, w.name, u.first_name
+         FROM wishlists w
+         JOIN users u ON u.id = w.user_id
+         WHERE w.share_token = $1 AND w.kind = 'wishlist'"
+    )
+    .bind(token)
+    .fetch_optional(pool.get_ref())
+    .await {
+        Ok(Some(wishlist)) => wishlist,
+        Ok(None) => return Ok(HttpResponse::NotFound().json("Wishlist not found")),
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            return Ok(HttpResponse::InternalServerError().json("Database error"));
+        }
+    };
+
+    let (wishlist_id, name, owner_name) = wishlist;
+    match load_wishlist_items(pool.get_ref(), wishlist_id).await {
+        Ok(items) => {
+            // Products that were taken off the shop are left out
+            let items = items.into_iter().filter(|item| item.is_active).collect();
+            Ok(HttpResponse::Ok().json(SharedWishlist { name, owner_name, items }))
+        }
+        Err(e) => {
+            eprintln!("Database error: {}", e);
+            Ok(HttpResponse::InternalServerError().json("Failed to fetch wishlist items"))
+        }
+    }
+}
+
+// Public share links, mounted under /api
+pub fn init(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_shared_wishlist);
+}
+
+// Mounted inside the authenticated scope
+pub fn user_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_wishlists)
+        .service(create_wishlist)
+        .service(get_wishlist)
+        .service(update_wishlist)
+        .service(delete_wishlist)
+        .service(add_wishlist_item)
+        .service(update_wishlist_item)
+        .service(remove_wishlist_item);
+}
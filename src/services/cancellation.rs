@@ -0,0 +1,93 @@
+use sqlx::{PgConnection, PgPool};
+use crate::models::order::{Order, OrderStatus, PaymentStatus};
+use crate::models::refund::Refund;
+use crate::services::{coupons, inventory, order_status, promotions};
+use crate::services::order_status::{Actor, TransitionError};
+
+const SWEEP_INTERVAL_SECS: u64 = 60;
+
+// Statuses a customer may still cancel from; after that the parcel is
+// being packed and only an admin can cancel
+pub const CUSTOMER_CANCELLABLE: [OrderStatus; 2] = [OrderStatus::Pending, OrderStatus::Confirmed];
+
+// Cancel an order locked by the caller and undo what checkout did: the
+// stock goes back on the shelf, coupon redemptions and flash-sale units are
+// released, and a paid order gets a pending refund of its final amount.
+pub async fn cancel_order(
+    conn: &mut PgConnection,
+    order: &Order,
+    actor: &Actor,
+    reason: Option<&str>,
+) -> Result<(Order, Option<Refund>), TransitionError> {
+    let cancelled = order_status::transition(&mut *conn, order, OrderStatus::Cancelled, actor, reason).await?;
+
+    inventory::release_reservations(&mut *conn, order.id).await?;
+    coupons::release_redemptions(&mut *conn, order.id).await?;
+    promotions::release_order_quantity(&mut *conn, order.id).await?;
+
+    let refund = if order.payment_status == PaymentStatus::Paid {
+        let refund = sqlx::query_as::<_, Refund>(
+            "INSERT INTO refunds (order_id, amount, reason, created_by)
+             VALUES ($1, $2, $3, $4)
+             RETURNING *"
+        )
+        .bind(order.id)
+        .bind(&order.final_amount)
+        .bind(reason.map(str::trim).filter(|r| !r.is_empty()).unwrap_or("Order cancelled"))
+        .bind(actor.user_id)
+        .fetch_one(&mut *conn)
+        .await?;
+        Some(refund)
+    } else {
+        None
+    };
+
+    Ok((cancelled, refund))
+}
+
+// Cancel unpaid orders whose stock reservation has run out. Returns the
+// number of orders that were cancelled.
+pub async fn cancel_expired_orders(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let expired = sqlx::query_as::<_, Order>(
+        "SELECT o.* FROM orders o
+         WHERE o.status = 'pending' AND o.payment_status = 'pending'
+           AND EXISTS (
+               SELECT 1 FROM stock_reservations sr
+               WHERE sr.order_id = o.id AND sr.status = 'active' AND sr.expires_at <= NOW()
+           )
+         ORDER BY o.id
+         FOR UPDATE SKIP LOCKED"
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for order in &expired {
+        match cancel_order(&mut tx, order, &Actor::system(), Some("Payment window expired")).await {
+            Ok(_) => {}
+            Err(TransitionError::Database(e)) => return Err(e),
+            // Only pending orders are selected, and those can be cancelled
+            Err(e) => eprintln!("Could not cancel expired order {}: {}", order.id, e),
+        }
+    }
+
+    tx.commit().await?;
+
+    Ok(expired.len() as u64)
+}
+
+// Periodically cancel expired unpaid orders in the background
+pub fn spawn_reservation_sweeper(pool: PgPool) {
+    actix_web::rt::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(SWEEP_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            match cancel_expired_orders(&pool).await {
+                Ok(0) => {}
+                Ok(count) => println!("⏰ Released stock for {} expired unpaid order(s)", count),
+                Err(e) => eprintln!("Failed to release expired reservations: {}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,166 @@
+use sqlx::{PgConnection, PgPool};
+use uuid::Uuid;
+use crate::models::cart::{Cart, CartPriceChange};
+use crate::utils::jwt::CART_TOKEN_TTL_DAYS;
+
+const SWEEP_INTERVAL_SECS: u64 = 60 * 60;
+
+// The user's cart, created on first use
+pub async fn user_cart(conn: &mut PgConnection, user_id: i32) -> Result<Cart, sqlx::Error> {
+    sqlx::query_as::<_, Cart>(
+        "INSERT INTO carts (user_id, created_at, updated_at)
+         VALUES ($1, NOW(), NOW())
+         ON CONFLICT (user_id) DO UPDATE SET updated_at = NOW()
+         RETURNING *"
+    )
+    .bind(user_id)
+    .fetch_one(conn)
+    .await
+}
+
+// The guest cart for `guest_token`, created on first use
+pub async fn guest_cart(conn: &mut PgConnection, guest_token: Uuid) -> Result<Cart, sqlx::Error> {
+    sqlx::query_as::<_, Cart>(
+        "INSERT INTO carts (guest_token, created_at, updated_at)
+         VALUES ($1, NOW(), NOW())
+         ON CONFLICT (guest_token) DO UPDATE SET updated_at = NOW()
+         RETURNING *"
+    )
+    .bind(guest_token)
+    .fetch_one(conn)
+    .await
+}
+
+pub async fn find_guest_cart(conn: &mut PgConnection, guest_token: Uuid) -> Result<Option<Cart>, sqlx::Error> {
+    sqlx::query_as::<_, Cart>("SELECT * FROM carts WHERE guest_token = $1")
+        .bind(guest_token)
+        .fetch_optional(conn)
+        .await
+}
+
+// Lines in the user's cart whose product price changed since they were
+// added and that the customer has not accepted yet
+pub async fn price_changes(conn: &mut PgConnection, user_id: i32) -> Result<Vec<CartPriceChange>, sqlx::Error> {
+    sqlx::query_as::<_, CartPriceChange>(
+        "SELECT ci.id AS cart_item_id, ci.product_id, ci.size, ci.color, ci.price_at_time, pp.current_price
+         FROM cart_items ci
+         JOIN carts c ON c.id = ci.cart_id
+         JOIN product_prices pp ON pp.product_id = ci.product_id
+         WHERE c.user_id = $1 AND ci.price_at_time <> pp.current_price
+         ORDER BY ci.id"
+    )
+    .bind(user_id)
+    .fetch_all(conn)
+    .await
+}
+
+// Move the guest cart's items into the user's cart and delete the guest
+// cart. Lines for the same product/size/color are combined; quantities are
+// clamped to the stock that is left and unavailable products are dropped.
+// Returns the number of guest lines that were merged.
+pub async fn merge_guest_cart(pool: &PgPool, guest_token: Uuid, user_id: i32) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let guest = sqlx::query_as::<_, (i32,)>("SELECT id FROM carts WHERE guest_token = $1 FOR UPDATE")
+        .bind(guest_token)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+    let Some((guest_cart_id,)) = guest else {
+        return Ok(0);
+    };
+
+    let cart = user_cart(&mut tx, user_id).await?;
+
+    let combined = sqlx::query(
+        "UPDATE cart_items u
+         SET quantity = LEAST(u.quantity + g.quantity, p.stock), updated_at = NOW()
+         FROM cart_items g
+         JOIN products p ON p.id = g.product_id
+         WHERE g.cart_id = $1 AND u.cart_id = $2
+           AND u.product_id = g.product_id
+           AND u.size IS NOT DISTINCT FROM g.size
+           AND u.color IS NOT DISTINCT FROM g.color
+           AND p.is_active = true AND COALESCE(p.stock, 0) > 0"
+    )
+    .bind(guest_cart_id)
+    .bind(cart.id)
+    .execute(&mut *tx)
+    .await?;
+
+    let added = sqlx::query(
+        "INSERT INTO cart_items (cart_id, product_id, quantity, size, color, price_at_time, created_at, updated_at)
+         SELECT $2, g.product_id, LEAST(g.quantity, p.stock), g.size, g.color, g.price_at_time, NOW(), NOW()
+         FROM cart_items g
+         JOIN products p ON p.id = g.product_id
+         WHERE g.cart_id = $1
+           AND p.is_active = true AND COALESCE(p.stock, 0) > 0
+           AND NOT EXISTS (
+               SELECT 1 FROM cart_items u
+               WHERE u.cart_id = $2
+                 AND u.product_id = g.product_id
+                 AND u.size IS NOT DISTINCT FROM g.size
+                 AND u.color IS NOT DISTINCT FROM g.color
+           )
+         ORDER BY g.created_at"
+    )
+    .bind(guest_cart_id)
+    .bind(cart.id)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("DELETE FROM cart_items WHERE cart_id = $1")
+        .bind(guest_cart_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query("DELETE FROM carts WHERE id = $1")
+        .bind(guest_cart_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(combined.rows_affected() + added.rows_affected())
+}
+
+// Delete guest carts whose token can no longer be valid
+pub async fn purge_stale_guest_carts(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query(
+        "DELETE FROM cart_items ci
+         USING carts c
+         WHERE ci.cart_id = c.id AND c.guest_token IS NOT NULL
+           AND c.updated_at < NOW() - make_interval(days => $1)"
+    )
+    .bind(CART_TOKEN_TTL_DAYS as i32)
+    .execute(&mut *tx)
+    .await?;
+
+    let result = sqlx::query(
+        "DELETE FROM carts
+         WHERE guest_token IS NOT NULL AND updated_at < NOW() - make_interval(days => $1)"
+    )
+    .bind(CART_TOKEN_TTL_DAYS as i32)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(result.rows_affected())
+}
+
+pub fn spawn_guest_cart_sweeper(pool: PgPool) {
+    actix_web::rt::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(SWEEP_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            match purge_stale_guest_carts(&pool).await {
+                Ok(0) => {}
+                Ok(count) => println!("🧹 Removed {} abandoned guest cart(s)", count),
+                Err(e) => eprintln!("Failed to remove abandoned guest carts: {}", e),
+            }
+        }
+    });
+}
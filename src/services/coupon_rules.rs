@@ -0,0 +1,81 @@
+use bigdecimal::{BigDecimal, Zero};
+
+// The coupon math: which lines a coupon covers, what it takes off and how
+// that is spread over the lines. Kept free of the app's models so the tests
+// can run it on its own.
+
+// A priced order line the coupons are applied to
+pub struct CouponLine {
+    pub product_id: i32,
+    pub category_id: Option<i32>,
+    pub line_total: BigDecimal,
+}
+
+// The products and categories a coupon is restricted to; both empty means
+// every product. Categories must already include their subcategories.
+pub struct CouponScope<'a> {
+    pub product_ids: &'a [i32],
+    pub category_ids: &'a [i32],
+}
+
+impl CouponScope<'_> {
+    pub fn applies_to(&self, line: &CouponLine) -> bool {
+        if self.product_ids.is_empty() && self.category_ids.is_empty() {
+            return true;
+        }
+        self.product_ids.contains(&line.product_id)
+            || line.category_id.is_some_and(|id| self.category_ids.contains(&id))
+    }
+
+    // Total of the lines the coupon covers
+    pub fn eligible_total(&self, lines: &[CouponLine]) -> BigDecimal {
+        lines.iter().filter(|line| self.applies_to(line)).map(|line| &line.line_total).sum()
+    }
+}
+
+// What a percentage or fixed coupon takes off the eligible total, never more
+// than that total or the coupon's cap
+pub fn discount_amount(
+    percentage: bool,
+    value: &BigDecimal,
+    max_discount: Option<&BigDecimal>,
+    eligible: &BigDecimal,
+) -> BigDecimal {
+    let discount = if percentage {
+        (eligible * value / BigDecimal::from(100)).round(2)
+    } else {
+        value.clone().min(eligible.clone())
+    };
+    match max_discount {
+        Some(max_discount) => discount.min(max_discount.clone()),
+        None => discount,
+    }
+}
+
+// Spread a discount over the eligible lines in proportion to their totals,
+// so each line knows what it was discounted (e.g. for tax). The last
+// eligible line takes the rounding difference.
+pub fn split_discount(
+    scope: &CouponScope,
+    lines: &[CouponLine],
+    discount: &BigDecimal,
+    eligible: &BigDecimal,
+) -> Vec<BigDecimal> {
+    let last = lines.iter().rposition(|line| scope.applies_to(line));
+    let mut remaining = discount.clone();
+    lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            if !scope.applies_to(line) || eligible.is_zero() {
+                BigDecimal::zero()
+            } else if Some(i) == last {
+                remaining.clone()
+            } else {
+                let share = (discount * &line.line_total / eligible).round(2);
+                remaining -= &share;
+                share
+            }
+        })
+        .collect()
+}
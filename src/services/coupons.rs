@@ -0,0 +1,229 @@
+use bigdecimal::{BigDecimal, Zero};
+use sqlx::PgConnection;
+use crate::models::coupon::{AppliedCoupon, Coupon, FREE_SHIPPING, PERCENTAGE};
+use crate::services::coupon_rules::{self, CouponScope};
+
+pub use crate::services::coupon_rules::CouponLine;
+
+pub enum CouponError {
+    // The codes can't be used on this order; the message is shown to the customer
+    Rejected(String),
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for CouponError {
+    fn from(e: sqlx::Error) -> Self {
+        CouponError::Database(e)
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct CouponRow {
+    #[sqlx(flatten)]
+    coupon: Coupon,
+    started: bool,
+    expired: bool,
+}
+
+// `coupon_code` and `coupon_codes` combined, normalized and de-duplicated
+pub fn requested_codes(coupon_code: Option<&str>, coupon_codes: &[String]) -> Vec<String> {
+    let mut codes: Vec<String> = Vec::new();
+    for code in coupon_code.into_iter().chain(coupon_codes.iter().map(String::as_str)) {
+        let code = code.trim().to_uppercase();
+        if !code.is_empty() && !codes.contains(&code) {
+            codes.push(code);
+        }
+    }
+    codes
+}
+
+// `category_ids` and every category below them
+async fn with_subcategories(conn: &mut PgConnection, category_ids: &[i32]) -> Result<Vec<i32>, sqlx::Error> {
+    sqlx::query_scalar::<_, i32>(
+        "WITH RECURSIVE covered AS (
+             SELECT id FROM categories WHERE id = ANY($1)
+             UNION
+             SELECT c.id FROM categories c JOIN covered cv ON c.parent_id = cv.id
+         )
+         SELECT id FROM covered"
+    )
+    .bind(category_ids)
+    .fetch_all(conn)
+    .await
+}
+
+// Check the codes against the order lines and work out what each takes off.
+// At most one discount coupon and one free-shipping coupon can be combined.
+// Checkout passes `lock` so usage limits hold under concurrent orders; the
+// coupon rows then stay locked until the transaction ends.
+pub async fn apply_coupons(
+    conn: &mut PgConnection,
+    user_id: i32,
+    codes: &[String],
+    lines: &[CouponLine],
+    lock: bool,
+) -> Result<Vec<AppliedCoupon>, CouponError> {
+    let subtotal: BigDecimal = lines.iter().map(|line| &line.line_total).sum();
+    let mut applied: Vec<AppliedCoupon> = Vec::new();
+
+    // Lock coupons in a fixed order so concurrent checkouts can't deadlock
+    let mut codes = codes.to_vec();
+    codes.sort();
+
+    for code in &codes {
+        let sql = format!(
+            "SELECT *, (valid_from IS NULL OR valid_from <= NOW()) AS started,
+                    (valid_until IS NOT NULL AND valid_until < NOW()) AS expired
+             FROM coupons WHERE code = $1{}",
+            if lock { " FOR UPDATE" } else { "" }
+        );
+        let row = sqlx::query_as::<_, CouponRow>(&sql)
+            .bind(code)
+            .fetch_optional(&mut *conn)
+            .await?;
+
+        let Some(CouponRow { mut coupon, started, expired }) = row else {
+            return Err(CouponError::Rejected(format!("Invalid coupon code {}", code)));
+        };
+        if !coupon.is_active || !started {
+            return Err(CouponError::Rejected(format!("Coupon {} is not active", code)));
+        }
+        if expired {
+            return Err(CouponError::Rejected(format!("Coupon {} has expired", code)));
+        }
+        if coupon.usage_limit.is_some_and(|limit| coupon.used_count >= limit) {
+            return Err(CouponError::Rejected(format!("Coupon {} has been fully redeemed", code)));
+        }
+
+        let free_shipping = coupon.discount_type == FREE_SHIPPING;
+        if applied.iter().any(|other| other.free_shipping == free_shipping) {
+            return Err(CouponError::Rejected(if free_shipping {
+                "Only one free-shipping coupon can be used per order".to_string()
+            } else {
+                "Only one discount coupon can be used per order".to_string()
+            }));
+        }
+
+        if let Some(limit) = coupon.per_user_limit {
+            let (used,) = sqlx::query_as::<_, (i64,)>(
+                "SELECT COUNT(*) FROM coupon_redemptions WHERE coupon_id = $1 AND user_id = $2"
+            )
+            .bind(coupon.id)
+            .bind(user_id)
+            .fetch_one(&mut *conn)
+            .await?;
+            if used >= limit as i64 {
+                return Err(CouponError::Rejected(format!("You have already used coupon {}", code)));
+            }
+        }
+
+        if coupon.first_order_only {
+            let (has_orders,) = sqlx::query_as::<_, (bool,)>(
+                "SELECT EXISTS (SELECT 1 FROM orders WHERE user_id = $1 AND status <> 'cancelled')"
+            )
+            .bind(user_id)
+            .fetch_one(&mut *conn)
+            .await?;
+            if has_orders {
+                return Err(CouponError::Rejected(format!("Coupon {} is only valid on your first order", code)));
+            }
+        }
+
+        if let Some(minimum) = &coupon.min_order_amount {
+            if &subtotal < minimum {
+                return Err(CouponError::Rejected(format!("Coupon {} requires a minimum order of {}", code, minimum)));
+            }
+        }
+
+        // A coupon on a category also covers its subcategories. Added to the
+        // list rather than replacing it, so deleted categories can't leave it
+        // empty and turn the coupon into one for every product.
+        if let Some(category_ids) = coupon.category_ids.as_mut().filter(|ids| !ids.is_empty()) {
+            let covered = with_subcategories(&mut *conn, category_ids).await?;
+            category_ids.extend(covered);
+        }
+
+        let scope = CouponScope {
+            product_ids: coupon.product_ids.as_deref().unwrap_or_default(),
+            category_ids: coupon.category_ids.as_deref().unwrap_or_default(),
+        };
+        let eligible = scope.eligible_total(lines);
+        if eligible.is_zero() {
+            return Err(CouponError::Rejected(format!("Coupon {} does not apply to any item in the order", code)));
+        }
+
+        // Percentage and fixed discounts only count the eligible lines
+        let discount_amount = if free_shipping {
+            BigDecimal::zero()
+        } else {
+            coupon_rules::discount_amount(
+                coupon.discount_type == PERCENTAGE,
+                &coupon.discount_value,
+                coupon.max_discount_amount.as_ref(),
+                &eligible,
+            )
+        };
+
+        let line_discounts = coupon_rules::split_discount(&scope, lines, &discount_amount, &eligible);
+        applied.push(AppliedCoupon {
+            coupon_id: coupon.id,
+            code: coupon.code,
+            discount_type: coupon.discount_type,
+            discount_amount,
+            free_shipping,
+            line_discounts,
+        });
+    }
+
+    Ok(applied)
+}
+
+// Record the coupons used on a new order and count them against their limits.
+// `shipping_discount` is the shipping cost waived by a free-shipping coupon.
+pub async fn record_redemptions(
+    conn: &mut PgConnection,
+    order_id: i32,
+    user_id: i32,
+    applied: &[AppliedCoupon],
+    shipping_discount: &BigDecimal,
+) -> Result<(), sqlx::Error> {
+    for coupon in applied {
+        let shipping_discount = if coupon.free_shipping { shipping_discount.clone() } else { BigDecimal::zero() };
+
+        sqlx::query(
+            "INSERT INTO coupon_redemptions (coupon_id, user_id, order_id, discount_amount, shipping_discount, created_at)
+             VALUES ($1, $2, $3, $4, $5, NOW())"
+        )
+        .bind(coupon.coupon_id)
+        .bind(user_id)
+        .bind(order_id)
+        .bind(&coupon.discount_amount)
+        .bind(shipping_discount)
+        .execute(&mut *conn)
+        .await?;
+
+        sqlx::query("UPDATE coupons SET used_count = used_count + 1 WHERE id = $1")
+            .bind(coupon.coupon_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+// Give back the coupons used on a cancelled order: the redemptions stop
+// counting towards usage and per-user limits
+pub async fn release_redemptions(conn: &mut PgConnection, order_id: i32) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "WITH released AS (
+             DELETE FROM coupon_redemptions WHERE order_id = $1 RETURNING coupon_id
+         )
+         UPDATE coupons c SET used_count = GREATEST(c.used_count - r.count, 0)
+         FROM (SELECT coupon_id, COUNT(*)::INTEGER AS count FROM released GROUP BY coupon_id) r
+         WHERE c.id = r.coupon_id"
+    )
+    .bind(order_id)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
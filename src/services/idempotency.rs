@@ -0,0 +1,152 @@
+use sha2::{Digest, Sha256};
+use sqlx::types::Json;
+use sqlx::PgPool;
+
+// How long a key and its response are kept for replays
+pub const KEY_TTL_HOURS: i32 = 24;
+// A key still without a response after this long belongs to a request that
+// never finished (e.g. the server restarted), so it may be used again
+const ABANDONED_MINUTES: i32 = 5;
+const PURGE_INTERVAL_SECS: u64 = 3600;
+
+// What to do with a request carrying an idempotency key
+pub enum Claim {
+    // First use: run the request and store its response in this row
+    New(i32),
+    // The first request with this key hasn't finished yet
+    InProgress,
+    // The key was used for a different request
+    Mismatch,
+    // Replay this response
+    Completed {
+        status: u16,
+        headers: Vec<(String, String)>,
+        body: Vec<u8>,
+    },
+}
+
+#[derive(sqlx::FromRow)]
+struct StoredKey {
+    request_hash: String,
+    response_status: Option<i16>,
+    response_headers: Option<Json<Vec<(String, String)>>>,
+    response_body: Option<Vec<u8>>,
+}
+
+// Fingerprint of a request, so a retry can be told apart from a new request
+// that reuses the key
+pub fn request_hash(method: &str, path: &str, query: &str, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    for part in [method.as_bytes(), path.as_bytes(), query.as_bytes(), body] {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+pub async fn claim(pool: &PgPool, user_id: i32, key: &str, request_hash: &str) -> Result<Claim, sqlx::Error> {
+    // Forget an expired or abandoned use of this key first
+    sqlx::query(
+        "DELETE FROM idempotency_keys
+         WHERE user_id = $1 AND idempotency_key = $2
+           AND (created_at < NOW() - $3 * INTERVAL '1 hour'
+                OR (response_status IS NULL AND created_at < NOW() - $4 * INTERVAL '1 minute'))"
+    )
+    .bind(user_id)
+    .bind(key)
+    .bind(KEY_TTL_HOURS)
+    .bind(ABANDONED_MINUTES)
+    .execute(pool)
+    .await?;
+
+    let inserted = sqlx::query_scalar::<_, i32>(
+        "INSERT INTO idempotency_keys (user_id, idempotency_key, request_hash)
+         VALUES ($1, $2, $3)
+         ON CONFLICT (user_id, idempotency_key) DO NOTHING
+         RETURNING id"
+    )
+    .bind(user_id)
+    .bind(key)
+    .bind(request_hash)
+    .fetch_optional(pool)
+    .await?;
+    if let Some(id) = inserted {
+        return Ok(Claim::New(id));
+    }
+
+    let stored = sqlx::query_as::<_, StoredKey>(
+        "SELECT request_hash, response_status, response_headers, response_body
+         FROM idempotency_keys WHERE user_id = $1 AND idempotency_key = $2"
+    )
+    .bind(user_id)
+    .bind(key)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(match stored {
+        Some(stored) if stored.request_hash != request_hash => Claim::Mismatch,
+        Some(StoredKey { response_status: Some(status), response_headers, response_body, .. }) => Claim::Completed {
+            status: status as u16,
+            headers: response_headers.map(|headers| headers.0).unwrap_or_default(),
+            body: response_body.unwrap_or_default(),
+        },
+        // Still running, or released by the first request a moment ago
+        _ => Claim::InProgress,
+    })
+}
+
+// Store the response of a claimed key for replays. Goes by the claimed row,
+// so a request that outlived ABANDONED_MINUTES can't overwrite the claim of
+// the retry that took the key over.
+pub async fn complete(
+    pool: &PgPool,
+    claim_id: i32,
+    status: u16,
+    headers: &[(String, String)],
+    body: &[u8],
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE idempotency_keys SET response_status = $2, response_headers = $3, response_body = $4
+         WHERE id = $1 AND response_status IS NULL"
+    )
+    .bind(claim_id)
+    .bind(status as i16)
+    .bind(Json(headers))
+    .bind(body)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// Give up a claimed key so the client can retry, e.g. after a server error
+pub async fn release(pool: &PgPool, claim_id: i32) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM idempotency_keys WHERE id = $1 AND response_status IS NULL")
+        .bind(claim_id)
+        .execute(pool)
+        .await?;
+
+    Ok(())
+}
+
+pub async fn purge_expired(pool: &PgPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM idempotency_keys WHERE created_at < NOW() - $1 * INTERVAL '1 hour'")
+        .bind(KEY_TTL_HOURS)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected())
+}
+
+// Periodically drop keys that can no longer be replayed
+pub fn spawn_key_purger(pool: PgPool) {
+    actix_web::rt::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(PURGE_INTERVAL_SECS));
+        loop {
+            interval.tick().await;
+            if let Err(e) = purge_expired(&pool).await {
+                eprintln!("Failed to purge idempotency keys: {}", e);
+            }
+        }
+    });
+}
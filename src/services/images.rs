@@ -0,0 +1,142 @@
+use actix_web::web;
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageFormat};
+use std::env;
+use std::fmt;
+use std::io::Cursor;
+use uuid::Uuid;
+use crate::services::storage::{self, BlobStore};
+
+const DEFAULT_MAX_FILE_SIZE: usize = 10 * 1024 * 1024;
+const LARGE_MAX_DIMENSION: u32 = 1600;
+const THUMBNAIL_MAX_DIMENSION: u32 = 400;
+
+#[derive(Debug)]
+pub enum ImageError {
+    TooLarge(usize),
+    UnsupportedType,
+    Invalid(String),
+}
+
+impl fmt::Display for ImageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageError::TooLarge(limit) => write!(f, "Image exceeds the maximum size of {} bytes", limit),
+            ImageError::UnsupportedType => write!(f, "Only JPEG, PNG and WebP images are allowed"),
+            ImageError::Invalid(msg) => write!(f, "Invalid image: {}", msg),
+        }
+    }
+}
+
+// An uploaded image plus the variants we serve to the storefront
+pub struct ProcessedImage {
+    pub original: Vec<u8>,
+    pub extension: &'static str,
+    pub content_type: &'static str,
+    pub webp: Vec<u8>,
+    pub thumbnail: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+// URLs of an image that has been written to the blob store
+pub struct StoredImage {
+    pub url: String,
+    pub webp_url: String,
+    pub thumbnail_url: String,
+    pub keys: Vec<String>,
+    pub width: i32,
+    pub height: i32,
+}
+
+// Upload size limit in bytes, from MAX_FILE_SIZE
+pub fn max_upload_bytes() -> usize {
+    env::var("MAX_FILE_SIZE")
+        .ok()
+        .and_then(|v| v.split_whitespace().next().and_then(|n| n.parse().ok()))
+        .unwrap_or(DEFAULT_MAX_FILE_SIZE)
+}
+
+fn encode_webp(image: &DynamicImage) -> Result<Vec<u8>, ImageError> {
+    // The WebP encoder only accepts 8-bit RGB(A)
+    let rgba = DynamicImage::ImageRgba8(image.to_rgba8());
+    let mut out = Cursor::new(Vec::new());
+    rgba.write_to(&mut out, ImageFormat::WebP)
+        .map_err(|e| ImageError::Invalid(e.to_string()))?;
+    Ok(out.into_inner())
+}
+
+// Validate an upload by its content (not its file name) and build the
+// WebP and thumbnail variants. CPU bound: run it inside `web::block`.
+pub fn process_upload(bytes: Vec<u8>) -> Result<ProcessedImage, ImageError> {
+    let limit = max_upload_bytes();
+    if bytes.len() > limit {
+        return Err(ImageError::TooLarge(limit));
+    }
+
+    let format = image::guess_format(&bytes).map_err(|_| ImageError::UnsupportedType)?;
+    let (extension, content_type) = match format {
+        ImageFormat::Jpeg => ("jpg", "image/jpeg"),
+        ImageFormat::Png => ("png", "image/png"),
+        ImageFormat::WebP => ("webp", "image/webp"),
+        _ => return Err(ImageError::UnsupportedType),
+    };
+
+    let decoded = image::load_from_memory_with_format(&bytes, format)
+        .map_err(|e| ImageError::Invalid(e.to_string()))?;
+
+    let large = if decoded.width() > LARGE_MAX_DIMENSION || decoded.height() > LARGE_MAX_DIMENSION {
+        decoded.resize(LARGE_MAX_DIMENSION, LARGE_MAX_DIMENSION, FilterType::Lanczos3)
+    } else {
+        decoded.clone()
+    };
+
+    Ok(ProcessedImage {
+        webp: encode_webp(&large)?,
+        thumbnail: encode_webp(&decoded.thumbnail(THUMBNAIL_MAX_DIMENSION, THUMBNAIL_MAX_DIMENSION))?,
+        width: decoded.width(),
+        height: decoded.height(),
+        original: bytes,
+        extension,
+        content_type,
+    })
+}
+
+// Validate and resize an upload, then write the original and its variants
+// under `prefix`. Errors are user-facing messages.
+pub async fn store_upload(store: &dyn BlobStore, prefix: &str, bytes: Vec<u8>) -> Result<StoredImage, String> {
+    let processed = web::block(move || process_upload(bytes))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+
+    let base = format!("{}/{}", prefix, Uuid::new_v4().simple());
+    let original_key = format!("{}.{}", base, processed.extension);
+    let webp_key = format!("{}.webp", base);
+    let thumbnail_key = format!("{}_thumb.webp", base);
+    let keys = vec![original_key.clone(), webp_key.clone(), thumbnail_key.clone()];
+
+    let stored = async {
+        let url = store.put(&original_key, processed.original, processed.content_type).await?;
+        let webp_url = store.put(&webp_key, processed.webp, "image/webp").await?;
+        let thumbnail_url = store.put(&thumbnail_key, processed.thumbnail, "image/webp").await?;
+        Ok::<_, storage::StorageError>((url, webp_url, thumbnail_url))
+    }
+    .await;
+
+    match stored {
+        Ok((url, webp_url, thumbnail_url)) => Ok(StoredImage {
+            url,
+            webp_url,
+            thumbnail_url,
+            keys,
+            width: processed.width as i32,
+            height: processed.height as i32,
+        }),
+        Err(e) => {
+            eprintln!("Storage error: {}", e);
+            storage::delete_all(store, &keys).await;
+            Err("Failed to store image".to_string())
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use chrono::{DateTime, Duration, Utc};
+use sqlx::PgConnection;
+use std::env;
+
+const DEFAULT_RESERVATION_MINUTES: i64 = 30;
+
+// How long stock stays reserved for an order that has not been paid yet
+pub fn reservation_expiry() -> DateTime<Utc> {
+    let minutes = env::var("STOCK_RESERVATION_MINUTES")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|m| *m > 0)
+        .unwrap_or(DEFAULT_RESERVATION_MINUTES);
+
+    Utc::now() + Duration::minutes(minutes)
+}
+
+// Lock the rows of the products an order takes stock from, one by one in id
+// order whatever order the lines came in, so concurrent checkouts can't
+// deadlock. The rows stay locked until the transaction ends.
+pub async fn lock_products(conn: &mut PgConnection, product_ids: &[i32]) -> Result<(), sqlx::Error> {
+    let mut product_ids = product_ids.to_vec();
+    product_ids.sort_unstable();
+    product_ids.dedup();
+
+    for product_id in product_ids {
+        sqlx::query("SELECT id FROM products WHERE id = $1 FOR UPDATE")
+            .bind(product_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(())
+}
+
+// Take `quantity` units out of stock and hold them for `order_id`.
+// The decrement only happens when enough stock is left, so two concurrent
+// checkouts can never both take the last unit. Returns false when the stock
+// is insufficient; the caller is expected to roll back its transaction.
+pub async fn reserve_stock(
+    conn: &mut PgConnection,
+    order_id: i32,
+    product_id: i32,
+    quantity: i32,
+    expires_at: DateTime<Utc>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE products SET stock = stock - $1, sold_count = COALESCE(sold_count, 0) + $1
+         WHERE id = $2 AND stock >= $1"
+    )
+    .bind(quantity)
+    .bind(product_id)
+    .execute(&mut *conn)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    sqlx::query(
+        "INSERT INTO stock_reservations (order_id, product_id, quantity, status, expires_at, created_at, updated_at)
+         VALUES ($1, $2, $3, 'active', $4, NOW(), NOW())"
+    )
+    .bind(order_id)
+    .bind(product_id)
+    .bind(quantity)
+    .bind(expires_at)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(true)
+}
+
+// Payment went through: the reserved units are now sold for good
+pub async fn consume_reservations(conn: &mut PgConnection, order_id: i32) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE stock_reservations SET status = 'consumed', updated_at = NOW()
+         WHERE order_id = $1 AND status = 'active'"
+    )
+    .bind(order_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+// Put the units of an order back on the shelf, whether they were still
+// reserved or already counted as sold
+pub async fn release_reservations(conn: &mut PgConnection, order_id: i32) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "WITH released AS (
+             UPDATE stock_reservations SET status = 'released', updated_at = NOW()
+             WHERE order_id = $1 AND status IN ('active', 'consumed')
+             RETURNING product_id, quantity
+         )
+         UPDATE products p SET
+             stock = p.stock + r.quantity,
+             sold_count = GREATEST(COALESCE(p.sold_count, 0) - r.quantity, 0)
+         FROM (SELECT product_id, SUM(quantity)::INTEGER AS quantity FROM released GROUP BY product_id) r
+         WHERE p.id = r.product_id"
+    )
+    .bind(order_id)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
@@ -0,0 +1,288 @@
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{Datelike, Utc};
+use sqlx::PgConnection;
+use sqlx::types::Json;
+use std::env;
+use crate::models::invoice::*;
+use crate::models::order::{Order, OrderItem, OrderStatus};
+use crate::services::pdf::{fit, Align, Font, PdfDocument, PAGE_HEIGHT, PAGE_WIDTH};
+use crate::services::tax::TaxRules;
+
+// Orders get an invoice once they have been confirmed; a pending order may
+// still change or expire
+fn can_invoice(status: OrderStatus) -> bool {
+    !matches!(status, OrderStatus::Pending | OrderStatus::Cancelled)
+}
+
+// Seller details printed on new invoices
+fn seller_from_env() -> InvoiceParty {
+    InvoiceParty {
+        name: env::var("INVOICE_SELLER_NAME").unwrap_or_else(|_| "Batik Kita".to_string()),
+        // Lines separated by `|`
+        address: env::var("INVOICE_SELLER_ADDRESS")
+            .map(|address| address.split('|').map(|line| line.trim().to_string()).filter(|line| !line.is_empty()).collect())
+            .unwrap_or_default(),
+        tax_id: env::var("INVOICE_SELLER_NPWP").ok().filter(|npwp| !npwp.trim().is_empty()),
+        phone: env::var("INVOICE_SELLER_PHONE").ok().filter(|phone| !phone.trim().is_empty()),
+    }
+}
+
+// Billing address of the order, or where it was shipped when the buyer gave none
+fn bill_to(order: &Order, account_name: String) -> InvoiceParty {
+    let address = order
+        .billing_address
+        .as_ref()
+        .filter(|address| address.is_object())
+        .or(order.shipping_address.as_ref())
+        .filter(|address| address.is_object());
+
+    let field = |name: &str| {
+        address
+            .and_then(|address| address.get(name))
+            .and_then(|value| value.as_str())
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+    };
+
+    let city_line: Vec<String> = ["city", "province", "postal_code"].iter().filter_map(|name| field(name)).collect();
+    let address_lines = [field("street"), Some(city_line.join(", ")), field("country")]
+        .into_iter()
+        .flatten()
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    InvoiceParty {
+        name: field("full_name").unwrap_or(account_name),
+        address: address_lines,
+        tax_id: None,
+        phone: field("phone"),
+    }
+}
+
+// Take the next number for `year`. The counter row stays locked until the
+// caller's transaction ends, which keeps the numbers free of gaps.
+async fn next_invoice_number(conn: &mut PgConnection, year: i32) -> Result<String, sqlx::Error> {
+    let (number,): (i32,) = sqlx::query_as(
+        "INSERT INTO invoice_counters (year, last_number) VALUES ($1, 1)
+         ON CONFLICT (year) DO UPDATE SET last_number = invoice_counters.last_number + 1
+         RETURNING last_number"
+    )
+    .bind(year)
+    .fetch_one(conn)
+    .await?;
+
+    Ok(format!("INV-{}-{:06}", year, number))
+}
+
+// The invoice of an order, issuing it on first use. None when the order
+// has no invoice and can't get one yet. The order must be locked by the
+// caller so two requests can't both issue one.
+pub async fn invoice_for_order(
+    conn: &mut PgConnection,
+    order: &Order,
+    issued_by: Option<i32>,
+) -> Result<Option<Invoice>, sqlx::Error> {
+    if let Some(invoice) = sqlx::query_as::<_, Invoice>("SELECT * FROM invoices WHERE order_id = $1")
+        .bind(order.id)
+        .fetch_optional(&mut *conn)
+        .await?
+    {
+        return Ok(Some(invoice));
+    }
+    if !can_invoice(order.status) {
+        return Ok(None);
+    }
+
+    let items = sqlx::query_as::<_, OrderItem>("SELECT * FROM order_items WHERE order_id = $1 ORDER BY id")
+        .bind(order.id)
+        .fetch_all(&mut *conn)
+        .await?;
+
+    let (account_name,): (String,) = sqlx::query_as(
+        "SELECT first_name || ' ' || last_name FROM users WHERE id = $1"
+    )
+    .bind(order.user_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let lines = items
+        .into_iter()
+        .map(|item| {
+            let variant: Vec<&str> = [item.size.as_deref(), item.color.as_deref()].into_iter().flatten().collect();
+            let name = item.product_name.unwrap_or_else(|| format!("Product #{}", item.product_id));
+            InvoiceLine {
+                description: if variant.is_empty() { name } else { format!("{} ({})", name, variant.join(", ")) },
+                quantity: item.quantity,
+                unit_price: item.price_at_time,
+                total: item.total_price,
+            }
+        })
+        .collect();
+
+    let mut tax_lines = Vec::new();
+    if order.tax_amount > BigDecimal::zero() {
+        let label = format!("{} {}%", TaxRules::from_env().label, order.tax_rate.normalized());
+        tax_lines.push(InvoiceTaxLine { label: "Taxable base (DPP)".to_string(), amount: order.taxable_amount.clone() });
+        tax_lines.push(InvoiceTaxLine {
+            label: if order.prices_include_tax { format!("{} (included)", label) } else { label },
+            amount: order.tax_amount.clone(),
+        });
+    }
+
+    let content = InvoiceContent {
+        seller: seller_from_env(),
+        bill_to: bill_to(order, account_name),
+        order_number: order.order_number.clone(),
+        order_date: order.created_at,
+        payment_method: order.payment_method.clone(),
+        lines,
+        subtotal: order.total_amount.clone(),
+        discount: order.discount_amount.clone(),
+        shipping: order.shipping_cost.clone(),
+        tax_lines,
+        total: order.final_amount.clone(),
+    };
+
+    let invoice_number = next_invoice_number(&mut *conn, Utc::now().year()).await?;
+
+    sqlx::query_as::<_, Invoice>(
+        "INSERT INTO invoices (order_id, invoice_number, content, issued_by)
+         VALUES ($1, $2, $3, $4)
+         RETURNING *"
+    )
+    .bind(order.id)
+    .bind(invoice_number)
+    .bind(Json(content))
+    .bind(issued_by)
+    .fetch_one(&mut *conn)
+    .await
+    .map(Some)
+}
+
+// 1234567.5 -> "Rp 1.234.567,50"
+fn rupiah(amount: &BigDecimal) -> String {
+    let fixed = amount.with_scale(2).abs().to_string();
+    let (whole, cents) = fixed.split_once('.').unwrap_or((&fixed, "00"));
+
+    let mut grouped = String::new();
+    for (i, digit) in whole.chars().enumerate() {
+        if i > 0 && (whole.len() - i) % 3 == 0 {
+            grouped.push('.');
+        }
+        grouped.push(digit);
+    }
+
+    let sign = if amount < &BigDecimal::zero() { "-" } else { "" };
+    if cents == "00" {
+        format!("{}Rp {}", sign, grouped)
+    } else {
+        format!("{}Rp {},{}", sign, grouped, cents)
+    }
+}
+
+const MARGIN: f32 = 50.0;
+const RIGHT: f32 = PAGE_WIDTH - MARGIN;
+const FOOTER_Y: f32 = 40.0;
+// Columns of the item table: description, quantity, unit price, amount
+const QTY_X: f32 = 355.0;
+const PRICE_X: f32 = 450.0;
+
+fn table_header(doc: &mut PdfDocument, y: f32) -> f32 {
+    doc.fill_rect(MARGIN, y - 6.0, RIGHT - MARGIN, 20.0, 0.92);
+    doc.text(MARGIN + 6.0, y, 9.0, Font::Bold, Align::Left, "Description");
+    doc.text(QTY_X, y, 9.0, Font::Bold, Align::Right, "Qty");
+    doc.text(PRICE_X, y, 9.0, Font::Bold, Align::Right, "Unit price");
+    doc.text(RIGHT - 6.0, y, 9.0, Font::Bold, Align::Right, "Amount");
+    y - 22.0
+}
+
+fn party(doc: &mut PdfDocument, x: f32, mut y: f32, heading: &str, party: &InvoiceParty) -> f32 {
+    doc.text(x, y, 8.0, Font::Bold, Align::Left, heading);
+    y -= 14.0;
+    doc.text(x, y, 10.0, Font::Bold, Align::Left, &fit(&party.name, 230.0, 10.0, Font::Bold));
+    y -= 13.0;
+    for line in &party.address {
+        doc.text(x, y, 9.0, Font::Regular, Align::Left, &fit(line, 230.0, 9.0, Font::Regular));
+        y -= 12.0;
+    }
+    if let Some(phone) = &party.phone {
+        doc.text(x, y, 9.0, Font::Regular, Align::Left, &format!("Phone: {}", phone));
+        y -= 12.0;
+    }
+    if let Some(tax_id) = &party.tax_id {
+        doc.text(x, y, 9.0, Font::Regular, Align::Left, &format!("NPWP: {}", tax_id));
+        y -= 12.0;
+    }
+    y
+}
+
+// Render an issued invoice as an A4 PDF. Only the stored snapshot is used,
+// so the same invoice always renders the same document.
+pub fn render_pdf(invoice: &Invoice) -> Vec<u8> {
+    let content = &invoice.content.0;
+    let mut doc = PdfDocument::new();
+
+    let mut y = PAGE_HEIGHT - 60.0;
+    doc.text(MARGIN, y, 22.0, Font::Bold, Align::Left, "INVOICE");
+    doc.text(RIGHT, y, 11.0, Font::Bold, Align::Right, &invoice.invoice_number);
+    y -= 16.0;
+    doc.text(RIGHT, y, 9.0, Font::Regular, Align::Right, &format!("Issued {}", invoice.issued_at.format("%d %b %Y")));
+    y -= 12.0;
+    doc.text(RIGHT, y, 9.0, Font::Regular, Align::Right, &format!("Order {}", content.order_number));
+    if let Some(order_date) = content.order_date {
+        y -= 12.0;
+        doc.text(RIGHT, y, 9.0, Font::Regular, Align::Right, &format!("Ordered {}", order_date.format("%d %b %Y")));
+    }
+    if let Some(payment_method) = &content.payment_method {
+        y -= 12.0;
+        doc.text(RIGHT, y, 9.0, Font::Regular, Align::Right, &format!("Payment: {}", payment_method));
+    }
+
+    y -= 30.0;
+    let seller_end = party(&mut doc, MARGIN, y, "FROM", &content.seller);
+    let buyer_end = party(&mut doc, PAGE_WIDTH / 2.0, y, "BILL TO", &content.bill_to);
+    y = seller_end.min(buyer_end) - 20.0;
+
+    y = table_header(&mut doc, y);
+    for line in &content.lines {
+        if y < FOOTER_Y + 40.0 {
+            doc.new_page();
+            y = table_header(&mut doc, PAGE_HEIGHT - 60.0);
+        }
+        doc.text(MARGIN + 6.0, y, 9.0, Font::Regular, Align::Left, &fit(&line.description, QTY_X - MARGIN - 40.0, 9.0, Font::Regular));
+        doc.text(QTY_X, y, 9.0, Font::Regular, Align::Right, &line.quantity.to_string());
+        doc.text(PRICE_X, y, 9.0, Font::Regular, Align::Right, &rupiah(&line.unit_price));
+        doc.text(RIGHT - 6.0, y, 9.0, Font::Regular, Align::Right, &rupiah(&line.total));
+        y -= 8.0;
+        doc.line(MARGIN, y, RIGHT, y, 0.5, 0.8);
+        y -= 14.0;
+    }
+
+    let mut totals: Vec<(String, String)> = vec![("Subtotal".to_string(), rupiah(&content.subtotal))];
+    if content.discount > BigDecimal::zero() {
+        totals.push(("Discount".to_string(), format!("-{}", rupiah(&content.discount))));
+    }
+    totals.push(("Shipping".to_string(), rupiah(&content.shipping)));
+    for tax in &content.tax_lines {
+        totals.push((tax.label.clone(), rupiah(&tax.amount)));
+    }
+
+    if y < FOOTER_Y + 30.0 + 16.0 * (totals.len() as f32 + 1.0) {
+        doc.new_page();
+        y = PAGE_HEIGHT - 60.0;
+    }
+    y -= 6.0;
+    for (label, amount) in &totals {
+        doc.text(PRICE_X, y, 9.0, Font::Regular, Align::Right, label);
+        doc.text(RIGHT - 6.0, y, 9.0, Font::Regular, Align::Right, amount);
+        y -= 16.0;
+    }
+    doc.line(QTY_X, y + 10.0, RIGHT, y + 10.0, 1.0, 0.0);
+    y -= 4.0;
+    doc.text(PRICE_X, y, 11.0, Font::Bold, Align::Right, "Total");
+    doc.text(RIGHT - 6.0, y, 11.0, Font::Bold, Align::Right, &rupiah(&content.total));
+
+    doc.text(MARGIN, FOOTER_Y, 8.0, Font::Regular, Align::Left, &format!("{} - {}", content.seller.name, invoice.invoice_number));
+    doc.finish(&format!("Invoice {}", invoice.invoice_number))
+}
@@ -0,0 +1,22 @@
+pub mod inventory;
+pub mod storage;
+pub mod images;
+pub mod slugs;
+pub mod carts;
+pub mod product_alerts;
+pub mod coupons;
+pub mod promotions;
+pub mod order_status;
+pub mod cancellation;
+pub mod shipping;
+pub mod tracking;
+pub mod returns;
+pub mod pdf;
+pub mod invoices;
+pub mod tax;
+pub mod idempotency;
+pub mod order_numbers;
+pub mod spreadsheet;
+pub mod order_search;
+pub mod return_rules;
+pub mod coupon_rules;
@@ -0,0 +1,61 @@
+use chrono::NaiveDate;
+use sqlx::PgConnection;
+
+// Luhn check digit over `digits`; catches any single mistyped or misheard
+// digit and most swapped neighbours
+pub fn check_digit(digits: &str) -> u32 {
+    let sum: u32 = digits
+        .chars()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(i, d)| if i % 2 == 0 { if d * 2 > 9 { d * 2 - 9 } else { d * 2 } } else { d })
+        .sum();
+    (10 - sum % 10) % 10
+}
+
+// BK-YYYYMMDD-NNNNNN-C: the day, its sequence number and a check digit over
+// both, e.g. BK-20261018-000123-7
+pub fn format_order_number(day: NaiveDate, sequence: i32) -> String {
+    let date = day.format("%Y%m%d").to_string();
+    let sequence = format!("{:06}", sequence);
+    let check = check_digit(&format!("{}{}", date, sequence));
+    format!("BK-{}-{}-{}", date, sequence, check)
+}
+
+// Whether `order_number` is one of ours with an intact check digit, so a
+// misread number can be caught before it's looked up
+pub fn is_valid_order_number(order_number: &str) -> bool {
+    let order_number = order_number.trim().to_uppercase();
+    let parts: Vec<&str> = order_number.split('-').collect();
+    let [prefix, date, sequence, check] = parts[..] else {
+        return false;
+    };
+    if prefix != "BK" || date.len() != 8 || sequence.len() != 6 || check.len() != 1 {
+        return false;
+    }
+    if !date.chars().chain(sequence.chars()).chain(check.chars()).all(|c| c.is_ascii_digit()) {
+        return false;
+    }
+    if NaiveDate::parse_from_str(date, "%Y%m%d").is_err() {
+        return false;
+    }
+    check_digit(&format!("{}{}", date, sequence)).to_string() == check
+}
+
+// Take the next number for today (Asia/Jakarta). The counter row stays
+// locked until the caller's transaction ends, which keeps the numbers free of
+// gaps. A day has room for 999,999 orders; past that the counter's CHECK
+// constraint fails the checkout rather than changing the number's shape.
+pub async fn next_order_number(conn: &mut PgConnection) -> Result<String, sqlx::Error> {
+    let (day, sequence): (NaiveDate, i32) = sqlx::query_as(
+        "INSERT INTO order_number_counters (day, last_number)
+         VALUES ((NOW() AT TIME ZONE 'Asia/Jakarta')::DATE, 1)
+         ON CONFLICT (day) DO UPDATE SET last_number = order_number_counters.last_number + 1
+         RETURNING day, last_number"
+    )
+    .fetch_one(conn)
+    .await?;
+
+    Ok(format_order_number(day, sequence))
+}
@@ -0,0 +1,218 @@
+use actix_web::{error, web};
+use futures_util::{Stream, TryStreamExt};
+use sqlx::PgPool;
+use tokio::sync::mpsc;
+
+use crate::models::order::{AdminOrderQuery, OrderExportRow};
+use crate::services::spreadsheet::{Cell, SheetFormat, SheetWriter};
+
+const ORDER_STATUSES: [&str; 7] = ["pending", "confirmed", "processing", "shipped", "delivered", "cancelled", "refunded"];
+const PAYMENT_STATUSES: [&str; 4] = ["pending", "paid", "failed", "refunded"];
+const DEFAULT_PER_PAGE: i64 = 20;
+const MAX_PER_PAGE: i64 = 100;
+// Export output is sent to the client in chunks of about this size
+const EXPORT_CHUNK_BYTES: usize = 64 * 1024;
+
+const EXPORT_HEADER: [&str; 21] = [
+    "Order ID", "Order number", "Order date", "Status", "Payment status", "Payment method",
+    "Customer", "Email", "Subtotal", "Discount", "Shipping", "Tax", "Order total",
+    "Product ID", "Product", "Size", "Color", "Quantity", "Unit price", "Line total", "Line tax",
+];
+
+// The filters and sort order of an admin order search, as SQL over
+// `orders o JOIN users u`. Parameters are bound as text, in order.
+pub struct OrderSearch {
+    pub where_sql: String,
+    pub order_by: String,
+    pub params: Vec<String>,
+}
+
+impl OrderSearch {
+    pub fn from_query(query: &AdminOrderQuery) -> Result<Self, String> {
+        let mut conditions: Vec<String> = Vec::new();
+        let mut params: Vec<String> = Vec::new();
+
+        if let Some(status) = &query.status {
+            let status = status.trim().to_lowercase();
+            if !ORDER_STATUSES.contains(&status.as_str()) {
+                return Err(format!("Invalid status, expected one of: {}", ORDER_STATUSES.join(", ")));
+            }
+            params.push(status);
+            conditions.push(format!("o.status = ${}", params.len()));
+        }
+
+        if let Some(payment_status) = &query.payment_status {
+            let payment_status = payment_status.trim().to_lowercase();
+            if !PAYMENT_STATUSES.contains(&payment_status.as_str()) {
+                return Err(format!("Invalid payment_status, expected one of: {}", PAYMENT_STATUSES.join(", ")));
+            }
+            params.push(payment_status);
+            conditions.push(format!("o.payment_status = ${}", params.len()));
+        }
+
+        if let (Some(from), Some(to)) = (query.date_from, query.date_to) {
+            if from > to {
+                return Err("date_from must not be after date_to".to_string());
+            }
+        }
+        if let Some(from) = query.date_from {
+            params.push(from.to_string());
+            conditions.push(format!("o.created_at >= ${}::DATE", params.len()));
+        }
+        if let Some(to) = query.date_to {
+            params.push(to.to_string());
+            conditions.push(format!("o.created_at < ${}::DATE + 1", params.len()));
+        }
+
+        if let Some(email) = query.email.as_deref().map(str::trim).filter(|email| !email.is_empty()) {
+            params.push(format!("%{}%", email));
+            conditions.push(format!("u.email ILIKE ${}", params.len()));
+        }
+
+        if let Some(order_number) = query.order_number.as_deref().map(str::trim).filter(|number| !number.is_empty()) {
+            params.push(format!("%{}%", order_number));
+            conditions.push(format!("o.order_number ILIKE ${}", params.len()));
+        }
+
+        if let Some(product_id) = query.product_id {
+            params.push(product_id.to_string());
+            conditions.push(format!(
+                "EXISTS (SELECT 1 FROM order_items oi WHERE oi.order_id = o.id AND oi.product_id = ${}::INTEGER)",
+                params.len()
+            ));
+        }
+
+        if let Some(product) = query.product.as_deref().map(str::trim).filter(|product| !product.is_empty()) {
+            params.push(format!("%{}%", product));
+            conditions.push(format!(
+                "EXISTS (SELECT 1 FROM order_items oi LEFT JOIN products p ON p.id = oi.product_id
+                         WHERE oi.order_id = o.id AND COALESCE(oi.product_name, p.name) ILIKE ${})",
+                params.len()
+            ));
+        }
+
+        let direction = match query.sort_order.as_deref().unwrap_or("desc") {
+            "asc" => "ASC",
+            "desc" => "DESC",
+            _ => return Err("Invalid sort_order, expected asc or desc".to_string()),
+        };
+        // Ends with the id so pages and export lines come in a stable order
+        let order_by = match query.sort_by.as_deref().unwrap_or("created_at") {
+            "created_at" => format!("o.created_at {0}, o.id {0}", direction),
+            "final_amount" => format!("o.final_amount {0}, o.id {0}", direction),
+            "order_number" => format!("o.order_number {0}, o.id {0}", direction),
+            _ => return Err("Invalid sort_by, expected one of: created_at, final_amount, order_number".to_string()),
+        };
+
+        let where_sql = if conditions.is_empty() {
+            "TRUE".to_string()
+        } else {
+            conditions.join(" AND ")
+        };
+
+        Ok(OrderSearch { where_sql, order_by, params })
+    }
+}
+
+// (page, per_page) of a list request, page counting from 1. The page has to
+// fit the i32 of the response, which also keeps its offset far from overflowing.
+pub fn page(query: &AdminOrderQuery) -> Result<(i64, i64), String> {
+    let page = query.page.unwrap_or(1).max(1);
+    if page > i32::MAX as i64 {
+        return Err(format!("page must be at most {}", i32::MAX));
+    }
+    Ok((page, query.per_page.unwrap_or(DEFAULT_PER_PAGE).clamp(1, MAX_PER_PAGE)))
+}
+
+fn amount(value: &bigdecimal::BigDecimal) -> Cell {
+    Cell::Number(value.to_string())
+}
+
+// Order amounts only go on an order's first line, so totals summed over the
+// sheet aren't multiplied by the number of items
+fn export_cells(row: &OrderExportRow, first_line: bool) -> Vec<Cell> {
+    let order_amount = |value| if first_line { amount(value) } else { Cell::Empty };
+    vec![
+        Cell::Number(row.order_id.to_string()),
+        row.order_number.as_str().into(),
+        row.created_at.map(|at| at.format("%Y-%m-%d %H:%M:%S").to_string()).into(),
+        row.status.as_str().into(),
+        row.payment_status.as_str().into(),
+        row.payment_method.clone().into(),
+        row.user_name.as_str().into(),
+        row.user_email.as_str().into(),
+        order_amount(&row.total_amount),
+        order_amount(&row.discount_amount),
+        order_amount(&row.shipping_cost),
+        order_amount(&row.tax_amount),
+        order_amount(&row.final_amount),
+        row.product_id.map(|id| Cell::Number(id.to_string())).unwrap_or(Cell::Empty),
+        row.product_name.clone().into(),
+        row.size.clone().into(),
+        row.color.clone().into(),
+        row.quantity.map(|quantity| Cell::Number(quantity.to_string())).unwrap_or(Cell::Empty),
+        row.price_at_time.as_ref().map(amount).unwrap_or(Cell::Empty),
+        row.item_total.as_ref().map(amount).unwrap_or(Cell::Empty),
+        row.item_tax.as_ref().map(amount).unwrap_or(Cell::Empty),
+    ]
+}
+
+type ExportChunk = Result<web::Bytes, actix_web::Error>;
+
+async fn write_export(
+    pool: &PgPool,
+    search: &OrderSearch,
+    format: SheetFormat,
+    tx: &mpsc::Sender<ExportChunk>,
+) -> Result<(), sqlx::Error> {
+    let sql = format!(
+        "SELECT o.id AS order_id, o.order_number, o.created_at, o.status, o.payment_status, o.payment_method,
+                u.name AS user_name, u.email AS user_email,
+                o.total_amount, o.discount_amount, o.shipping_cost, o.tax_amount, o.final_amount,
+                oi.product_id, COALESCE(oi.product_name, p.name) AS product_name, oi.size, oi.color, oi.quantity, oi.price_at_time,
+                oi.total_price AS item_total, oi.tax_amount AS item_tax
+         FROM orders o
+         JOIN users u ON u.id = o.user_id
+         LEFT JOIN order_items oi ON oi.order_id = o.id
+         LEFT JOIN products p ON p.id = oi.product_id
+         WHERE {}
+         ORDER BY {}, oi.id",
+        search.where_sql, search.order_by
+    );
+    let mut query = sqlx::query_as::<_, OrderExportRow>(&sql);
+    for param in &search.params {
+        query = query.bind(param);
+    }
+    let mut rows = query.fetch(pool);
+
+    let mut sheet = SheetWriter::new(format, "Orders");
+    sheet.write_row(&EXPORT_HEADER.map(Cell::from));
+    let mut last_order = None;
+    while let Some(row) = rows.try_next().await? {
+        sheet.write_row(&export_cells(&row, last_order != Some(row.order_id)));
+        last_order = Some(row.order_id);
+
+        if sheet.buffered() >= EXPORT_CHUNK_BYTES && tx.send(Ok(web::Bytes::from(sheet.take()))).await.is_err() {
+            // The client went away
+            return Ok(());
+        }
+    }
+
+    let _ = tx.send(Ok(web::Bytes::from(sheet.finish()))).await;
+    Ok(())
+}
+
+// The matching orders and their items as a spreadsheet, produced while the
+// rows are read so the export never sits in memory as a whole. A database
+// error midway aborts the download.
+pub fn export(pool: PgPool, search: OrderSearch, format: SheetFormat) -> impl Stream<Item = ExportChunk> {
+    let (tx, rx) = mpsc::channel::<ExportChunk>(4);
+    actix_web::rt::spawn(async move {
+        if let Err(e) = write_export(&pool, &search, format, &tx).await {
+            eprintln!("Database error: {}", e);
+            let _ = tx.send(Err(error::ErrorInternalServerError("Order export failed"))).await;
+        }
+    });
+
+    futures_util::stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|chunk| (chunk, rx)) })
+}
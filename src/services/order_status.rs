@@ -0,0 +1,127 @@
+use sqlx::PgConnection;
+use std::fmt;
+use crate::models::order::{Order, OrderStatus};
+
+pub const ACTOR_CUSTOMER: &str = "customer";
+pub const ACTOR_ADMIN: &str = "admin";
+pub const ACTOR_SYSTEM: &str = "system";
+
+// Statuses an order can move to from `from`. Cancelled and Refunded are
+// final; a delivered order can only be refunded.
+pub fn allowed_transitions(from: OrderStatus) -> &'static [OrderStatus] {
+    use OrderStatus::*;
+    match from {
+        Pending => &[Confirmed, Cancelled],
+        Confirmed => &[Processing, Shipped, Cancelled],
+        Processing => &[Shipped, Cancelled],
+        Shipped => &[Delivered],
+        Delivered => &[Refunded],
+        Cancelled | Refunded => &[],
+    }
+}
+
+#[derive(Debug)]
+pub enum TransitionError {
+    NotAllowed { from: OrderStatus, to: OrderStatus },
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for TransitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransitionError::NotAllowed { from, to } if from == to => {
+                write!(f, "Order is already {}", from.as_str())
+            }
+            TransitionError::NotAllowed { from, to } => {
+                let allowed: Vec<&str> = allowed_transitions(*from).iter().map(|s| s.as_str()).collect();
+                write!(f, "Cannot change a {} order to {}", from.as_str(), to.as_str())?;
+                if allowed.is_empty() {
+                    write!(f, " (no further changes are possible)")
+                } else {
+                    write!(f, " (allowed: {})", allowed.join(", "))
+                }
+            }
+            TransitionError::Database(e) => write!(f, "database error: {}", e),
+        }
+    }
+}
+
+impl From<sqlx::Error> for TransitionError {
+    fn from(e: sqlx::Error) -> Self {
+        TransitionError::Database(e)
+    }
+}
+
+// Who changed the status; background jobs have no user
+pub struct Actor {
+    pub user_id: Option<i32>,
+    pub role: &'static str,
+}
+
+impl Actor {
+    pub fn customer(user_id: i32) -> Self {
+        Actor { user_id: Some(user_id), role: ACTOR_CUSTOMER }
+    }
+
+    pub fn admin(user_id: i32) -> Self {
+        Actor { user_id: Some(user_id), role: ACTOR_ADMIN }
+    }
+
+    pub fn system() -> Self {
+        Actor { user_id: None, role: ACTOR_SYSTEM }
+    }
+}
+
+pub async fn record_change(
+    conn: &mut PgConnection,
+    order_id: i32,
+    from: Option<OrderStatus>,
+    to: OrderStatus,
+    actor: &Actor,
+    note: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO order_status_history (order_id, from_status, to_status, actor_id, actor_role, note)
+         VALUES ($1, $2, $3, $4, $5, $6)"
+    )
+    .bind(order_id)
+    .bind(from)
+    .bind(to)
+    .bind(actor.user_id)
+    .bind(actor.role)
+    .bind(note.map(str::trim).filter(|n| !n.is_empty()))
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+// Move an order (locked by the caller) to `to`, stamping shipped_at or
+// delivered_at and recording the change
+pub async fn transition(
+    conn: &mut PgConnection,
+    order: &Order,
+    to: OrderStatus,
+    actor: &Actor,
+    note: Option<&str>,
+) -> Result<Order, TransitionError> {
+    if !allowed_transitions(order.status).contains(&to) {
+        return Err(TransitionError::NotAllowed { from: order.status, to });
+    }
+
+    let updated = sqlx::query_as::<_, Order>(
+        "UPDATE orders SET status = $2, updated_at = NOW(),
+                shipped_at = CASE WHEN $2 = 'shipped' THEN NOW() ELSE shipped_at END,
+                delivered_at = CASE WHEN $2 = 'delivered' THEN NOW() ELSE delivered_at END
+         WHERE id = $1
+         RETURNING *"
+    )
+    .bind(order.id)
+    .bind(to)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    record_change(conn, order.id, Some(order.status), to, actor, note).await?;
+
+    Ok(updated)
+}
@@ -0,0 +1,185 @@
+use pdf_writer::{Content, Finish, Name, Pdf, Rect, Ref, Str, TextStr};
+
+// A4 in points
+pub const PAGE_WIDTH: f32 = 595.0;
+pub const PAGE_HEIGHT: f32 = 842.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Font {
+    Regular,
+    Bold,
+}
+
+impl Font {
+    fn resource_name(self) -> Name<'static> {
+        match self {
+            Font::Regular => Name(b"F1"),
+            Font::Bold => Name(b"F2"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+// Glyph widths of the standard Helvetica fonts for ASCII 32..=126, in
+// 1/1000 of the font size. The standard 14 fonts need no embedding, which
+// keeps the renderer free of font files.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+const HELVETICA_BOLD_WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611,
+    975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556,
+    333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611,
+    611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+// Text as WinAnsi bytes; characters the encoding can't show become '?'
+fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c as u32 {
+            0x20..=0x7e | 0xa0..=0xff => c as u8,
+            _ => b'?',
+        })
+        .collect()
+}
+
+// Width of `text` in points
+pub fn text_width(text: &str, size: f32, font: Font) -> f32 {
+    let widths = match font {
+        Font::Regular => &HELVETICA_WIDTHS,
+        Font::Bold => &HELVETICA_BOLD_WIDTHS,
+    };
+    let units: u32 = encode(text)
+        .into_iter()
+        .map(|b| match b {
+            0x20..=0x7e => widths[(b - 0x20) as usize] as u32,
+            _ => 556,
+        })
+        .sum();
+    units as f32 * size / 1000.0
+}
+
+// Shorten `text` with an ellipsis so it fits in `max_width`
+pub fn fit(text: &str, max_width: f32, size: f32, font: Font) -> String {
+    if text_width(text, size, font) <= max_width {
+        return text.to_string();
+    }
+    let mut fitted: String = text.to_string();
+    while !fitted.is_empty() && text_width(&format!("{}...", fitted), size, font) > max_width {
+        fitted.pop();
+    }
+    format!("{}...", fitted.trim_end())
+}
+
+// A document of A4 pages drawn with absolute coordinates (origin bottom-left)
+#[derive(Default)]
+pub struct PdfDocument {
+    pages: Vec<Content>,
+}
+
+impl PdfDocument {
+    pub fn new() -> Self {
+        let mut document = Self::default();
+        document.new_page();
+        document
+    }
+
+    pub fn new_page(&mut self) {
+        self.pages.push(Content::new());
+    }
+
+    fn current(&mut self) -> &mut Content {
+        self.pages.last_mut().expect("a document always has a page")
+    }
+
+    pub fn text(&mut self, x: f32, y: f32, size: f32, font: Font, align: Align, text: &str) {
+        let x = match align {
+            Align::Left => x,
+            Align::Right => x - text_width(text, size, font),
+        };
+        let bytes = encode(text);
+        let content = self.current();
+        content.begin_text();
+        content.set_font(font.resource_name(), size);
+        content.next_line(x, y);
+        content.show(Str(&bytes));
+        content.end_text();
+    }
+
+    pub fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, width: f32, gray: f32) {
+        let content = self.current();
+        content.save_state();
+        content.set_line_width(width);
+        content.set_stroke_gray(gray);
+        content.move_to(x1, y1);
+        content.line_to(x2, y2);
+        content.stroke();
+        content.restore_state();
+    }
+
+    pub fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32, gray: f32) {
+        let content = self.current();
+        content.save_state();
+        content.set_fill_gray(gray);
+        content.rect(x, y, width, height);
+        content.fill_nonzero();
+        content.restore_state();
+    }
+
+    pub fn finish(self, title: &str) -> Vec<u8> {
+        let catalog_id = Ref::new(1);
+        let page_tree_id = Ref::new(2);
+        let regular_id = Ref::new(3);
+        let bold_id = Ref::new(4);
+        let info_id = Ref::new(5);
+        let first_page = 6;
+
+        let page_ids: Vec<Ref> = (0..self.pages.len())
+            .map(|i| Ref::new(first_page + 2 * i as i32))
+            .collect();
+
+        let mut pdf = Pdf::new();
+        pdf.catalog(catalog_id).pages(page_tree_id);
+        pdf.pages(page_tree_id)
+            .kids(page_ids.iter().copied())
+            .count(page_ids.len() as i32);
+        pdf.type1_font(regular_id)
+            .base_font(Name(b"Helvetica"))
+            .encoding_predefined(Name(b"WinAnsiEncoding"));
+        pdf.type1_font(bold_id)
+            .base_font(Name(b"Helvetica-Bold"))
+            .encoding_predefined(Name(b"WinAnsiEncoding"));
+        pdf.document_info(info_id).title(TextStr(title));
+
+        for (page_id, content) in page_ids.into_iter().zip(self.pages) {
+            let content_id = Ref::new(page_id.get() + 1);
+            let mut page = pdf.page(page_id);
+            page.parent(page_tree_id)
+                .media_box(Rect::new(0.0, 0.0, PAGE_WIDTH, PAGE_HEIGHT))
+                .contents(content_id);
+            let mut resources = page.resources();
+            let mut fonts = resources.fonts();
+            fonts.pair(Font::Regular.resource_name(), regular_id);
+            fonts.pair(Font::Bold.resource_name(), bold_id);
+            fonts.finish();
+            resources.finish();
+            page.finish();
+
+            pdf.stream(content_id, &content.finish());
+        }
+
+        pdf.finish()
+    }
+}
@@ -0,0 +1,153 @@
+use bigdecimal::BigDecimal;
+use sqlx::PgConnection;
+use crate::models::product::Product;
+
+pub const PRICE_DROP: &str = "price_drop";
+pub const BACK_IN_STOCK: &str = "back_in_stock";
+pub const ALERT_TYPES: [&str; 2] = [PRICE_DROP, BACK_IN_STOCK];
+
+// Alerts are favorite notifications so they follow the user's "favorite" preference
+const NOTIFICATION_TYPE: &str = "favorite";
+const DELIVERY_METHOD: &str = "app";
+// The same alert for the same product is sent at most once per window
+const REPEAT_WINDOW_HOURS: i32 = 24;
+// Upper bound on favorite alerts a user receives per day
+const DAILY_LIMIT: i64 = 10;
+
+// Price and stock of a product before an update. `current_price` is the
+// price customers pay, from product_prices, so running promotions count.
+#[derive(Debug, sqlx::FromRow)]
+pub struct ProductSnapshot {
+    pub slug: Option<String>,
+    pub current_price: BigDecimal,
+    pub stock: Option<i32>,
+}
+
+// Lock the product and take its snapshot before updating it
+pub async fn snapshot_for_update(conn: &mut PgConnection, product_id: i32) -> Result<Option<ProductSnapshot>, sqlx::Error> {
+    sqlx::query_as::<_, ProductSnapshot>(
+        "SELECT p.slug, pp.current_price, p.stock
+         FROM products p
+         JOIN product_prices pp ON pp.product_id = p.id
+         WHERE p.id = $1
+         FOR UPDATE OF p"
+    )
+    .bind(product_id)
+    .fetch_optional(conn)
+    .await
+}
+
+fn rupiah(amount: &BigDecimal) -> String {
+    format!("Rp {}", amount.with_scale(0))
+}
+
+// Notify interested users when an update lowered the price customers pay or
+// brought the product back in stock. Must run in the transaction that
+// updated the product. Returns the number of notifications created.
+pub async fn notify_product_changes(
+    conn: &mut PgConnection,
+    before: &ProductSnapshot,
+    after: &Product,
+) -> Result<u64, sqlx::Error> {
+    if !after.is_active {
+        return Ok(0);
+    }
+
+    let link = after.slug.as_ref().map(|slug| format!("/{}", slug));
+    let mut sent = 0;
+
+    let old_price = &before.current_price;
+    let new_price = sqlx::query_scalar::<_, BigDecimal>("SELECT current_price FROM product_prices WHERE product_id = $1")
+        .bind(after.id)
+        .fetch_one(&mut *conn)
+        .await?;
+    if &new_price < old_price {
+        sent += send_alert(
+            &mut *conn,
+            after.id,
+            PRICE_DROP,
+            &format!("Price drop: {}", after.name),
+            &format!("{} is now {} (was {}).", after.name, rupiah(&new_price), rupiah(old_price)),
+            link.as_deref(),
+            serde_json::json!({
+                "alert": PRICE_DROP,
+                "old_price": old_price.to_string(),
+                "new_price": new_price.to_string(),
+            }),
+        )
+        .await?;
+    }
+
+    if before.stock.unwrap_or(0) <= 0 && after.stock.unwrap_or(0) > 0 {
+        sent += send_alert(
+            &mut *conn,
+            after.id,
+            BACK_IN_STOCK,
+            &format!("Back in stock: {}", after.name),
+            &format!("{} is available again.", after.name),
+            link.as_deref(),
+            serde_json::json!({
+                "alert": BACK_IN_STOCK,
+                "stock": after.stock,
+            }),
+        )
+        .await?;
+    }
+
+    Ok(sent)
+}
+
+// Users who favorited the product (in any list) or subscribed to this
+// alert, minus those who turned favorite notifications off, were already
+// told about it recently or reached the daily limit
+async fn send_alert(
+    conn: &mut PgConnection,
+    product_id: i32,
+    alert_type: &str,
+    title: &str,
+    message: &str,
+    action_url: Option<&str>,
+    metadata: serde_json::Value,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT INTO notifications (user_id, title, message, type, reference_id, reference_type,
+                                    action_url, metadata, created_at, updated_at)
+         SELECT r.user_id, $3, $4, $5, $1, 'product', $6, $7, NOW(), NOW()
+         FROM (
+             SELECT user_id FROM favorites WHERE product_id = $1
+             UNION
+             SELECT user_id FROM product_alert_subscriptions WHERE product_id = $1 AND alert_type = $2
+         ) r
+         WHERE NOT EXISTS (
+                 SELECT 1 FROM notification_preferences np
+                 WHERE np.user_id = r.user_id AND np.notification_type = $5
+                   AND np.delivery_method = $8 AND np.enabled = false
+             )
+           AND NOT EXISTS (
+                 SELECT 1 FROM notifications n
+                 WHERE n.user_id = r.user_id AND n.type = $5
+                   AND n.reference_type = 'product' AND n.reference_id = $1
+                   AND n.metadata->>'alert' = $2
+                   AND n.created_at > NOW() - make_interval(hours => $9)
+             )
+           AND (
+                 SELECT COUNT(*) FROM notifications n
+                 WHERE n.user_id = r.user_id AND n.type = $5
+                   AND n.created_at > NOW() - INTERVAL '1 day'
+             ) < $10"
+    )
+    .bind(product_id)
+    .bind(alert_type)
+    .bind(title)
+    .bind(message)
+    .bind(NOTIFICATION_TYPE)
+    .bind(action_url)
+    .bind(metadata)
+    .bind(DELIVERY_METHOD)
+    .bind(REPEAT_WINDOW_HOURS)
+    .bind(DAILY_LIMIT)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
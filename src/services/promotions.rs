@@ -0,0 +1,40 @@
+use sqlx::PgConnection;
+
+// Count `quantity` units against a promotion's flash-sale cap. Like the
+// stock decrement this only succeeds while enough of the cap is left, so
+// concurrent checkouts can't oversell the sale. Uncapped promotions always
+// succeed.
+pub async fn claim_promotion_quantity(
+    conn: &mut PgConnection,
+    promotion_id: i32,
+    quantity: i32,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE promotions SET sold_quantity = sold_quantity + $2
+         WHERE id = $1 AND (quantity_limit IS NULL OR sold_quantity + $2 <= quantity_limit)"
+    )
+    .bind(promotion_id)
+    .bind(quantity)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Return the units a cancelled order bought under flash sales to their caps
+pub async fn release_order_quantity(conn: &mut PgConnection, order_id: i32) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE promotions p SET sold_quantity = GREATEST(p.sold_quantity - oi.quantity, 0)
+         FROM (
+             SELECT promotion_id, SUM(quantity)::INTEGER AS quantity FROM order_items
+             WHERE order_id = $1 AND promotion_id IS NOT NULL
+             GROUP BY promotion_id
+         ) oi
+         WHERE p.id = oi.promotion_id"
+    )
+    .bind(order_id)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
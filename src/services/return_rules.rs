@@ -0,0 +1,203 @@
+use bigdecimal::{BigDecimal, Zero};
+use chrono::{Duration, NaiveDateTime};
+use sqlx::PgConnection;
+use std::env;
+
+// The rules of the return (RMA) workflow that decide quantities and money.
+// Kept free of the app's models so the tests can run them on their own.
+
+const DEFAULT_RETURN_WINDOW_DAYS: i64 = 7;
+
+// Days after delivery during which the buyer can ask for a return
+pub fn return_window_days() -> i64 {
+    env::var("RETURN_WINDOW_DAYS")
+        .ok()
+        .and_then(|v| v.parse::<i64>().ok())
+        .filter(|days| *days > 0)
+        .unwrap_or(DEFAULT_RETURN_WINDOW_DAYS)
+}
+
+pub fn within_return_window(delivered_at: Option<NaiveDateTime>, now: NaiveDateTime, window_days: i64) -> bool {
+    delivered_at.is_some_and(|delivered_at| now <= delivered_at + Duration::days(window_days))
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ReturnLineError {
+    NotInOrder(i32),
+    TooMany { order_item_id: i32, available: i64 },
+}
+
+// Check the (order_item_id, quantity) lines of a new return against what
+// was bought and what other returns, except rejected ones, already cover.
+// The caller holds the order row locked.
+pub async fn check_return_lines(
+    conn: &mut PgConnection,
+    order_id: i32,
+    lines: &[(i32, i32)],
+) -> Result<Result<(), ReturnLineError>, sqlx::Error> {
+    let bought = sqlx::query_as::<_, (i32, i32, i64)>(
+        "SELECT oi.id, oi.quantity, COALESCE(SUM(ri.quantity) FILTER (WHERE rr.status <> 'rejected'), 0)
+         FROM order_items oi
+         LEFT JOIN return_items ri ON ri.order_item_id = oi.id
+         LEFT JOIN return_requests rr ON rr.id = ri.return_id
+         WHERE oi.order_id = $1
+         GROUP BY oi.id, oi.quantity"
+    )
+    .bind(order_id)
+    .fetch_all(conn)
+    .await?;
+
+    for &(order_item_id, quantity) in lines {
+        let Some((_, quantity_bought, returning)) = bought.iter().find(|(id, _, _)| *id == order_item_id) else {
+            return Ok(Err(ReturnLineError::NotInOrder(order_item_id)));
+        };
+        let available = *quantity_bought as i64 - returning;
+        if quantity as i64 > available {
+            return Ok(Err(ReturnLineError::TooMany { order_item_id, available: available.max(0) }));
+        }
+    }
+
+    Ok(Ok(()))
+}
+
+// Put the returned units back on sale
+pub async fn restock_return(conn: &mut PgConnection, return_id: i32) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE products p SET
+             stock = COALESCE(p.stock, 0) + r.quantity,
+             sold_count = GREATEST(COALESCE(p.sold_count, 0) - r.quantity, 0)
+         FROM (
+             SELECT oi.product_id, SUM(ri.quantity)::INTEGER AS quantity
+             FROM return_items ri
+             JOIN order_items oi ON oi.id = ri.order_item_id
+             WHERE ri.return_id = $1
+             GROUP BY oi.product_id
+         ) r
+         WHERE p.id = r.product_id"
+    )
+    .bind(return_id)
+    .execute(conn)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+#[derive(Debug, PartialEq)]
+pub enum RefundError {
+    NotFound,
+    // Only received returns can be refunded; holds the current status
+    NotReceived(String),
+    // The requested amount is more than what is left of the payment
+    AmountTooHigh(BigDecimal),
+    AlreadyRefunded,
+}
+
+#[derive(Debug)]
+pub struct ReturnRefund {
+    pub refund_id: i32,
+    pub order_id: i32,
+    pub amount: BigDecimal,
+    // Every unit of a delivered order has now been returned and refunded,
+    // so the order itself should become Refunded
+    pub order_fully_refunded: bool,
+}
+
+// Record the refund of a received return and mark it refunded. Without an
+// amount the value of the returned units is refunded, in any case never more
+// than is left of the order's payment. Locks the return, then the order, so
+// a second refund of the same return waits and then finds it refunded.
+pub async fn refund_return(
+    conn: &mut PgConnection,
+    return_id: i32,
+    requested: Option<&BigDecimal>,
+    created_by: i32,
+) -> Result<Result<ReturnRefund, RefundError>, sqlx::Error> {
+    let request = sqlx::query_as::<_, (String, i32, String)>(
+        "SELECT status, order_id, reason FROM return_requests WHERE id = $1 FOR UPDATE"
+    )
+    .bind(return_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let (order_id, reason) = match request {
+        None => return Ok(Err(RefundError::NotFound)),
+        Some((status, _, _)) if status != "received" => return Ok(Err(RefundError::NotReceived(status))),
+        Some((_, order_id, reason)) => (order_id, reason),
+    };
+
+    let (final_amount, order_status): (BigDecimal, String) =
+        sqlx::query_as("SELECT final_amount, status FROM orders WHERE id = $1 FOR UPDATE")
+            .bind(order_id)
+            .fetch_one(&mut *conn)
+            .await?;
+
+    let already_refunded = sqlx::query_scalar::<_, BigDecimal>(
+        "SELECT COALESCE(SUM(amount), 0) FROM refunds WHERE order_id = $1"
+    )
+    .bind(order_id)
+    .fetch_one(&mut *conn)
+    .await?;
+    let refundable = &final_amount - &already_refunded;
+
+    // What was paid for the returned units: the line after its share of the
+    // coupon discounts, plus its tax when that was added on top of the price
+    let items_value = sqlx::query_scalar::<_, BigDecimal>(
+        "SELECT COALESCE(SUM(ROUND(
+             (oi.total_price - oi.discount_amount
+              + CASE WHEN o.prices_include_tax THEN 0 ELSE oi.tax_amount END)
+             * ri.quantity / oi.quantity, 2)), 0)
+         FROM return_items ri
+         JOIN order_items oi ON oi.id = ri.order_item_id
+         JOIN orders o ON o.id = oi.order_id
+         WHERE ri.return_id = $1"
+    )
+    .bind(return_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    let amount = match requested {
+        Some(amount) if *amount > refundable => return Ok(Err(RefundError::AmountTooHigh(refundable))),
+        Some(amount) => amount.clone(),
+        None => items_value.min(refundable),
+    };
+    if amount <= BigDecimal::zero() {
+        return Ok(Err(RefundError::AlreadyRefunded));
+    }
+
+    let refund_id = sqlx::query_scalar::<_, i32>(
+        "INSERT INTO refunds (order_id, return_id, amount, reason, created_by)
+         VALUES ($1, $2, $3, $4, $5)
+         RETURNING id"
+    )
+    .bind(order_id)
+    .bind(return_id)
+    .bind(&amount)
+    .bind(format!("Return #{}: {}", return_id, reason))
+    .bind(created_by)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    sqlx::query("UPDATE return_requests SET status = 'refunded' WHERE id = $1 AND status = 'received'")
+        .bind(return_id)
+        .execute(&mut *conn)
+        .await?;
+
+    // Units bought that haven't come back in a refunded return
+    let units_left = sqlx::query_scalar::<_, i64>(
+        "SELECT COALESCE(SUM(oi.quantity - COALESCE((
+             SELECT SUM(ri.quantity) FROM return_items ri
+             JOIN return_requests rr ON rr.id = ri.return_id
+             WHERE ri.order_item_id = oi.id AND rr.status = 'refunded'
+         ), 0)), 0)::BIGINT
+         FROM order_items oi WHERE oi.order_id = $1"
+    )
+    .bind(order_id)
+    .fetch_one(&mut *conn)
+    .await?;
+
+    Ok(Ok(ReturnRefund {
+        refund_id,
+        order_id,
+        amount,
+        order_fully_refunded: units_left <= 0 && order_status == "delivered",
+    }))
+}
@@ -0,0 +1,65 @@
+use sqlx::PgConnection;
+use crate::models::returns::{ReturnItem, ReturnPhoto, ReturnRequest};
+
+// Fill in `items` and `photos` for a page of return requests
+pub async fn load_return_details(conn: &mut PgConnection, returns: &mut [ReturnRequest]) -> Result<(), sqlx::Error> {
+    if returns.is_empty() {
+        return Ok(());
+    }
+    let ids: Vec<i32> = returns.iter().map(|r| r.id).collect();
+
+    let items = sqlx::query_as::<_, ReturnItem>(
+        "SELECT ri.id, ri.return_id, ri.order_item_id, oi.product_id, oi.product_name, ri.quantity, oi.price_at_time
+         FROM return_items ri
+         JOIN order_items oi ON oi.id = ri.order_item_id
+         WHERE ri.return_id = ANY($1)
+         ORDER BY ri.id"
+    )
+    .bind(&ids)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let photos = sqlx::query_as::<_, ReturnPhoto>(
+        "SELECT * FROM return_photos WHERE return_id = ANY($1) ORDER BY sort_order, id"
+    )
+    .bind(&ids)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for item in items {
+        if let Some(request) = returns.iter_mut().find(|r| r.id == item.return_id) {
+            request.items.push(item);
+        }
+    }
+    for photo in photos {
+        if let Some(request) = returns.iter_mut().find(|r| Some(r.id) == photo.return_id) {
+            request.photos.push(photo);
+        }
+    }
+
+    Ok(())
+}
+
+// Tell the buyer what happened to their return
+pub async fn notify_buyer(
+    conn: &mut PgConnection,
+    request: &ReturnRequest,
+    title: &str,
+    message: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO notifications (user_id, title, message, type, reference_id, reference_type,
+                                    action_url, metadata, created_at, updated_at)
+         VALUES ($1, $2, $3, 'order', $4, 'order', $5, $6, NOW(), NOW())"
+    )
+    .bind(request.user_id)
+    .bind(title)
+    .bind(message)
+    .bind(request.order_id)
+    .bind(format!("/orders/{}", request.order_id))
+    .bind(serde_json::json!({ "return_id": request.id, "status": request.status }))
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,405 @@
+use async_trait::async_trait;
+use bigdecimal::{BigDecimal, ToPrimitive};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+use std::sync::Arc;
+
+// Couriers round the weight up to the next kilogram once the fraction
+// exceeds this many grams
+const ROUNDING_TOLERANCE_GRAMS: i32 = 300;
+
+#[derive(Debug)]
+pub enum ShippingError {
+    // No service delivers to the destination
+    Unserviceable(String),
+    #[cfg(feature = "rajaongkir")]
+    Backend(String),
+}
+
+impl fmt::Display for ShippingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShippingError::Unserviceable(msg) => write!(f, "{}", msg),
+            #[cfg(feature = "rajaongkir")]
+            ShippingError::Backend(msg) => write!(f, "shipping backend error: {}", msg),
+        }
+    }
+}
+
+// Destination and total weight of a parcel. The rate table only looks at
+// the province; API-backed providers resolve the city or postal code.
+#[derive(Debug, Clone)]
+#[cfg_attr(not(feature = "rajaongkir"), allow(dead_code))]
+pub struct ShipmentRequest {
+    pub province: String,
+    pub city: String,
+    pub postal_code: String,
+    pub weight_grams: i32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ShippingQuote {
+    pub courier: String,
+    pub service: String,
+    pub description: String,
+    pub cost: BigDecimal,
+    pub etd_days: Option<String>,
+}
+
+#[async_trait]
+pub trait ShippingProvider: Send + Sync {
+    // Every service that can deliver the parcel, cheapest first
+    async fn quote(&self, request: &ShipmentRequest) -> Result<Vec<ShippingQuote>, ShippingError>;
+}
+
+// Weight the courier charges for, in whole kilograms (at least 1)
+pub fn billable_kg(weight_grams: i32) -> i64 {
+    let grams = weight_grams.max(0) as i64;
+    let kg = grams / 1000 + if grams % 1000 > ROUNDING_TOLERANCE_GRAMS as i64 { 1 } else { 0 };
+    kg.max(1)
+}
+
+// Shipping weight of `quantity` units of a product weighing `weight_kg`;
+// None when it is too heavy to count in grams
+pub fn line_weight_grams(weight_kg: Option<&BigDecimal>, quantity: i32) -> Option<i32> {
+    match weight_kg {
+        Some(kg) => (kg * BigDecimal::from(1000) * BigDecimal::from(quantity)).round(0).to_i32().map(|grams| grams.max(0)),
+        None => Some(0),
+    }
+}
+
+// Total weight of (weight_kg, quantity) lines; None when it is too heavy to
+// count in grams, rather than wrapping around to a small parcel
+pub fn parcel_weight_grams<'a>(lines: impl IntoIterator<Item = (Option<&'a BigDecimal>, i32)>) -> Option<i32> {
+    lines
+        .into_iter()
+        .try_fold(0i32, |total, (weight_kg, quantity)| total.checked_add(line_weight_grams(weight_kg, quantity)?))
+}
+
+fn normalize_region(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceRate {
+    pub courier: String,
+    pub service: String, // REG, YES, ...
+    pub description: String,
+    pub per_kg: i64,
+    pub etd_days: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShippingZone {
+    pub name: String,
+    pub provinces: Vec<String>,
+    pub services: Vec<ServiceRate>,
+}
+
+// Flat per-kilogram rates by destination zone, from SHIPPING_RATES_FILE
+// (JSON list of zones) or the built-in table
+pub struct TableShippingProvider {
+    zones: Vec<ShippingZone>,
+}
+
+fn rate(service: &str, description: &str, per_kg: i64, etd_days: &str) -> ServiceRate {
+    ServiceRate {
+        courier: "jne".to_string(),
+        service: service.to_string(),
+        description: description.to_string(),
+        per_kg,
+        etd_days: Some(etd_days.to_string()),
+    }
+}
+
+fn zone(name: &str, provinces: &[&str], services: Vec<ServiceRate>) -> ShippingZone {
+    ShippingZone {
+        name: name.to_string(),
+        provinces: provinces.iter().map(|p| p.to_string()).collect(),
+        services,
+    }
+}
+
+impl TableShippingProvider {
+    pub fn new(zones: Vec<ShippingZone>) -> Self {
+        Self { zones }
+    }
+
+    // Rates from our warehouse on Java, per JNE zone
+    pub fn default_rates() -> Self {
+        Self::new(vec![
+            zone(
+                "Jawa",
+                &["DKI Jakarta", "Jakarta", "Banten", "Jawa Barat", "Jawa Tengah", "DI Yogyakarta", "Yogyakarta", "Jawa Timur"],
+                vec![rate("REG", "Layanan Reguler", 10000, "2-3"), rate("YES", "Yakin Esok Sampai", 18000, "1")],
+            ),
+            zone(
+                "Sumatera, Bali & NTB",
+                &[
+                    "Aceh", "Sumatera Utara", "Sumatera Barat", "Riau", "Kepulauan Riau", "Jambi", "Sumatera Selatan",
+                    "Kepulauan Bangka Belitung", "Bangka Belitung", "Bengkulu", "Lampung", "Bali", "Nusa Tenggara Barat",
+                ],
+                vec![rate("REG", "Layanan Reguler", 18000, "3-4"), rate("YES", "Yakin Esok Sampai", 30000, "2")],
+            ),
+            zone(
+                "Kalimantan & Sulawesi",
+                &[
+                    "Kalimantan Barat", "Kalimantan Tengah", "Kalimantan Selatan", "Kalimantan Timur", "Kalimantan Utara",
+                    "Sulawesi Utara", "Sulawesi Tengah", "Sulawesi Selatan", "Sulawesi Tenggara", "Sulawesi Barat", "Gorontalo",
+                ],
+                vec![rate("REG", "Layanan Reguler", 28000, "3-5"), rate("YES", "Yakin Esok Sampai", 45000, "2")],
+            ),
+            zone(
+                "Nusa Tenggara Timur & Maluku",
+                &["Nusa Tenggara Timur", "Maluku", "Maluku Utara"],
+                vec![rate("REG", "Layanan Reguler", 40000, "4-6"), rate("YES", "Yakin Esok Sampai", 65000, "3")],
+            ),
+            zone(
+                "Papua",
+                &["Papua", "Papua Barat", "Papua Barat Daya", "Papua Selatan", "Papua Tengah", "Papua Pegunungan"],
+                vec![rate("REG", "Layanan Reguler", 60000, "5-8")],
+            ),
+        ])
+    }
+
+    pub fn from_file(path: &str) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path).map_err(|e| format!("cannot read {}: {}", path, e))?;
+        let zones = serde_json::from_str(&contents).map_err(|e| format!("invalid rate table {}: {}", path, e))?;
+        Ok(Self::new(zones))
+    }
+
+    fn zone_for(&self, province: &str) -> Option<&ShippingZone> {
+        let province = normalize_region(province);
+        self.zones
+            .iter()
+            .find(|zone| zone.provinces.iter().any(|p| normalize_region(p) == province))
+    }
+}
+
+#[async_trait]
+impl ShippingProvider for TableShippingProvider {
+    async fn quote(&self, request: &ShipmentRequest) -> Result<Vec<ShippingQuote>, ShippingError> {
+        let zone = self
+            .zone_for(&request.province)
+            .ok_or_else(|| ShippingError::Unserviceable(format!("We don't ship to {} yet", request.province.trim())))?;
+
+        let kg = billable_kg(request.weight_grams);
+        let mut quotes: Vec<ShippingQuote> = zone
+            .services
+            .iter()
+            .map(|rate| ShippingQuote {
+                courier: rate.courier.clone(),
+                service: rate.service.clone(),
+                description: rate.description.clone(),
+                cost: BigDecimal::from(rate.per_kg * kg),
+                etd_days: rate.etd_days.clone(),
+            })
+            .collect();
+        quotes.sort_by(|a, b| a.cost.cmp(&b.cost));
+        Ok(quotes)
+    }
+}
+
+// Pick the shipping provider from SHIPPING_PROVIDER (table by default)
+pub fn from_env() -> Arc<dyn ShippingProvider> {
+    if env::var("SHIPPING_PROVIDER").as_deref() == Ok("rajaongkir") {
+        #[cfg(feature = "rajaongkir")]
+        {
+            return Arc::new(rajaongkir::RajaOngkirProvider::from_env());
+        }
+        #[cfg(not(feature = "rajaongkir"))]
+        panic!("SHIPPING_PROVIDER=rajaongkir requires building with `--features rajaongkir`");
+    }
+
+    match env::var("SHIPPING_RATES_FILE") {
+        Ok(path) => Arc::new(TableShippingProvider::from_file(&path).unwrap_or_else(|e| panic!("{}", e))),
+        Err(_) => Arc::new(TableShippingProvider::default_rates()),
+    }
+}
+
+#[cfg(feature = "rajaongkir")]
+pub mod rajaongkir {
+    use super::{normalize_region, ShipmentRequest, ShippingError, ShippingProvider, ShippingQuote};
+    use async_trait::async_trait;
+    use bigdecimal::BigDecimal;
+    use serde::Deserialize;
+    use std::env;
+    use tokio::sync::OnceCell;
+
+    #[derive(Deserialize)]
+    struct Envelope<T> {
+        rajaongkir: Body<T>,
+    }
+
+    #[derive(Deserialize)]
+    struct Body<T> {
+        status: Status,
+        #[serde(default = "Vec::new")]
+        results: Vec<T>,
+    }
+
+    #[derive(Deserialize)]
+    struct Status {
+        code: u16,
+        description: String,
+    }
+
+    #[derive(Clone, Deserialize)]
+    struct City {
+        city_id: String,
+        province: String,
+        #[serde(rename = "type")]
+        kind: String, // Kabupaten, Kota
+        city_name: String,
+        postal_code: String,
+    }
+
+    #[derive(Deserialize)]
+    struct CourierResult {
+        code: String,
+        costs: Vec<CourierService>,
+    }
+
+    #[derive(Deserialize)]
+    struct CourierService {
+        service: String,
+        description: String,
+        cost: Vec<CostValue>,
+    }
+
+    #[derive(Deserialize)]
+    struct CostValue {
+        value: i64,
+        etd: String,
+    }
+
+    // RajaOngkir-compatible API: cities from GET /city, prices from POST /cost
+    // with the `key` header. The city list is fetched once and kept.
+    pub struct RajaOngkirProvider {
+        client: reqwest::Client,
+        base_url: String,
+        api_key: String,
+        origin_city_id: String,
+        couriers: Vec<String>,
+        cities: OnceCell<Vec<City>>,
+    }
+
+    impl RajaOngkirProvider {
+        pub fn new(base_url: &str, api_key: &str, origin_city_id: &str, couriers: Vec<String>) -> Self {
+            Self {
+                client: reqwest::Client::new(),
+                base_url: base_url.trim_end_matches('/').to_string(),
+                api_key: api_key.to_string(),
+                origin_city_id: origin_city_id.to_string(),
+                couriers,
+                cities: OnceCell::new(),
+            }
+        }
+
+        pub fn from_env() -> Self {
+            let couriers = env::var("RAJAONGKIR_COURIERS").unwrap_or_else(|_| "jne".to_string());
+            Self::new(
+                &env::var("RAJAONGKIR_BASE_URL").unwrap_or_else(|_| "https://api.rajaongkir.com/starter".to_string()),
+                &env::var("RAJAONGKIR_API_KEY").expect("RAJAONGKIR_API_KEY harus diset untuk SHIPPING_PROVIDER=rajaongkir"),
+                &env::var("RAJAONGKIR_ORIGIN_CITY_ID").expect("RAJAONGKIR_ORIGIN_CITY_ID harus diset"),
+                couriers.split(',').map(|c| c.trim().to_lowercase()).filter(|c| !c.is_empty()).collect(),
+            )
+        }
+
+        fn backend_error(e: impl std::fmt::Display) -> ShippingError {
+            ShippingError::Backend(e.to_string())
+        }
+
+        async fn unwrap_body<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<Vec<T>, ShippingError> {
+            let envelope: Envelope<T> = response.json().await.map_err(Self::backend_error)?;
+            if envelope.rajaongkir.status.code != 200 {
+                return Err(ShippingError::Backend(envelope.rajaongkir.status.description));
+            }
+            Ok(envelope.rajaongkir.results)
+        }
+
+        async fn cities(&self) -> Result<&Vec<City>, ShippingError> {
+            self.cities
+                .get_or_try_init(|| async {
+                    let response = self
+                        .client
+                        .get(format!("{}/city", self.base_url))
+                        .header("key", &self.api_key)
+                        .send()
+                        .await
+                        .map_err(Self::backend_error)?;
+                    Self::unwrap_body::<City>(response).await
+                })
+                .await
+        }
+
+        // Match on postal code first, then on the city name within the province
+        async fn destination_city_id(&self, request: &ShipmentRequest) -> Result<String, ShippingError> {
+            let cities = self.cities().await?;
+            let province = normalize_region(&request.province);
+            let city = normalize_region(&request.city);
+            let in_province = |c: &&City| normalize_region(&c.province) == province;
+
+            cities
+                .iter()
+                .filter(in_province)
+                .find(|c| c.postal_code == request.postal_code.trim())
+                .or_else(|| {
+                    cities.iter().filter(in_province).find(|c| {
+                        let name = normalize_region(&c.city_name);
+                        name == city || format!("{} {}", normalize_region(&c.kind), name) == city
+                    })
+                })
+                .map(|c| c.city_id.clone())
+                .ok_or_else(|| {
+                    ShippingError::Unserviceable(format!("Unknown destination {}, {}", request.city.trim(), request.province.trim()))
+                })
+        }
+    }
+
+    #[async_trait]
+    impl ShippingProvider for RajaOngkirProvider {
+        async fn quote(&self, request: &ShipmentRequest) -> Result<Vec<ShippingQuote>, ShippingError> {
+            let destination = self.destination_city_id(request).await?;
+            let weight = request.weight_grams.max(1).to_string();
+
+            let mut quotes = Vec::new();
+            for courier in &self.couriers {
+                let response = self
+                    .client
+                    .post(format!("{}/cost", self.base_url))
+                    .header("key", &self.api_key)
+                    .form(&[
+                        ("origin", self.origin_city_id.as_str()),
+                        ("destination", destination.as_str()),
+                        ("weight", weight.as_str()),
+                        ("courier", courier.as_str()),
+                    ])
+                    .send()
+                    .await
+                    .map_err(Self::backend_error)?;
+
+                for result in Self::unwrap_body::<CourierResult>(response).await? {
+                    for service in result.costs {
+                        if let Some(cost) = service.cost.first() {
+                            quotes.push(ShippingQuote {
+                                courier: result.code.clone(),
+                                service: service.service,
+                                description: service.description,
+                                cost: BigDecimal::from(cost.value),
+                                etd_days: Some(cost.etd.clone()).filter(|etd| !etd.is_empty()),
+                            });
+                        }
+                    }
+                }
+            }
+
+            if quotes.is_empty() {
+                return Err(ShippingError::Unserviceable("No courier service delivers to this destination".to_string()));
+            }
+            quotes.sort_by(|a, b| a.cost.cmp(&b.cost));
+            Ok(quotes)
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use sqlx::{PgConnection, PgPool};
+
+// Entity types stored in slug_redirects
+pub const PRODUCT: &str = "product";
+pub const CATEGORY: &str = "category";
+
+// `base`, or `base-2`, `base-3`, ... when another product already uses it
+pub async fn unique_product_slug(conn: &mut PgConnection, base: &str, except_id: Option<i32>) -> Result<String, sqlx::Error> {
+    let taken: Vec<(String,)> = sqlx::query_as(
+        "SELECT slug FROM products
+         WHERE (slug = $1 OR slug LIKE $1 || '-%') AND id IS DISTINCT FROM $2"
+    )
+    .bind(base)
+    .bind(except_id)
+    .fetch_all(&mut *conn)
+    .await?;
+
+    let taken: Vec<String> = taken.into_iter().map(|(slug,)| slug).collect();
+    let mut candidate = base.to_string();
+    let mut suffix = 2;
+    while taken.contains(&candidate) {
+        candidate = format!("{}-{}", base, suffix);
+        suffix += 1;
+    }
+
+    Ok(candidate)
+}
+
+// Remember `old_slug` so links to it can be redirected to the entity's
+// current slug. A slug that is in use again no longer redirects.
+pub async fn record_slug_change(
+    conn: &mut PgConnection,
+    entity_type: &str,
+    entity_id: i32,
+    old_slug: Option<&str>,
+    new_slug: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM slug_redirects WHERE entity_type = $1 AND old_slug = $2")
+        .bind(entity_type)
+        .bind(new_slug)
+        .execute(&mut *conn)
+        .await?;
+
+    if let Some(old_slug) = old_slug.filter(|old| *old != new_slug) {
+        sqlx::query(
+            "INSERT INTO slug_redirects (entity_type, old_slug, entity_id, created_at)
+             VALUES ($1, $2, $3, NOW())
+             ON CONFLICT (entity_type, old_slug) DO UPDATE SET entity_id = EXCLUDED.entity_id, created_at = NOW()"
+        )
+        .bind(entity_type)
+        .bind(old_slug)
+        .bind(entity_id)
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+// Id of the entity that used to be reachable under `slug`
+pub async fn find_redirect(pool: &PgPool, entity_type: &str, slug: &str) -> Result<Option<i32>, sqlx::Error> {
+    let redirect: Option<(i32,)> = sqlx::query_as(
+        "SELECT entity_id FROM slug_redirects WHERE entity_type = $1 AND old_slug = $2"
+    )
+    .bind(entity_type)
+    .bind(slug)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(redirect.map(|(entity_id,)| entity_id))
+}
@@ -0,0 +1,370 @@
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use std::io::Write;
+
+// Writes a single-sheet CSV or XLSX file row by row. Output is collected in
+// a buffer that `take` drains, so large exports can be streamed to the client
+// as they are produced instead of being built in memory first.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SheetFormat {
+    Csv,
+    Xlsx,
+}
+
+impl SheetFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "csv" => Some(SheetFormat::Csv),
+            "xlsx" => Some(SheetFormat::Xlsx),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            SheetFormat::Csv => "text/csv; charset=utf-8",
+            SheetFormat::Xlsx => "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            SheetFormat::Csv => "csv",
+            SheetFormat::Xlsx => "xlsx",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Text(String),
+    // A plain decimal such as "125000.00", stored as a number in XLSX
+    Number(String),
+    Empty,
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Self {
+        Cell::Text(text.to_string())
+    }
+}
+
+impl From<String> for Cell {
+    fn from(text: String) -> Self {
+        Cell::Text(text)
+    }
+}
+
+impl<T: Into<Cell>> From<Option<T>> for Cell {
+    fn from(value: Option<T>) -> Self {
+        value.map(Into::into).unwrap_or(Cell::Empty)
+    }
+}
+
+pub enum SheetWriter {
+    Csv(CsvWriter),
+    Xlsx(XlsxWriter),
+}
+
+impl SheetWriter {
+    pub fn new(format: SheetFormat, sheet_name: &str) -> Self {
+        match format {
+            SheetFormat::Csv => SheetWriter::Csv(CsvWriter::new()),
+            SheetFormat::Xlsx => SheetWriter::Xlsx(XlsxWriter::new(sheet_name)),
+        }
+    }
+
+    pub fn write_row(&mut self, cells: &[Cell]) {
+        match self {
+            SheetWriter::Csv(writer) => writer.write_row(cells),
+            SheetWriter::Xlsx(writer) => writer.write_row(cells),
+        }
+    }
+
+    // Bytes produced so far
+    pub fn buffered(&self) -> usize {
+        match self {
+            SheetWriter::Csv(writer) => writer.out.len(),
+            SheetWriter::Xlsx(writer) => writer.out.len(),
+        }
+    }
+
+    pub fn take(&mut self) -> Vec<u8> {
+        match self {
+            SheetWriter::Csv(writer) => std::mem::take(&mut writer.out),
+            SheetWriter::Xlsx(writer) => std::mem::take(&mut writer.out),
+        }
+    }
+
+    // The remaining bytes, ending the file
+    pub fn finish(self) -> Vec<u8> {
+        match self {
+            SheetWriter::Csv(writer) => writer.out,
+            SheetWriter::Xlsx(writer) => writer.finish(),
+        }
+    }
+}
+
+pub struct CsvWriter {
+    out: Vec<u8>,
+}
+
+impl CsvWriter {
+    fn new() -> Self {
+        // The BOM makes Excel read the file as UTF-8
+        CsvWriter { out: "\u{feff}".as_bytes().to_vec() }
+    }
+
+    fn write_row(&mut self, cells: &[Cell]) {
+        let fields: Vec<String> = cells
+            .iter()
+            .map(|cell| match cell {
+                Cell::Text(text) => csv_field(text),
+                Cell::Number(number) => number.clone(),
+                Cell::Empty => String::new(),
+            })
+            .collect();
+        self.out.extend_from_slice(fields.join(",").as_bytes());
+        self.out.extend_from_slice(b"\r\n");
+    }
+}
+
+// Quote a text field when needed. Text that a spreadsheet would run as a
+// formula gets a leading apostrophe, so customer-entered data can't inject one.
+pub fn csv_field(text: &str) -> String {
+    let text = if text.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", text)
+    } else {
+        text.to_string()
+    };
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text
+    }
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            // Other control characters are not allowed in XML at all
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if (c as u32) < 0x20 => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/></Types>"#;
+
+const ROOT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#;
+
+const WORKBOOK_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/></Relationships>"#;
+
+const SHEET_START: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>"#;
+
+const SHEET_END: &str = "</sheetData></worksheet>";
+
+// Zip flag: sizes and CRC follow the data in a descriptor
+const FLAG_DATA_DESCRIPTOR: u16 = 0x0008;
+// Zip flag: file names are UTF-8
+const FLAG_UTF8: u16 = 0x0800;
+const METHOD_DEFLATE: u16 = 8;
+// 1980-01-01 00:00 in MS-DOS format; entry times don't matter here
+const DOS_DATE: u16 = 0x0021;
+
+struct ZipEntry {
+    name: &'static str,
+    flags: u16,
+    offset: u32,
+    crc: u32,
+    compressed_size: u32,
+    size: u32,
+}
+
+// A minimal XLSX package: a workbook with one sheet of inline strings and
+// numbers. The sheet is deflated as rows arrive and its sizes written after
+// the data, which is what lets the zip be streamed. Offsets and sizes are
+// 32-bit, so a file is limited to 4 GB.
+pub struct XlsxWriter {
+    out: Vec<u8>,
+    written: u64,
+    entries: Vec<ZipEntry>,
+    sheet: DeflateEncoder<Vec<u8>>,
+    sheet_offset: u32,
+    sheet_crc: crc32fast::Hasher,
+    sheet_size: u64,
+    sheet_compressed: u64,
+}
+
+impl XlsxWriter {
+    fn new(sheet_name: &str) -> Self {
+        let mut writer = XlsxWriter {
+            out: Vec::new(),
+            written: 0,
+            entries: Vec::new(),
+            sheet: DeflateEncoder::new(Vec::new(), Compression::default()),
+            sheet_offset: 0,
+            sheet_crc: crc32fast::Hasher::new(),
+            sheet_size: 0,
+            sheet_compressed: 0,
+        };
+
+        let workbook = format!(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="{}" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+            xml_escape(&sheet_name.chars().take(31).collect::<String>())
+        );
+        writer.add_file("[Content_Types].xml", CONTENT_TYPES.as_bytes());
+        writer.add_file("_rels/.rels", ROOT_RELS.as_bytes());
+        writer.add_file("xl/workbook.xml", workbook.as_bytes());
+        writer.add_file("xl/_rels/workbook.xml.rels", WORKBOOK_RELS.as_bytes());
+
+        writer.sheet_offset = writer.written as u32;
+        writer.local_header("xl/worksheets/sheet1.xml", FLAG_DATA_DESCRIPTOR | FLAG_UTF8, 0, 0, 0);
+        writer.sheet_xml(SHEET_START);
+        writer
+    }
+
+    fn emit(&mut self, bytes: &[u8]) {
+        self.out.extend_from_slice(bytes);
+        self.written += bytes.len() as u64;
+    }
+
+    fn local_header(&mut self, name: &str, flags: u16, crc: u32, compressed_size: u32, size: u32) {
+        let mut header = Vec::with_capacity(30 + name.len());
+        header.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        header.extend_from_slice(&20u16.to_le_bytes());
+        header.extend_from_slice(&flags.to_le_bytes());
+        header.extend_from_slice(&METHOD_DEFLATE.to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(&DOS_DATE.to_le_bytes());
+        header.extend_from_slice(&crc.to_le_bytes());
+        header.extend_from_slice(&compressed_size.to_le_bytes());
+        header.extend_from_slice(&size.to_le_bytes());
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(name.as_bytes());
+        self.emit(&header);
+    }
+
+    // A small file whose sizes are known up front
+    fn add_file(&mut self, name: &'static str, data: &[u8]) {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).expect("writing to a Vec can't fail");
+        let compressed = encoder.finish().expect("writing to a Vec can't fail");
+        let crc = crc32fast::hash(data);
+
+        let offset = self.written as u32;
+        self.local_header(name, FLAG_UTF8, crc, compressed.len() as u32, data.len() as u32);
+        self.emit(&compressed);
+        self.entries.push(ZipEntry {
+            name,
+            flags: FLAG_UTF8,
+            offset,
+            crc,
+            compressed_size: compressed.len() as u32,
+            size: data.len() as u32,
+        });
+    }
+
+    fn sheet_xml(&mut self, xml: &str) {
+        self.sheet_crc.update(xml.as_bytes());
+        self.sheet_size += xml.len() as u64;
+        self.sheet.write_all(xml.as_bytes()).expect("writing to a Vec can't fail");
+        let compressed = std::mem::take(self.sheet.get_mut());
+        self.sheet_compressed += compressed.len() as u64;
+        self.emit(&compressed);
+    }
+
+    fn write_row(&mut self, cells: &[Cell]) {
+        let mut xml = String::from("<row>");
+        for cell in cells {
+            match cell {
+                Cell::Text(text) => {
+                    xml.push_str(r#"<c t="inlineStr"><is><t xml:space="preserve">"#);
+                    xml.push_str(&xml_escape(text));
+                    xml.push_str("</t></is></c>");
+                }
+                Cell::Number(number) => {
+                    xml.push_str("<c><v>");
+                    xml.push_str(&xml_escape(number));
+                    xml.push_str("</v></c>");
+                }
+                Cell::Empty => xml.push_str("<c/>"),
+            }
+        }
+        xml.push_str("</row>");
+        self.sheet_xml(&xml);
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        self.sheet_xml(SHEET_END);
+        let encoder = std::mem::replace(&mut self.sheet, DeflateEncoder::new(Vec::new(), Compression::default()));
+        let rest = encoder.finish().expect("writing to a Vec can't fail");
+        self.sheet_compressed += rest.len() as u64;
+        self.emit(&rest);
+
+        let crc = self.sheet_crc.clone().finalize();
+        let mut descriptor = Vec::with_capacity(16);
+        descriptor.extend_from_slice(&0x08074b50u32.to_le_bytes());
+        descriptor.extend_from_slice(&crc.to_le_bytes());
+        descriptor.extend_from_slice(&(self.sheet_compressed as u32).to_le_bytes());
+        descriptor.extend_from_slice(&(self.sheet_size as u32).to_le_bytes());
+        self.emit(&descriptor);
+        self.entries.push(ZipEntry {
+            name: "xl/worksheets/sheet1.xml",
+            flags: FLAG_DATA_DESCRIPTOR | FLAG_UTF8,
+            offset: self.sheet_offset,
+            crc,
+            compressed_size: self.sheet_compressed as u32,
+            size: self.sheet_size as u32,
+        });
+
+        let directory_offset = self.written as u32;
+        let mut directory = Vec::new();
+        for entry in &self.entries {
+            directory.extend_from_slice(&0x02014b50u32.to_le_bytes());
+            directory.extend_from_slice(&20u16.to_le_bytes());
+            directory.extend_from_slice(&20u16.to_le_bytes());
+            directory.extend_from_slice(&entry.flags.to_le_bytes());
+            directory.extend_from_slice(&METHOD_DEFLATE.to_le_bytes());
+            directory.extend_from_slice(&0u16.to_le_bytes());
+            directory.extend_from_slice(&DOS_DATE.to_le_bytes());
+            directory.extend_from_slice(&entry.crc.to_le_bytes());
+            directory.extend_from_slice(&entry.compressed_size.to_le_bytes());
+            directory.extend_from_slice(&entry.size.to_le_bytes());
+            directory.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            // Extra field, comment, disk number, internal and external attributes
+            directory.extend_from_slice(&[0u8; 12]);
+            directory.extend_from_slice(&entry.offset.to_le_bytes());
+            directory.extend_from_slice(entry.name.as_bytes());
+        }
+
+        let entry_count = self.entries.len() as u16;
+        let mut end = Vec::with_capacity(22);
+        end.extend_from_slice(&0x06054b50u32.to_le_bytes());
+        end.extend_from_slice(&0u16.to_le_bytes());
+        end.extend_from_slice(&0u16.to_le_bytes());
+        end.extend_from_slice(&entry_count.to_le_bytes());
+        end.extend_from_slice(&entry_count.to_le_bytes());
+        end.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        end.extend_from_slice(&directory_offset.to_le_bytes());
+        end.extend_from_slice(&0u16.to_le_bytes());
+
+        self.emit(&directory);
+        self.emit(&end);
+        self.out
+    }
+}
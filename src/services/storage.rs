@@ -0,0 +1,228 @@
+use async_trait::async_trait;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::env;
+
+#[derive(Debug)]
+pub enum StorageError {
+    Io(std::io::Error),
+    #[cfg(feature = "s3")]
+    Backend(String),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::Io(e) => write!(f, "storage I/O error: {}", e),
+            #[cfg(feature = "s3")]
+            StorageError::Backend(msg) => write!(f, "storage backend error: {}", msg),
+        }
+    }
+}
+
+impl From<std::io::Error> for StorageError {
+    fn from(e: std::io::Error) -> Self {
+        StorageError::Io(e)
+    }
+}
+
+// Where uploaded files (product images, review photos) end up.
+// Keys are generated by the server and only contain [a-z0-9/._-].
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    // Store `bytes` under `key` and return the public URL of the object
+    async fn put(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<String, StorageError>;
+
+    // Remove the object; deleting a missing key is not an error
+    async fn delete(&self, key: &str) -> Result<(), StorageError>;
+}
+
+// Files on local disk, served by the app under `public_url`
+pub struct LocalBlobStore {
+    root: PathBuf,
+    public_url: String,
+}
+
+impl LocalBlobStore {
+    pub fn new(root: impl Into<PathBuf>, public_url: &str) -> Self {
+        Self {
+            root: root.into(),
+            public_url: public_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl BlobStore for LocalBlobStore {
+    async fn put(&self, key: &str, bytes: Vec<u8>, _content_type: &str) -> Result<String, StorageError> {
+        let path = self.root.join(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&path, bytes).await?;
+
+        Ok(format!("{}/{}", self.public_url, key))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StorageError> {
+        match tokio::fs::remove_file(self.root.join(key)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+// Best-effort cleanup of several objects, e.g. all variants of one image
+pub async fn delete_all(store: &dyn BlobStore, keys: &[String]) {
+    for key in keys {
+        if let Err(e) = store.delete(key).await {
+            eprintln!("Failed to delete {}: {}", key, e);
+        }
+    }
+}
+
+// Directory served at /uploads when the local backend is used
+pub fn local_upload_dir() -> Option<String> {
+    match env::var("STORAGE_BACKEND").as_deref() {
+        Ok("s3") => None,
+        _ => Some(env::var("UPLOAD_DIR").unwrap_or_else(|_| "./uploads".to_string())),
+    }
+}
+
+// Pick the storage backend from STORAGE_BACKEND (local by default)
+pub fn from_env() -> Arc<dyn BlobStore> {
+    if env::var("STORAGE_BACKEND").as_deref() == Ok("s3") {
+        #[cfg(feature = "s3")]
+        {
+            return Arc::new(s3::S3BlobStore::from_env());
+        }
+        #[cfg(not(feature = "s3"))]
+        panic!("STORAGE_BACKEND=s3 requires building with `--features s3`");
+    }
+
+    let root = local_upload_dir().unwrap_or_else(|| "./uploads".to_string());
+    let public_url = env::var("UPLOAD_PUBLIC_URL").unwrap_or_else(|_| "/uploads".to_string());
+    Arc::new(LocalBlobStore::new(root, &public_url))
+}
+
+#[cfg(feature = "s3")]
+pub mod s3 {
+    use super::{BlobStore, StorageError};
+    use async_trait::async_trait;
+    use chrono::Utc;
+    use hmac::{Hmac, Mac};
+    use sha2::{Digest, Sha256};
+    use std::env;
+
+    type HmacSha256 = Hmac<Sha256>;
+
+    // Any S3-compatible store (AWS, MinIO, R2) using path-style URLs and SigV4
+    pub struct S3BlobStore {
+        client: reqwest::Client,
+        endpoint: String,
+        bucket: String,
+        region: String,
+        access_key: String,
+        secret_key: String,
+        public_url: String,
+    }
+
+    impl S3BlobStore {
+        pub fn from_env() -> Self {
+            let endpoint = env::var("S3_ENDPOINT").expect("S3_ENDPOINT harus diset untuk STORAGE_BACKEND=s3");
+            let endpoint = endpoint.trim_end_matches('/').to_string();
+            let bucket = env::var("S3_BUCKET").expect("S3_BUCKET harus diset untuk STORAGE_BACKEND=s3");
+            let public_url = env::var("S3_PUBLIC_URL")
+                .unwrap_or_else(|_| format!("{}/{}", endpoint, bucket))
+                .trim_end_matches('/')
+                .to_string();
+
+            Self {
+                client: reqwest::Client::new(),
+                region: env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+                access_key: env::var("S3_ACCESS_KEY_ID").expect("S3_ACCESS_KEY_ID harus diset"),
+                secret_key: env::var("S3_SECRET_ACCESS_KEY").expect("S3_SECRET_ACCESS_KEY harus diset"),
+                endpoint,
+                bucket,
+                public_url,
+            }
+        }
+
+        fn hmac(key: &[u8], data: &str) -> Vec<u8> {
+            let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts any key length");
+            mac.update(data.as_bytes());
+            mac.finalize().into_bytes().to_vec()
+        }
+
+        async fn send(&self, method: reqwest::Method, key: &str, body: Vec<u8>, content_type: Option<&str>) -> Result<reqwest::Response, StorageError> {
+            let now = Utc::now();
+            let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+            let date = now.format("%Y%m%d").to_string();
+
+            let url = format!("{}/{}/{}", self.endpoint, self.bucket, key);
+            let parsed = reqwest::Url::parse(&url).map_err(|e| StorageError::Backend(e.to_string()))?;
+            let host = match parsed.port() {
+                Some(port) => format!("{}:{}", parsed.host_str().unwrap_or_default(), port),
+                None => parsed.host_str().unwrap_or_default().to_string(),
+            };
+
+            let payload_hash = hex::encode(Sha256::digest(&body));
+            let canonical_request = format!(
+                "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+                method.as_str(), parsed.path(), host, payload_hash, amz_date, payload_hash
+            );
+
+            let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+            let string_to_sign = format!(
+                "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+                amz_date, scope, hex::encode(Sha256::digest(canonical_request.as_bytes()))
+            );
+
+            let k_date = Self::hmac(format!("AWS4{}", self.secret_key).as_bytes(), &date);
+            let k_region = Self::hmac(&k_date, &self.region);
+            let k_service = Self::hmac(&k_region, "s3");
+            let k_signing = Self::hmac(&k_service, "aws4_request");
+            let signature = hex::encode(Self::hmac(&k_signing, &string_to_sign));
+
+            let authorization = format!(
+                "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+                self.access_key, scope, signature
+            );
+
+            let mut request = self.client
+                .request(method, parsed)
+                .header("x-amz-date", amz_date)
+                .header("x-amz-content-sha256", payload_hash)
+                .header("authorization", authorization)
+                .body(body);
+            if let Some(content_type) = content_type {
+                request = request.header("content-type", content_type);
+            }
+
+            request.send().await.map_err(|e| StorageError::Backend(e.to_string()))
+        }
+    }
+
+    #[async_trait]
+    impl BlobStore for S3BlobStore {
+        async fn put(&self, key: &str, bytes: Vec<u8>, content_type: &str) -> Result<String, StorageError> {
+            let response = self.send(reqwest::Method::PUT, key, bytes, Some(content_type)).await?;
+            if !response.status().is_success() {
+                return Err(StorageError::Backend(format!("PUT {} returned {}", key, response.status())));
+            }
+
+            Ok(format!("{}/{}", self.public_url, key))
+        }
+
+        async fn delete(&self, key: &str) -> Result<(), StorageError> {
+            let response = self.send(reqwest::Method::DELETE, key, Vec::new(), None).await?;
+            if !response.status().is_success() && response.status() != reqwest::StatusCode::NOT_FOUND {
+                return Err(StorageError::Backend(format!("DELETE {} returned {}", key, response.status())));
+            }
+
+            Ok(())
+        }
+    }
+}
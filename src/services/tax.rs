@@ -0,0 +1,83 @@
+use bigdecimal::{BigDecimal, Zero};
+use sqlx::PgConnection;
+use std::env;
+use std::str::FromStr;
+
+// How sales tax (PPN) is charged, configured through the environment:
+//   TAX_RATE                 percentage, 11 by default; 0 turns tax off
+//   TAX_PRICES_INCLUDE_TAX   true (default) when catalogue prices already
+//                            contain the tax, false to add it on top
+//   TAX_EXEMPT_CATEGORIES    comma separated category slugs; their
+//                            subcategories are exempt as well
+//   TAX_LABEL                name printed on orders and invoices, "PPN"
+#[derive(Debug, Clone)]
+pub struct TaxRules {
+    pub label: String,
+    pub rate: BigDecimal,
+    pub prices_include_tax: bool,
+    pub exempt_categories: Vec<String>,
+}
+
+impl TaxRules {
+    pub fn from_env() -> Self {
+        TaxRules {
+            label: env::var("TAX_LABEL").ok().filter(|label| !label.trim().is_empty()).unwrap_or_else(|| "PPN".to_string()),
+            rate: env::var("TAX_RATE")
+                .ok()
+                .and_then(|rate| BigDecimal::from_str(rate.trim()).ok())
+                .filter(|rate| *rate >= BigDecimal::zero())
+                .unwrap_or_else(|| BigDecimal::from(11)),
+            prices_include_tax: env::var("TAX_PRICES_INCLUDE_TAX")
+                .map(|value| !matches!(value.trim(), "false" | "0" | "no"))
+                .unwrap_or(true),
+            exempt_categories: env::var("TAX_EXEMPT_CATEGORIES")
+                .unwrap_or_default()
+                .split(',')
+                .map(|slug| slug.trim().to_lowercase())
+                .filter(|slug| !slug.is_empty())
+                .collect(),
+        }
+    }
+
+    // Tax on one order line. `amount` is what the customer pays for the line
+    // after discounts; inclusive prices have the tax taken out of it,
+    // exclusive prices get it added. Rounded to whole rupiah.
+    pub fn line_tax(&self, amount: &BigDecimal, exempt: bool) -> BigDecimal {
+        if exempt || self.rate.is_zero() || *amount <= BigDecimal::zero() {
+            return BigDecimal::zero();
+        }
+        let hundred = BigDecimal::from(100);
+        let tax = if self.prices_include_tax {
+            amount * &self.rate / (&hundred + &self.rate)
+        } else {
+            amount * &self.rate / hundred
+        };
+        tax.round(0).with_scale(2)
+    }
+}
+
+// The products among `product_ids` whose category, or one of its parent
+// categories, is tax exempt
+pub async fn exempt_products(
+    conn: &mut PgConnection,
+    rules: &TaxRules,
+    product_ids: &[i32],
+) -> Result<Vec<i32>, sqlx::Error> {
+    if rules.exempt_categories.is_empty() || product_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    sqlx::query_scalar::<_, i32>(
+        "WITH RECURSIVE exempt AS (
+             SELECT id FROM categories WHERE slug = ANY($2)
+             UNION
+             SELECT c.id FROM categories c JOIN exempt e ON c.parent_id = e.id
+         )
+         SELECT p.id FROM products p
+         WHERE p.id = ANY($1) AND p.category_id IN (SELECT id FROM exempt)"
+    )
+    .bind(product_ids)
+    .bind(&rules.exempt_categories)
+    .fetch_all(conn)
+    .await
+}
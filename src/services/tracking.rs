@@ -0,0 +1,273 @@
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::PgPool;
+use std::env;
+use std::fmt;
+use std::sync::Arc;
+
+// Checkpoint history is fetched from the courier at most this often per
+// shipment; delivered shipments are never fetched again
+pub const CACHE_MINUTES: i32 = 30;
+
+#[derive(Debug)]
+pub enum TrackingError {
+    // The courier doesn't know the waybill (yet)
+    #[cfg_attr(not(feature = "rajaongkir"), allow(dead_code))]
+    NotFound(String),
+    // No tracker is configured
+    Unavailable,
+    #[cfg(feature = "rajaongkir")]
+    Backend(String),
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for TrackingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrackingError::NotFound(msg) => write!(f, "{}", msg),
+            TrackingError::Unavailable => write!(f, "shipment tracking is not configured"),
+            #[cfg(feature = "rajaongkir")]
+            TrackingError::Backend(msg) => write!(f, "tracking backend error: {}", msg),
+            TrackingError::Database(e) => write!(f, "database error: {}", e),
+        }
+    }
+}
+
+impl From<sqlx::Error> for TrackingError {
+    fn from(e: sqlx::Error) -> Self {
+        TrackingError::Database(e)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackingCheckpoint {
+    pub time: Option<NaiveDateTime>,
+    pub location: Option<String>,
+    pub description: String,
+}
+
+// What the courier reports for one waybill, newest checkpoint first
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackingInfo {
+    pub status: String,
+    pub delivered: bool,
+    pub checkpoints: Vec<TrackingCheckpoint>,
+}
+
+#[async_trait]
+pub trait ShipmentTracker: Send + Sync {
+    async fn track(&self, courier: &str, waybill: &str) -> Result<TrackingInfo, TrackingError>;
+}
+
+// Used when no tracking API is configured: buyers still see the waybill
+// and can look it up on the courier's site
+pub struct NoTracker;
+
+#[async_trait]
+impl ShipmentTracker for NoTracker {
+    async fn track(&self, _courier: &str, _waybill: &str) -> Result<TrackingInfo, TrackingError> {
+        Err(TrackingError::Unavailable)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CachedTracking {
+    #[serde(flatten)]
+    pub info: TrackingInfo,
+    pub tracked_at: NaiveDateTime,
+    // The courier couldn't be reached and this is the last known history
+    pub stale: bool,
+}
+
+// Tracking for one shipment, from the cache while it is fresh. A successful
+// fetch replaces the cache; a failed one falls back to the cached history.
+pub async fn shipment_tracking(
+    pool: &PgPool,
+    tracker: &dyn ShipmentTracker,
+    shipment_id: i32,
+) -> Result<CachedTracking, TrackingError> {
+    let (courier, waybill, cached, tracked_at, fresh): (String, String, Option<Json<TrackingInfo>>, Option<NaiveDateTime>, bool) =
+        sqlx::query_as(
+            "SELECT courier, waybill, tracking, tracked_at,
+                    COALESCE(tracked_at > NOW() - make_interval(mins => $2), false)
+             FROM shipments WHERE id = $1"
+        )
+        .bind(shipment_id)
+        .bind(CACHE_MINUTES)
+        .fetch_one(pool)
+        .await?;
+
+    let cached = cached.zip(tracked_at).map(|(Json(info), tracked_at)| CachedTracking {
+        info,
+        tracked_at,
+        stale: false,
+    });
+    match cached {
+        Some(cached) if fresh || cached.info.delivered => return Ok(cached),
+        _ => {}
+    }
+
+    match tracker.track(&courier, &waybill).await {
+        Ok(info) => {
+            let (tracked_at,): (NaiveDateTime,) = sqlx::query_as(
+                "UPDATE shipments
+                 SET tracking = $2, tracked_at = NOW(),
+                     delivered_at = CASE WHEN $3 THEN COALESCE(delivered_at, NOW()) ELSE delivered_at END
+                 WHERE id = $1
+                 RETURNING tracked_at"
+            )
+            .bind(shipment_id)
+            .bind(Json(&info))
+            .bind(info.delivered)
+            .fetch_one(pool)
+            .await?;
+
+            Ok(CachedTracking { info, tracked_at, stale: false })
+        }
+        Err(e) => match cached {
+            Some(cached) => {
+                eprintln!("Tracking error for shipment {}: {}", shipment_id, e);
+                Ok(CachedTracking { stale: true, ..cached })
+            }
+            None => Err(e),
+        },
+    }
+}
+
+// Pick the tracker from SHIPMENT_TRACKER (none by default)
+pub fn from_env() -> Arc<dyn ShipmentTracker> {
+    if env::var("SHIPMENT_TRACKER").as_deref() == Ok("rajaongkir") {
+        #[cfg(feature = "rajaongkir")]
+        {
+            return Arc::new(rajaongkir::RajaOngkirTracker::from_env());
+        }
+        #[cfg(not(feature = "rajaongkir"))]
+        panic!("SHIPMENT_TRACKER=rajaongkir requires building with `--features rajaongkir`");
+    }
+
+    Arc::new(NoTracker)
+}
+
+#[cfg(feature = "rajaongkir")]
+pub mod rajaongkir {
+    use super::{ShipmentTracker, TrackingCheckpoint, TrackingError, TrackingInfo};
+    use async_trait::async_trait;
+    use chrono::NaiveDateTime;
+    use serde::Deserialize;
+    use std::env;
+
+    #[derive(Deserialize)]
+    struct Envelope {
+        rajaongkir: Body,
+    }
+
+    #[derive(Deserialize)]
+    struct Body {
+        status: Status,
+        result: Option<Waybill>,
+    }
+
+    #[derive(Deserialize)]
+    struct Status {
+        code: u16,
+        description: String,
+    }
+
+    #[derive(Deserialize)]
+    struct Waybill {
+        delivered: bool,
+        summary: Summary,
+        #[serde(default)]
+        manifest: Vec<Manifest>,
+    }
+
+    #[derive(Deserialize)]
+    struct Summary {
+        status: String,
+    }
+
+    #[derive(Deserialize)]
+    struct Manifest {
+        manifest_description: String,
+        manifest_date: String,
+        manifest_time: String,
+        city_name: Option<String>,
+    }
+
+    fn checkpoint_time(date: &str, time: &str) -> Option<NaiveDateTime> {
+        let stamp = format!("{} {}", date.trim(), time.trim());
+        NaiveDateTime::parse_from_str(&stamp, "%Y-%m-%d %H:%M:%S")
+            .or_else(|_| NaiveDateTime::parse_from_str(&stamp, "%Y-%m-%d %H:%M"))
+            .ok()
+    }
+
+    // RajaOngkir-compatible POST /waybill (Pro accounts), same key and base
+    // URL as the rate adapter
+    pub struct RajaOngkirTracker {
+        client: reqwest::Client,
+        base_url: String,
+        api_key: String,
+    }
+
+    impl RajaOngkirTracker {
+        pub fn new(base_url: &str, api_key: &str) -> Self {
+            Self {
+                client: reqwest::Client::new(),
+                base_url: base_url.trim_end_matches('/').to_string(),
+                api_key: api_key.to_string(),
+            }
+        }
+
+        pub fn from_env() -> Self {
+            Self::new(
+                &env::var("RAJAONGKIR_BASE_URL").unwrap_or_else(|_| "https://pro.rajaongkir.com/api".to_string()),
+                &env::var("RAJAONGKIR_API_KEY").expect("RAJAONGKIR_API_KEY harus diset untuk SHIPMENT_TRACKER=rajaongkir"),
+            )
+        }
+    }
+
+    #[async_trait]
+    impl ShipmentTracker for RajaOngkirTracker {
+        async fn track(&self, courier: &str, waybill: &str) -> Result<TrackingInfo, TrackingError> {
+            let envelope: Envelope = self
+                .client
+                .post(format!("{}/waybill", self.base_url))
+                .header("key", &self.api_key)
+                .form(&[("waybill", waybill), ("courier", courier)])
+                .send()
+                .await
+                .map_err(|e| TrackingError::Backend(e.to_string()))?
+                .json()
+                .await
+                .map_err(|e| TrackingError::Backend(e.to_string()))?;
+
+            let status = envelope.rajaongkir.status;
+            let result = match (status.code, envelope.rajaongkir.result) {
+                (200, Some(result)) => result,
+                // Unknown or not yet scanned waybill
+                (400, _) | (200, None) => return Err(TrackingError::NotFound(status.description)),
+                _ => return Err(TrackingError::Backend(status.description)),
+            };
+
+            // The manifest lists the oldest scan first
+            let checkpoints = result
+                .manifest
+                .into_iter()
+                .rev()
+                .map(|m| TrackingCheckpoint {
+                    time: checkpoint_time(&m.manifest_date, &m.manifest_time),
+                    location: m.city_name.filter(|city| !city.trim().is_empty()),
+                    description: m.manifest_description,
+                })
+                .collect();
+
+            Ok(TrackingInfo {
+                status: result.summary.status,
+                delivered: result.delivered,
+                checkpoints,
+            })
+        }
+    }
+}
@@ -1,7 +1,12 @@
 use jsonwebtoken::{encode, decode, Header, EncodingKey, DecodingKey, Validation, Algorithm};
 use chrono::{Utc, Duration};
 use crate::models::user::Claims;
+use crate::models::cart::CartTokenClaims;
 use std::env;
+use uuid::Uuid;
+
+// Guest carts live longer than a login session
+pub const CART_TOKEN_TTL_DAYS: i64 = 30;
 
 pub fn create_jwt(user_id: i32, role: String) -> Result<String, jsonwebtoken::errors::Error> {
     let secret = env::var("JWT_SECRET").unwrap_or_else(|_| "default-secret".to_string());
@@ -46,4 +51,30 @@ pub fn verify_token(token: &str) -> Result<Claims, jsonwebtoken::errors::Error>
     
     let token_data = decode::<Claims>(token, &key, &validation)?;
     Ok(token_data.claims)
-}
\ No newline at end of file
+}
+
+pub fn create_cart_token(cart: Uuid) -> Result<String, jsonwebtoken::errors::Error> {
+    let secret = env::var("JWT_SECRET").unwrap_or_else(|_| "default-secret".to_string());
+    let key = EncodingKey::from_secret(secret.as_bytes());
+
+    let expiration = Utc::now()
+        .checked_add_signed(Duration::days(CART_TOKEN_TTL_DAYS))
+        .expect("valid timestamp")
+        .timestamp();
+
+    let claims = CartTokenClaims {
+        cart,
+        exp: expiration as usize,
+    };
+
+    encode(&Header::default(), &claims, &key)
+}
+
+pub fn validate_cart_token(token: &str) -> Result<Uuid, jsonwebtoken::errors::Error> {
+    let secret = env::var("JWT_SECRET").unwrap_or_else(|_| "default-secret".to_string());
+    let key = DecodingKey::from_secret(secret.as_bytes());
+    let validation = Validation::new(Algorithm::HS256);
+
+    decode::<CartTokenClaims>(token, &key, &validation)
+        .map(|data| data.claims.cart)
+}
@@ -2,3 +2,5 @@ pub mod jwt;
 pub mod error;
 pub mod error_helpers;
 pub mod response;
+pub mod multipart;
+pub mod slug;
@@ -0,0 +1,52 @@
+use actix_multipart::Multipart;
+use futures_util::StreamExt;
+use std::collections::HashMap;
+use crate::services::images::{max_upload_bytes, ImageError};
+
+pub struct UploadedFile {
+    pub file_name: Option<String>,
+    pub bytes: Vec<u8>,
+}
+
+// Read a multipart body into its files (all parts named `file_field`) and
+// text fields. The size limit is enforced while streaming, so oversized
+// uploads are rejected before they are fully buffered.
+pub async fn read_multipart(
+    mut payload: Multipart,
+    file_field: &str,
+    max_files: usize,
+) -> Result<(Vec<UploadedFile>, HashMap<String, String>), String> {
+    let limit = max_upload_bytes();
+    let mut files = Vec::new();
+    let mut fields = HashMap::new();
+
+    while let Some(field) = payload.next().await {
+        let mut field = field.map_err(|e| format!("Invalid multipart body: {}", e))?;
+        let name = field.name().unwrap_or_default().to_string();
+        let file_name = field
+            .content_disposition()
+            .and_then(|cd| cd.get_filename())
+            .map(|f| f.to_string());
+
+        if name == file_field && files.len() >= max_files {
+            return Err(format!("At most {} files can be uploaded at once", max_files));
+        }
+
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.next().await {
+            let chunk = chunk.map_err(|e| format!("Invalid multipart body: {}", e))?;
+            if bytes.len() + chunk.len() > limit {
+                return Err(ImageError::TooLarge(limit).to_string());
+            }
+            bytes.extend_from_slice(&chunk);
+        }
+
+        if name == file_field {
+            files.push(UploadedFile { file_name, bytes });
+        } else {
+            fields.insert(name, String::from_utf8_lossy(&bytes).trim().to_string());
+        }
+    }
+
+    Ok((files, fields))
+}
@@ -0,0 +1,46 @@
+// Fixtures shared by the database tests. Each test binary uses only some of
+// them, hence the allow.
+#![allow(dead_code)]
+
+use sqlx::postgres::PgPoolOptions;
+use sqlx::PgPool;
+
+pub async fn connect() -> PgPool {
+    let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+    PgPoolOptions::new()
+        .max_connections(10)
+        .connect(&database_url)
+        .await
+        .expect("Failed to connect to database")
+}
+
+// A throwaway buyer named after the test's tag
+pub async fn create_user(pool: &PgPool, tag: &str) -> i32 {
+    let (id,): (i32,) = sqlx::query_as(
+        "INSERT INTO users (username, email, password_hash, first_name, last_name, name, password)
+         VALUES ($1, $2, 'x', 'Test', 'Buyer', 'Test Buyer', 'x')
+         RETURNING id"
+    )
+    .bind(tag)
+    .bind(format!("{}@example.com", tag))
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create user");
+    id
+}
+
+// A 100k product whose SKU is the test's tag
+pub async fn create_product(pool: &PgPool, tag: &str, stock: i32, sold_count: i32) -> i32 {
+    let (id,): (i32,) = sqlx::query_as(
+        "INSERT INTO products (name, price, sku, stock_quantity, category, stock, sold_count)
+         VALUES ('Batik Kawung Cap', 100000, $1, $2, 'Batik Cap', $2, $3)
+         RETURNING id"
+    )
+    .bind(tag)
+    .bind(stock)
+    .bind(sold_count)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create product");
+    id
+}
@@ -0,0 +1,70 @@
+// Coupon discounts: which lines they cover, caps and the per-line split
+#[allow(dead_code)]
+#[path = "../src/services/coupon_rules.rs"]
+mod coupon_rules;
+
+use bigdecimal::BigDecimal;
+use coupon_rules::{CouponLine, CouponScope};
+use std::str::FromStr;
+
+fn rp(amount: &str) -> BigDecimal {
+    BigDecimal::from_str(amount).unwrap()
+}
+
+fn line(product_id: i32, category_id: Option<i32>, line_total: &str) -> CouponLine {
+    CouponLine { product_id, category_id, line_total: rp(line_total) }
+}
+
+const EVERYTHING: CouponScope = CouponScope { product_ids: &[], category_ids: &[] };
+
+#[test]
+fn scoped_coupons_only_count_their_lines() {
+    let lines = [line(1, Some(10), "100000"), line(2, Some(20), "50000"), line(3, None, "25000")];
+
+    assert_eq!(EVERYTHING.eligible_total(&lines), rp("175000"));
+    let by_category = CouponScope { product_ids: &[], category_ids: &[20] };
+    assert_eq!(by_category.eligible_total(&lines), rp("50000"));
+    let by_product_or_category = CouponScope { product_ids: &[3], category_ids: &[10] };
+    assert_eq!(by_product_or_category.eligible_total(&lines), rp("125000"));
+    let elsewhere = CouponScope { product_ids: &[9], category_ids: &[99] };
+    assert_eq!(elsewhere.eligible_total(&lines), rp("0"));
+}
+
+#[test]
+fn discounts_never_exceed_the_eligible_total_or_the_cap() {
+    // 15% of 123456 = 18518.40
+    assert_eq!(coupon_rules::discount_amount(true, &rp("15"), None, &rp("123456")), rp("18518.40"));
+    assert_eq!(coupon_rules::discount_amount(true, &rp("50"), Some(&rp("25000")), &rp("100000")), rp("25000"));
+    assert_eq!(coupon_rules::discount_amount(false, &rp("30000"), None, &rp("100000")), rp("30000"));
+    // A fixed coupon bigger than what it applies to takes off only that
+    assert_eq!(coupon_rules::discount_amount(false, &rp("150000"), None, &rp("100000")), rp("100000"));
+    assert_eq!(coupon_rules::discount_amount(false, &rp("80000"), Some(&rp("50000")), &rp("100000")), rp("50000"));
+}
+
+#[test]
+fn the_split_follows_line_totals_and_skips_other_lines() {
+    let lines = [line(1, Some(10), "300000"), line(2, Some(20), "40000"), line(3, Some(10), "100000")];
+    let scope = CouponScope { product_ids: &[], category_ids: &[10] };
+    let eligible = scope.eligible_total(&lines);
+
+    let split = coupon_rules::split_discount(&scope, &lines, &rp("40000"), &eligible);
+    assert_eq!(split, vec![rp("30000"), rp("0"), rp("10000")]);
+}
+
+#[test]
+fn the_last_eligible_line_takes_the_rounding_difference() {
+    let lines = [line(1, None, "10000"), line(2, None, "10000"), line(3, None, "10000"), line(4, Some(5), "10000")];
+    let scope = CouponScope { product_ids: &[1, 2, 3], category_ids: &[] };
+    let eligible = scope.eligible_total(&lines);
+
+    // 100 over three equal lines doesn't divide evenly
+    let split = coupon_rules::split_discount(&scope, &lines, &rp("100"), &eligible);
+    assert_eq!(split, vec![rp("33.33"), rp("33.33"), rp("33.34"), rp("0")]);
+    assert_eq!(split.iter().sum::<BigDecimal>(), rp("100"));
+}
+
+#[test]
+fn nothing_to_split_without_eligible_lines() {
+    let lines = [line(1, None, "0")];
+    assert_eq!(coupon_rules::split_discount(&EVERYTHING, &lines, &rp("0"), &rp("0")), vec![rp("0")]);
+}
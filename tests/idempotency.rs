@@ -0,0 +1,106 @@
+// Idempotency-Key storage. Needs a real, migrated database:
+// DATABASE_URL=... cargo test -- --ignored
+#[allow(dead_code)]
+#[path = "../src/services/idempotency.rs"]
+mod idempotency;
+mod common;
+
+use common::{connect, create_user};
+use idempotency::Claim;
+
+#[test]
+fn hash_covers_method_path_and_body() {
+    let checkout = idempotency::request_hash("POST", "/api/auth/checkout", "", br#"{"items":[]}"#);
+
+    assert_eq!(checkout, idempotency::request_hash("POST", "/api/auth/checkout", "", br#"{"items":[]}"#));
+    assert_ne!(checkout, idempotency::request_hash("POST", "/api/auth/checkout", "", br#"{"items":[1]}"#));
+    assert_ne!(checkout, idempotency::request_hash("PUT", "/api/auth/checkout", "", br#"{"items":[]}"#));
+    // Parts are length-prefixed, so moving bytes between them changes the hash
+    assert_ne!(
+        idempotency::request_hash("POST", "/a", "b", b""),
+        idempotency::request_hash("POST", "/ab", "", b""),
+    );
+}
+
+#[tokio::test]
+#[ignore = "requires DATABASE_URL pointing at a migrated database"]
+async fn a_key_replays_its_first_response() {
+    let pool = connect().await;
+    let tag = format!("idem-{}", uuid::Uuid::new_v4().simple());
+    let user_id = create_user(&pool, &tag).await;
+    let hash = idempotency::request_hash("POST", "/api/auth/checkout", "", b"{}");
+    let other_hash = idempotency::request_hash("POST", "/api/auth/checkout", "", b"{\"x\":1}");
+
+    let Claim::New(first) = idempotency::claim(&pool, user_id, "key-1", &hash).await.unwrap() else {
+        panic!("expected a new claim");
+    };
+    // A retry while the first request is running
+    assert!(matches!(idempotency::claim(&pool, user_id, "key-1", &hash).await.unwrap(), Claim::InProgress));
+
+    let headers = vec![("content-type".to_string(), "application/json".to_string())];
+    idempotency::complete(&pool, first, 201, &headers, b"{\"id\":1}").await.unwrap();
+
+    match idempotency::claim(&pool, user_id, "key-1", &hash).await.unwrap() {
+        Claim::Completed { status, headers: replayed, body } => {
+            assert_eq!(status, 201);
+            assert_eq!(replayed, headers);
+            assert_eq!(body, b"{\"id\":1}");
+        }
+        _ => panic!("expected the stored response"),
+    }
+    assert!(matches!(idempotency::claim(&pool, user_id, "key-1", &other_hash).await.unwrap(), Claim::Mismatch));
+
+    // A released key can be used again, and keys expire after a day
+    let Claim::New(second) = idempotency::claim(&pool, user_id, "key-2", &hash).await.unwrap() else {
+        panic!("expected a new claim");
+    };
+    idempotency::release(&pool, second).await.unwrap();
+    assert!(matches!(idempotency::claim(&pool, user_id, "key-2", &other_hash).await.unwrap(), Claim::New(_)));
+
+    sqlx::query("UPDATE idempotency_keys SET created_at = NOW() - INTERVAL '25 hours' WHERE user_id = $1 AND idempotency_key = 'key-1'")
+        .bind(user_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    assert!(matches!(idempotency::claim(&pool, user_id, "key-1", &other_hash).await.unwrap(), Claim::New(_)));
+
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(user_id).execute(&pool).await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires DATABASE_URL pointing at a migrated database"]
+async fn an_abandoned_request_cannot_touch_the_retry_claim() {
+    let pool = connect().await;
+    let tag = format!("idem-{}", uuid::Uuid::new_v4().simple());
+    let user_id = create_user(&pool, &tag).await;
+    let hash = idempotency::request_hash("POST", "/api/auth/checkout", "", b"{}");
+
+    let Claim::New(stale) = idempotency::claim(&pool, user_id, "key-1", &hash).await.unwrap() else {
+        panic!("expected a new claim");
+    };
+    // The first request runs past ABANDONED_MINUTES and a retry takes the key over
+    sqlx::query("UPDATE idempotency_keys SET created_at = NOW() - INTERVAL '10 minutes' WHERE id = $1")
+        .bind(stale)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let Claim::New(retry) = idempotency::claim(&pool, user_id, "key-1", &hash).await.unwrap() else {
+        panic!("expected the retry to take over the key");
+    };
+
+    // The slow first request finally finishes, or fails
+    idempotency::complete(&pool, stale, 500, &[], b"late").await.unwrap();
+    idempotency::release(&pool, stale).await.unwrap();
+    assert!(matches!(idempotency::claim(&pool, user_id, "key-1", &hash).await.unwrap(), Claim::InProgress));
+
+    idempotency::complete(&pool, retry, 201, &[], b"{\"id\":1}").await.unwrap();
+    match idempotency::claim(&pool, user_id, "key-1", &hash).await.unwrap() {
+        Claim::Completed { status, body, .. } => {
+            assert_eq!(status, 201);
+            assert_eq!(body, b"{\"id\":1}");
+        }
+        _ => panic!("expected the retry's response"),
+    }
+
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(user_id).execute(&pool).await.unwrap();
+}
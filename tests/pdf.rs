@@ -0,0 +1,42 @@
+// The PDF writer used for invoices
+#[allow(dead_code)]
+#[path = "../src/services/pdf.rs"]
+mod pdf;
+
+use pdf::{fit, text_width, Align, Font, PdfDocument};
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle)
+}
+
+#[test]
+fn long_text_is_shortened_to_fit() {
+    let name = "Kain Batik Tulis Lasem Motif Sekar Jagad Premium Edisi Terbatas";
+    let fitted = fit(name, 150.0, 9.0, Font::Regular);
+
+    assert!(fitted.ends_with("..."));
+    assert!(text_width(&fitted, 9.0, Font::Regular) <= 150.0);
+    assert_eq!(fit("Batik Parang", 150.0, 9.0, Font::Regular), "Batik Parang");
+}
+
+#[test]
+fn bold_text_is_wider() {
+    assert!(text_width("Batik", 10.0, Font::Bold) > text_width("Batik", 10.0, Font::Regular));
+    // All digits share one width, so right aligned amounts line up
+    assert_eq!(text_width("111", 10.0, Font::Regular), text_width("999", 10.0, Font::Regular));
+}
+
+#[test]
+fn document_lists_every_page() {
+    let mut doc = PdfDocument::new();
+    doc.text(50.0, 780.0, 12.0, Font::Bold, Align::Left, "INVOICE");
+    doc.new_page();
+    doc.text(545.0, 780.0, 9.0, Font::Regular, Align::Right, "Rp 1.250.000 \u{2013} lunas");
+    let bytes = doc.finish("Invoice INV-2026-000001");
+
+    assert!(bytes.starts_with(b"%PDF-"));
+    assert!(contains(&bytes, b"/Count 2"));
+    assert!(contains(&bytes, b"/BaseFont /Helvetica-Bold"));
+    // Text the WinAnsi encoding can't show is replaced instead of breaking the file
+    assert!(contains(&bytes, b"(Rp 1.250.000 ? lunas)"));
+}
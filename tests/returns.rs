@@ -0,0 +1,299 @@
+// Quantities and money in the return (RMA) workflow. The database tests
+// need a real, migrated database: DATABASE_URL=... cargo test -- --ignored
+#[allow(dead_code)]
+#[path = "../src/services/return_rules.rs"]
+mod return_rules;
+mod common;
+
+use bigdecimal::BigDecimal;
+use chrono::{Duration, Utc};
+use common::{connect, create_product, create_user};
+use return_rules::{RefundError, ReturnLineError};
+use sqlx::PgPool;
+
+// A delivered order of `lines` (product, quantity, unit price), paid in full
+async fn create_delivered_order(pool: &PgPool, user_id: i32, tag: &str, lines: &[(i32, i32, i64)]) -> (i32, Vec<i32>) {
+    let total: i64 = lines.iter().map(|(_, quantity, price)| *quantity as i64 * price).sum();
+    let (order_id,): (i32,) = sqlx::query_as(
+        "INSERT INTO orders (user_id, order_number, status, total_amount, final_amount, payment_method,
+                             payment_status, shipping_address, billing_address, delivered_at)
+         VALUES ($1, $2, 'delivered', $3, $3, 'transfer_bank', 'paid', '{}', '{}', NOW())
+         RETURNING id"
+    )
+    .bind(user_id)
+    .bind(tag)
+    .bind(BigDecimal::from(total))
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create order");
+
+    let mut item_ids = Vec::new();
+    for (product_id, quantity, price) in lines {
+        let (item_id,): (i32,) = sqlx::query_as(
+            "INSERT INTO order_items (order_id, product_id, quantity, unit_price, total_price, price_at_time)
+             VALUES ($1, $2, $3, $4, $4 * $3, $4)
+             RETURNING id"
+        )
+        .bind(order_id)
+        .bind(product_id)
+        .bind(quantity)
+        .bind(BigDecimal::from(*price))
+        .fetch_one(pool)
+        .await
+        .expect("Failed to create order item");
+        item_ids.push(item_id);
+    }
+    (order_id, item_ids)
+}
+
+async fn create_return(pool: &PgPool, order_id: i32, user_id: i32, status: &str, items: &[(i32, i32)]) -> i32 {
+    let (return_id,): (i32,) = sqlx::query_as(
+        "INSERT INTO return_requests (order_id, user_id, status, reason) VALUES ($1, $2, $3, 'defect') RETURNING id"
+    )
+    .bind(order_id)
+    .bind(user_id)
+    .bind(status)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create return");
+
+    for (order_item_id, quantity) in items {
+        sqlx::query("INSERT INTO return_items (return_id, order_item_id, quantity) VALUES ($1, $2, $3)")
+            .bind(return_id)
+            .bind(order_item_id)
+            .bind(quantity)
+            .execute(pool)
+            .await
+            .expect("Failed to create return item");
+    }
+    return_id
+}
+
+async fn refund(
+    pool: &PgPool,
+    return_id: i32,
+    amount: Option<i64>,
+    admin_id: i32,
+) -> Result<return_rules::ReturnRefund, RefundError> {
+    let mut tx = pool.begin().await.unwrap();
+    let amount = amount.map(BigDecimal::from);
+    let result = return_rules::refund_return(&mut tx, return_id, amount.as_ref(), admin_id).await.unwrap();
+    if result.is_ok() {
+        tx.commit().await.unwrap();
+    }
+    result
+}
+
+async fn cleanup(pool: &PgPool, user_id: i32, product_ids: &[i32]) {
+    sqlx::query("DELETE FROM orders WHERE user_id = $1").bind(user_id).execute(pool).await.unwrap();
+    sqlx::query("DELETE FROM products WHERE id = ANY($1)").bind(product_ids).execute(pool).await.unwrap();
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(user_id).execute(pool).await.unwrap();
+}
+
+#[test]
+fn returns_close_after_the_window() {
+    let now = Utc::now().naive_utc();
+    assert!(return_rules::within_return_window(Some(now - Duration::days(6)), now, 7));
+    assert!(return_rules::within_return_window(Some(now - Duration::days(7)), now, 7));
+    assert!(!return_rules::within_return_window(Some(now - Duration::days(8)), now, 7));
+    // Never delivered
+    assert!(!return_rules::within_return_window(None, now, 7));
+}
+
+#[tokio::test]
+#[ignore = "requires DATABASE_URL pointing at a migrated database"]
+async fn units_cannot_be_returned_twice() {
+    let pool = connect().await;
+    let tag = format!("rma-qty-{}", uuid::Uuid::new_v4().simple());
+    let user_id = create_user(&pool, &tag).await;
+    let product_id = create_product(&pool, &tag, 0, 3).await;
+    let (order_id, items) = create_delivered_order(&pool, user_id, &tag, &[(product_id, 3, 100000)]).await;
+
+    create_return(&pool, order_id, user_id, "pending", &[(items[0], 2)]).await;
+    // A rejected return gives its units back
+    create_return(&pool, order_id, user_id, "rejected", &[(items[0], 1)]).await;
+
+    let mut conn = pool.acquire().await.unwrap();
+    assert_eq!(return_rules::check_return_lines(&mut conn, order_id, &[(items[0], 1)]).await.unwrap(), Ok(()));
+    assert_eq!(
+        return_rules::check_return_lines(&mut conn, order_id, &[(items[0], 2)]).await.unwrap(),
+        Err(ReturnLineError::TooMany { order_item_id: items[0], available: 1 })
+    );
+    assert_eq!(
+        return_rules::check_return_lines(&mut conn, order_id, &[(-1, 1)]).await.unwrap(),
+        Err(ReturnLineError::NotInOrder(-1))
+    );
+    drop(conn);
+
+    cleanup(&pool, user_id, &[product_id]).await;
+}
+
+#[tokio::test]
+#[ignore = "requires DATABASE_URL pointing at a migrated database"]
+async fn received_units_go_back_on_sale() {
+    let pool = connect().await;
+    let tag = format!("rma-stock-{}", uuid::Uuid::new_v4().simple());
+    let user_id = create_user(&pool, &tag).await;
+    let product_id = create_product(&pool, &tag, 4, 3).await;
+    let (order_id, items) = create_delivered_order(&pool, user_id, &tag, &[(product_id, 3, 100000)]).await;
+    let return_id = create_return(&pool, order_id, user_id, "received", &[(items[0], 2)]).await;
+
+    let mut conn = pool.acquire().await.unwrap();
+    return_rules::restock_return(&mut conn, return_id).await.unwrap();
+    drop(conn);
+
+    let (stock, sold_count): (i32, i32) = sqlx::query_as("SELECT stock, sold_count FROM products WHERE id = $1")
+        .bind(product_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!((stock, sold_count), (6, 1));
+
+    cleanup(&pool, user_id, &[product_id]).await;
+}
+
+#[tokio::test]
+#[ignore = "requires DATABASE_URL pointing at a migrated database"]
+async fn refunds_are_capped_by_what_was_paid() {
+    let pool = connect().await;
+    let tag = format!("rma-cap-{}", uuid::Uuid::new_v4().simple());
+    let user_id = create_user(&pool, &tag).await;
+    let kawung = create_product(&pool, &format!("{}-a", tag), 0, 1).await;
+    let parang = create_product(&pool, &format!("{}-b", tag), 0, 1).await;
+    let (order_id, items) =
+        create_delivered_order(&pool, user_id, &tag, &[(kawung, 1, 100000), (parang, 1, 200000)]).await;
+
+    // Only received returns are refunded
+    let pending = create_return(&pool, order_id, user_id, "pending", &[(items[0], 1)]).await;
+    assert_eq!(refund(&pool, pending, None, user_id).await.unwrap_err(), RefundError::NotReceived("pending".to_string()));
+    sqlx::query("DELETE FROM return_requests WHERE id = $1").bind(pending).execute(&pool).await.unwrap();
+
+    // A partial refund of the first item
+    let first = create_return(&pool, order_id, user_id, "received", &[(items[0], 1)]).await;
+    assert_eq!(
+        refund(&pool, first, Some(300001), user_id).await.unwrap_err(),
+        RefundError::AmountTooHigh(BigDecimal::from(300000))
+    );
+    let partial = refund(&pool, first, Some(50000), user_id).await.unwrap();
+    assert_eq!(partial.amount, BigDecimal::from(50000));
+    assert!(!partial.order_fully_refunded);
+
+    // Some of the payment already went back outside the return flow, so
+    // the second item's value no longer fits in what is left
+    sqlx::query("INSERT INTO refunds (order_id, amount, reason) VALUES ($1, 100000, 'Goodwill')")
+        .bind(order_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let second = create_return(&pool, order_id, user_id, "received", &[(items[1], 1)]).await;
+    let rest = refund(&pool, second, None, user_id).await.unwrap();
+    assert_eq!(rest.amount, BigDecimal::from(150000));
+    // Every unit is back, so the delivered order becomes Refunded
+    assert!(rest.order_fully_refunded);
+
+    cleanup(&pool, user_id, &[kawung, parang]).await;
+}
+
+#[tokio::test]
+#[ignore = "requires DATABASE_URL pointing at a migrated database"]
+async fn refunds_follow_what_was_paid_for_each_unit() {
+    let pool = connect().await;
+    let tag = format!("rma-paid-{}", uuid::Uuid::new_v4().simple());
+    let user_id = create_user(&pool, &tag).await;
+    let product_id = create_product(&pool, &tag, 0, 2).await;
+
+    // Two units at 100k with a 50% coupon: 100k was paid
+    let (order_id, items) = create_delivered_order(&pool, user_id, &tag, &[(product_id, 2, 100000)]).await;
+    sqlx::query("UPDATE order_items SET discount_amount = 100000 WHERE id = $1")
+        .bind(items[0])
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE orders SET discount_amount = 100000, final_amount = 100000 WHERE id = $1")
+        .bind(order_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let first = create_return(&pool, order_id, user_id, "received", &[(items[0], 1)]).await;
+    let first = refund(&pool, first, None, user_id).await.unwrap();
+    assert_eq!(first.amount, BigDecimal::from(50000));
+    assert!(!first.order_fully_refunded);
+    let second = create_return(&pool, order_id, user_id, "received", &[(items[0], 1)]).await;
+    let second = refund(&pool, second, None, user_id).await.unwrap();
+    assert_eq!(second.amount, BigDecimal::from(50000));
+    assert!(second.order_fully_refunded);
+
+    // Tax added on top of the price was paid too
+    let (taxed_order, taxed_items) =
+        create_delivered_order(&pool, user_id, &format!("{}-tax", tag), &[(product_id, 3, 100000)]).await;
+    sqlx::query("UPDATE order_items SET tax_amount = 33000 WHERE id = $1")
+        .bind(taxed_items[0])
+        .execute(&pool)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE orders SET tax_amount = 33000, final_amount = 333000, prices_include_tax = FALSE WHERE id = $1")
+        .bind(taxed_order)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let taxed = create_return(&pool, taxed_order, user_id, "received", &[(taxed_items[0], 2)]).await;
+    assert_eq!(refund(&pool, taxed, None, user_id).await.unwrap().amount, BigDecimal::from(222000));
+
+    cleanup(&pool, user_id, &[product_id]).await;
+}
+
+#[tokio::test]
+#[ignore = "requires DATABASE_URL pointing at a migrated database"]
+async fn a_fully_refunded_order_refunds_nothing_more() {
+    let pool = connect().await;
+    let tag = format!("rma-full-{}", uuid::Uuid::new_v4().simple());
+    let user_id = create_user(&pool, &tag).await;
+    let product_id = create_product(&pool, &tag, 0, 2).await;
+    let (order_id, items) = create_delivered_order(&pool, user_id, &tag, &[(product_id, 2, 100000)]).await;
+
+    sqlx::query("INSERT INTO refunds (order_id, amount, reason) VALUES ($1, 200000, 'Goodwill')")
+        .bind(order_id)
+        .execute(&pool)
+        .await
+        .unwrap();
+    let return_id = create_return(&pool, order_id, user_id, "received", &[(items[0], 1)]).await;
+    assert_eq!(refund(&pool, return_id, None, user_id).await.unwrap_err(), RefundError::AlreadyRefunded);
+
+    cleanup(&pool, user_id, &[product_id]).await;
+}
+
+#[tokio::test]
+#[ignore = "requires DATABASE_URL pointing at a migrated database"]
+async fn concurrent_refunds_pay_a_return_once() {
+    let pool = connect().await;
+    let tag = format!("rma-race-{}", uuid::Uuid::new_v4().simple());
+    let user_id = create_user(&pool, &tag).await;
+    let product_id = create_product(&pool, &tag, 0, 2).await;
+    let (order_id, items) = create_delivered_order(&pool, user_id, &tag, &[(product_id, 2, 100000)]).await;
+    let return_id = create_return(&pool, order_id, user_id, "received", &[(items[0], 1)]).await;
+
+    let attempts: Vec<_> = (0..2)
+        .map(|_| {
+            let pool = pool.clone();
+            tokio::spawn(async move { refund(&pool, return_id, None, user_id).await })
+        })
+        .collect();
+    let mut results = Vec::new();
+    for attempt in attempts {
+        results.push(attempt.await.unwrap());
+    }
+
+    assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+    assert!(results
+        .iter()
+        .any(|result| matches!(result, Err(RefundError::NotReceived(status)) if status == "refunded")));
+    let (refunds,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM refunds WHERE return_id = $1")
+        .bind(return_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(refunds, 1);
+
+    cleanup(&pool, user_id, &[product_id]).await;
+}
@@ -0,0 +1,154 @@
+// Tracking cache for shipments, using a fake courier tracker. These need a
+// real, migrated database: DATABASE_URL=... cargo test -- --ignored
+#[allow(dead_code)]
+#[path = "../src/services/tracking.rs"]
+mod tracking;
+mod common;
+
+use async_trait::async_trait;
+use common::{connect, create_user};
+use sqlx::PgPool;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use tracking::{ShipmentTracker, TrackingCheckpoint, TrackingError, TrackingInfo};
+
+// Answers with whatever the test scripted and counts the calls
+struct FakeTracker {
+    response: Mutex<Option<TrackingInfo>>,
+    calls: AtomicUsize,
+}
+
+impl FakeTracker {
+    fn new(response: Option<TrackingInfo>) -> Self {
+        Self { response: Mutex::new(response), calls: AtomicUsize::new(0) }
+    }
+
+    fn respond(&self, response: Option<TrackingInfo>) {
+        *self.response.lock().unwrap() = response;
+    }
+
+    fn calls(&self) -> usize {
+        self.calls.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl ShipmentTracker for FakeTracker {
+    async fn track(&self, _courier: &str, waybill: &str) -> Result<TrackingInfo, TrackingError> {
+        self.calls.fetch_add(1, Ordering::SeqCst);
+        self.response
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| TrackingError::NotFound(format!("Waybill {} not found", waybill)))
+    }
+}
+
+fn info(status: &str, delivered: bool) -> TrackingInfo {
+    TrackingInfo {
+        status: status.to_string(),
+        delivered,
+        checkpoints: vec![TrackingCheckpoint {
+            time: None,
+            location: Some("PEKALONGAN".to_string()),
+            description: status.to_string(),
+        }],
+    }
+}
+
+// A throwaway user with one order and one shipment; deleting the user
+// removes the rest
+async fn create_shipment(pool: &PgPool, tag: &str) -> (i32, i32) {
+    let user_id = create_user(pool, tag).await;
+
+    let (shipment_id,): (i32,) = sqlx::query_as(
+        "WITH o AS (
+             INSERT INTO orders (user_id, order_number, total_amount, final_amount, payment_method, shipping_address, billing_address)
+             VALUES ($1, $2, 450000, 460000, 'transfer_bank', '{}', '{}')
+             RETURNING id
+         )
+         INSERT INTO shipments (order_id, courier, service, waybill, weight_grams, cost)
+         SELECT id, 'jne', 'REG', $2, 1200, 10000 FROM o
+         RETURNING id"
+    )
+    .bind(user_id)
+    .bind(tag)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create shipment");
+
+    (user_id, shipment_id)
+}
+
+async fn cleanup(pool: &PgPool, user_id: i32) {
+    sqlx::query("DELETE FROM orders WHERE user_id = $1").bind(user_id).execute(pool).await.unwrap();
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(user_id).execute(pool).await.unwrap();
+}
+
+async fn expire_cache(pool: &PgPool, shipment_id: i32) {
+    sqlx::query("UPDATE shipments SET tracked_at = NOW() - INTERVAL '1 day' WHERE id = $1")
+        .bind(shipment_id)
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+#[ignore = "requires DATABASE_URL pointing at a migrated database"]
+async fn tracking_is_cached_until_it_expires() {
+    let pool = connect().await;
+    let tag = format!("track-{}", std::process::id());
+    let (user_id, shipment_id) = create_shipment(&pool, &tag).await;
+    let tracker = FakeTracker::new(Some(info("ON PROCESS", false)));
+
+    let first = tracking::shipment_tracking(&pool, &tracker, shipment_id).await.unwrap();
+    let second = tracking::shipment_tracking(&pool, &tracker, shipment_id).await.unwrap();
+    assert_eq!(tracker.calls(), 1);
+    assert_eq!(first.tracked_at, second.tracked_at);
+
+    expire_cache(&pool, shipment_id).await;
+    tracker.respond(Some(info("DELIVERED", true)));
+    let delivered = tracking::shipment_tracking(&pool, &tracker, shipment_id).await.unwrap();
+    assert_eq!(tracker.calls(), 2);
+    assert!(delivered.info.delivered);
+
+    // Delivered parcels never change again
+    expire_cache(&pool, shipment_id).await;
+    tracking::shipment_tracking(&pool, &tracker, shipment_id).await.unwrap();
+    assert_eq!(tracker.calls(), 2);
+
+    let (delivered_at,): (Option<chrono::NaiveDateTime>,) =
+        sqlx::query_as("SELECT delivered_at FROM shipments WHERE id = $1")
+            .bind(shipment_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert!(delivered_at.is_some());
+
+    cleanup(&pool, user_id).await;
+}
+
+#[tokio::test]
+#[ignore = "requires DATABASE_URL pointing at a migrated database"]
+async fn stale_history_is_served_when_the_courier_fails() {
+    let pool = connect().await;
+    let tag = format!("track-stale-{}", std::process::id());
+    let (user_id, shipment_id) = create_shipment(&pool, &tag).await;
+    let tracker = FakeTracker::new(None);
+
+    // Nothing cached yet, so the courier's answer is passed on
+    let result = tracking::shipment_tracking(&pool, &tracker, shipment_id).await;
+    assert!(matches!(result, Err(TrackingError::NotFound(_))));
+
+    tracker.respond(Some(info("ON PROCESS", false)));
+    tracking::shipment_tracking(&pool, &tracker, shipment_id).await.unwrap();
+
+    expire_cache(&pool, shipment_id).await;
+    tracker.respond(None);
+    let stale = tracking::shipment_tracking(&pool, &tracker, shipment_id).await.unwrap();
+    assert!(stale.stale);
+    assert_eq!(stale.info.status, "ON PROCESS");
+    assert_eq!(tracker.calls(), 3);
+
+    cleanup(&pool, user_id).await;
+}
@@ -0,0 +1,156 @@
+// Shipping rate providers. The RajaOngkir adapter runs against a local stub:
+// cargo test --features rajaongkir
+#[allow(dead_code)]
+#[path = "../src/services/shipping.rs"]
+mod shipping;
+
+use bigdecimal::BigDecimal;
+use shipping::{ShipmentRequest, ShippingError, ShippingProvider, TableShippingProvider};
+
+fn parcel(province: &str, weight_grams: i32) -> ShipmentRequest {
+    ShipmentRequest {
+        province: province.to_string(),
+        city: "Kota Jayapura".to_string(),
+        postal_code: "99111".to_string(),
+        weight_grams,
+    }
+}
+
+#[test]
+fn weight_rounds_up_past_tolerance() {
+    assert_eq!(shipping::billable_kg(0), 1);
+    assert_eq!(shipping::billable_kg(1300), 1);
+    assert_eq!(shipping::billable_kg(1301), 2);
+    assert_eq!(shipping::billable_kg(2000), 2);
+}
+
+#[test]
+fn parcel_weight_refuses_to_wrap_around() {
+    let kain = "0.35".parse::<BigDecimal>().unwrap();
+    assert_eq!(shipping::parcel_weight_grams([(Some(&kain), 3), (None, 2)]), Some(1050));
+    // Enough units to pass i32::MAX grams, on one line or over several
+    assert_eq!(shipping::parcel_weight_grams([(Some(&kain), i32::MAX)]), None);
+    assert_eq!(shipping::parcel_weight_grams([(Some(&kain), 4_000_000); 2]), None);
+}
+
+#[tokio::test]
+async fn table_rates_depend_on_zone_and_weight() {
+    let provider = TableShippingProvider::default_rates();
+
+    let java = provider.quote(&parcel("jawa tengah", 600)).await.unwrap();
+    assert_eq!(java.iter().map(|q| q.service.as_str()).collect::<Vec<_>>(), ["REG", "YES"]);
+    assert_eq!(java[0].cost, BigDecimal::from(10000));
+
+    let papua = provider.quote(&parcel("Papua", 2500)).await.unwrap();
+    assert_eq!(papua.len(), 1);
+    assert_eq!(papua[0].cost, BigDecimal::from(180000));
+}
+
+#[tokio::test]
+async fn unknown_province_is_unserviceable() {
+    let provider = TableShippingProvider::default_rates();
+    let result = provider.quote(&parcel("Atlantis", 500)).await;
+    assert!(matches!(result, Err(ShippingError::Unserviceable(_))));
+}
+
+#[cfg(feature = "rajaongkir")]
+mod rajaongkir_stub {
+    use super::*;
+    use actix_web::{get, post, web, App, HttpRequest, HttpResponse, HttpServer};
+    use serde::Deserialize;
+    use shipping::rajaongkir::RajaOngkirProvider;
+
+    #[derive(Deserialize)]
+    struct CostForm {
+        origin: String,
+        destination: String,
+        weight: i32,
+        courier: String,
+    }
+
+    fn authorized(req: &HttpRequest) -> bool {
+        req.headers().get("key").and_then(|v| v.to_str().ok()) == Some("test-key")
+    }
+
+    #[get("/city")]
+    async fn cities(req: HttpRequest) -> HttpResponse {
+        if !authorized(&req) {
+            return HttpResponse::Ok().json(serde_json::json!({
+                "rajaongkir": { "status": { "code": 400, "description": "Invalid key" } }
+            }));
+        }
+        HttpResponse::Ok().json(serde_json::json!({
+            "rajaongkir": {
+                "status": { "code": 200, "description": "OK" },
+                "results": [
+                    { "city_id": "399", "province": "Jawa Tengah", "type": "Kota", "city_name": "Pekalongan", "postal_code": "51122" },
+                    { "city_id": "157", "province": "Papua", "type": "Kota", "city_name": "Jayapura", "postal_code": "99114" }
+                ]
+            }
+        }))
+    }
+
+    #[post("/cost")]
+    async fn cost(req: HttpRequest, form: web::Form<CostForm>) -> HttpResponse {
+        assert!(authorized(&req));
+        assert_eq!(form.origin, "399");
+        assert_eq!(form.destination, "157");
+        let per_kg = if form.courier == "jne" { 55000 } else { 70000 };
+        let kg = (form.weight as i64 + 999) / 1000;
+        HttpResponse::Ok().json(serde_json::json!({
+            "rajaongkir": {
+                "status": { "code": 200, "description": "OK" },
+                "results": [{
+                    "code": form.courier,
+                    "name": form.courier.to_uppercase(),
+                    "costs": [{
+                        "service": "REG",
+                        "description": "Layanan Reguler",
+                        "cost": [{ "value": per_kg * kg, "etd": "5-7", "note": "" }]
+                    }]
+                }]
+            }
+        }))
+    }
+
+    async fn start_stub() -> String {
+        let server = HttpServer::new(|| App::new().service(cities).service(cost))
+            .workers(1)
+            .bind(("127.0.0.1", 0))
+            .unwrap();
+        let address = server.addrs()[0];
+        tokio::spawn(server.run());
+        format!("http://{}", address)
+    }
+
+    #[actix_web::test]
+    async fn quotes_come_from_the_api_cheapest_first() {
+        let base_url = start_stub().await;
+        let provider = RajaOngkirProvider::new(&base_url, "test-key", "399", vec!["pos".to_string(), "jne".to_string()]);
+
+        let quotes = provider.quote(&parcel("Papua", 1500)).await.unwrap();
+        assert_eq!(quotes.len(), 2);
+        assert_eq!(quotes[0].courier, "jne");
+        assert_eq!(quotes[0].cost, BigDecimal::from(110000));
+        assert_eq!(quotes[1].courier, "pos");
+        assert_eq!(quotes[0].etd_days.as_deref(), Some("5-7"));
+    }
+
+    #[actix_web::test]
+    async fn unknown_city_is_unserviceable() {
+        let base_url = start_stub().await;
+        let provider = RajaOngkirProvider::new(&base_url, "test-key", "399", vec!["jne".to_string()]);
+
+        let mut request = parcel("Papua", 1000);
+        request.city = "Merauke".to_string();
+        request.postal_code = "99600".to_string();
+        assert!(matches!(provider.quote(&request).await, Err(ShippingError::Unserviceable(_))));
+    }
+
+    #[actix_web::test]
+    async fn api_errors_are_reported() {
+        let base_url = start_stub().await;
+        let provider = RajaOngkirProvider::new(&base_url, "wrong-key", "399", vec!["jne".to_string()]);
+        assert!(matches!(provider.quote(&parcel("Papua", 1000)).await, Err(ShippingError::Backend(_))));
+    }
+}
@@ -0,0 +1,87 @@
+// CSV and XLSX output of the streaming sheet writer
+#[allow(dead_code)]
+#[path = "../src/services/spreadsheet.rs"]
+mod spreadsheet;
+
+use flate2::read::DeflateDecoder;
+use spreadsheet::{csv_field, Cell, SheetFormat, SheetWriter};
+use std::io::Read;
+
+fn u16_at(bytes: &[u8], at: usize) -> usize {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]]) as usize
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
+
+// Reads every file of a zip through its central directory, checking CRCs
+fn unzip(bytes: &[u8]) -> Vec<(String, String)> {
+    let end = bytes.len() - 22;
+    assert_eq!(u32_at(bytes, end), 0x06054b50);
+    let count = u16_at(bytes, end + 10);
+    let mut at = u32_at(bytes, end + 16) as usize;
+
+    let mut files = Vec::new();
+    for _ in 0..count {
+        assert_eq!(u32_at(bytes, at), 0x02014b50);
+        let crc = u32_at(bytes, at + 16);
+        let compressed_size = u32_at(bytes, at + 20) as usize;
+        let name_length = u16_at(bytes, at + 28);
+        let offset = u32_at(bytes, at + 42) as usize;
+        let name = String::from_utf8(bytes[at + 46..at + 46 + name_length].to_vec()).unwrap();
+        at += 46 + name_length;
+
+        assert_eq!(u32_at(bytes, offset), 0x04034b50);
+        let data_start = offset + 30 + u16_at(bytes, offset + 26) + u16_at(bytes, offset + 28);
+        let mut content = String::new();
+        DeflateDecoder::new(&bytes[data_start..data_start + compressed_size])
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(crc32fast::hash(content.as_bytes()), crc, "bad CRC for {}", name);
+        files.push((name, content));
+    }
+    files
+}
+
+#[test]
+fn csv_quotes_fields_and_defuses_formulas() {
+    assert_eq!(csv_field("Batik Tulis"), "Batik Tulis");
+    assert_eq!(csv_field("Jl. Malioboro, 12"), "\"Jl. Malioboro, 12\"");
+    assert_eq!(csv_field("5\" wide"), "\"5\"\" wide\"");
+    assert_eq!(csv_field("=HYPERLINK(\"x\")"), "\"'=HYPERLINK(\"\"x\"\")\"");
+    assert_eq!(csv_field("@sum"), "'@sum");
+
+    let mut sheet = SheetWriter::new(SheetFormat::Csv, "Orders");
+    sheet.write_row(&["Order number".into(), "Total".into()]);
+    sheet.write_row(&["BK-20261018-000123".into(), Cell::Number("-15000.00".to_string())]);
+    sheet.write_row(&[Cell::Empty, Cell::Empty]);
+    let csv = String::from_utf8(sheet.finish()).unwrap();
+    assert_eq!(csv, "\u{feff}Order number,Total\r\nBK-20261018-000123,-15000.00\r\n,\r\n");
+}
+
+#[test]
+fn xlsx_is_a_valid_streamed_package() {
+    let mut sheet = SheetWriter::new(SheetFormat::Xlsx, "Orders");
+    let mut bytes = Vec::new();
+    sheet.write_row(&["Product".into(), "Total".into()]);
+    for i in 0..2000 {
+        sheet.write_row(&[format!("Kain <Parang> & Kawung {}", i).into(), Cell::Number("125000.00".to_string())]);
+        bytes.extend(sheet.take());
+    }
+    bytes.extend(sheet.finish());
+
+    let files = unzip(&bytes);
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(
+        names,
+        ["[Content_Types].xml", "_rels/.rels", "xl/workbook.xml", "xl/_rels/workbook.xml.rels", "xl/worksheets/sheet1.xml"]
+    );
+    assert!(files[2].1.contains(r#"<sheet name="Orders""#));
+
+    let sheet_xml = &files[4].1;
+    assert!(sheet_xml.ends_with("</sheetData></worksheet>"));
+    assert_eq!(sheet_xml.matches("<row>").count(), 2001);
+    assert!(sheet_xml.contains("<t xml:space=\"preserve\">Kain &lt;Parang&gt; &amp; Kawung 1999</t>"));
+    assert!(sheet_xml.contains("<c><v>125000.00</v></c>"));
+}
@@ -0,0 +1,185 @@
+// Concurrency checks for stock reservations. These need a real, migrated
+// database: DATABASE_URL=... cargo test -- --ignored
+#[allow(dead_code)]
+#[path = "../src/services/inventory.rs"]
+mod inventory;
+mod common;
+
+use common::{connect, create_user};
+use sqlx::PgPool;
+
+async fn create_product(pool: &PgPool, tag: &str, stock: i32) -> i32 {
+    common::create_product(pool, tag, stock, 0).await
+}
+
+async fn create_order(pool: &PgPool, user_id: i32, order_number: &str) -> i32 {
+    let (id,): (i32,) = sqlx::query_as(
+        "INSERT INTO orders (user_id, order_number, total_amount, final_amount, payment_method, shipping_address, billing_address)
+         VALUES ($1, $2, 1500000, 1500000, 'transfer_bank', '{}', '{}')
+         RETURNING id"
+    )
+    .bind(user_id)
+    .bind(order_number)
+    .fetch_one(pool)
+    .await
+    .expect("Failed to create order");
+    id
+}
+
+async fn cleanup(pool: &PgPool, user_id: i32, product_id: i32) {
+    sqlx::query("DELETE FROM orders WHERE user_id = $1").bind(user_id).execute(pool).await.unwrap();
+    sqlx::query("DELETE FROM products WHERE id = $1").bind(product_id).execute(pool).await.unwrap();
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(user_id).execute(pool).await.unwrap();
+}
+
+// The checkout transaction's stock steps: lock the products with checkout's
+// own locking, then reserve each line
+async fn attempt_checkout(pool: PgPool, order_id: i32, lines: Vec<(i32, i32)>) -> bool {
+    let mut tx = pool.begin().await.unwrap();
+
+    let product_ids: Vec<i32> = lines.iter().map(|(product_id, _)| *product_id).collect();
+    inventory::lock_products(&mut tx, &product_ids).await.unwrap();
+
+    let mut reserved = true;
+    for (product_id, quantity) in lines {
+        reserved = reserved
+            && inventory::reserve_stock(&mut tx, order_id, product_id, quantity, inventory::reservation_expiry())
+                .await
+                .unwrap();
+    }
+
+    if reserved {
+        tx.commit().await.unwrap();
+    } else {
+        tx.rollback().await.unwrap();
+    }
+    reserved
+}
+
+#[tokio::test]
+#[ignore = "requires DATABASE_URL pointing at a migrated database"]
+async fn parallel_checkouts_cannot_oversell_last_unit() {
+    let pool = connect().await;
+    let tag = format!("race-{}", uuid::Uuid::new_v4().simple());
+    let user_id = create_user(&pool, &tag).await;
+    let product_id = create_product(&pool, &tag, 1).await;
+
+    let mut order_ids = Vec::new();
+    for i in 0..8 {
+        order_ids.push(create_order(&pool, user_id, &format!("{}-{}", tag, i)).await);
+    }
+
+    let attempts: Vec<_> = order_ids
+        .iter()
+        .map(|order_id| tokio::spawn(attempt_checkout(pool.clone(), *order_id, vec![(product_id, 1)])))
+        .collect();
+
+    let mut successes = 0;
+    for attempt in attempts {
+        if attempt.await.unwrap() {
+            successes += 1;
+        }
+    }
+
+    let (stock,): (i32,) = sqlx::query_as("SELECT stock FROM products WHERE id = $1")
+        .bind(product_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    cleanup(&pool, user_id, product_id).await;
+
+    assert_eq!(successes, 1, "exactly one checkout should get the last unit");
+    assert_eq!(stock, 0);
+}
+
+#[tokio::test]
+#[ignore = "requires DATABASE_URL pointing at a migrated database"]
+async fn checkouts_listing_products_in_any_order_do_not_deadlock() {
+    let pool = connect().await;
+    let tag = format!("deadlock-{}", uuid::Uuid::new_v4().simple());
+    let user_id = create_user(&pool, &tag).await;
+    let kawung = create_product(&pool, &format!("{}-a", tag), 20).await;
+    let parang = create_product(&pool, &format!("{}-b", tag), 20).await;
+
+    let mut attempts = Vec::new();
+    for i in 0..10 {
+        let order_id = create_order(&pool, user_id, &format!("{}-{}", tag, i)).await;
+        // Half the orders list the products the other way round
+        let lines = if i % 2 == 0 { vec![(kawung, 1), (parang, 1)] } else { vec![(parang, 1), (kawung, 1)] };
+        attempts.push(tokio::spawn(attempt_checkout(pool.clone(), order_id, lines)));
+    }
+    for attempt in attempts {
+        // A deadlock would fail one of them with a database error
+        assert!(attempt.await.unwrap());
+    }
+
+    let stock: Vec<i32> = sqlx::query_scalar("SELECT stock FROM products WHERE id = ANY($1)")
+        .bind([kawung, parang])
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+
+    sqlx::query("DELETE FROM products WHERE id = $1").bind(parang).execute(&pool).await.unwrap();
+    cleanup(&pool, user_id, kawung).await;
+
+    assert_eq!(stock, vec![10, 10]);
+}
+
+#[tokio::test]
+#[ignore = "requires DATABASE_URL pointing at a migrated database"]
+async fn released_reservation_returns_stock() {
+    let pool = connect().await;
+    let tag = format!("release-{}", uuid::Uuid::new_v4().simple());
+    let user_id = create_user(&pool, &tag).await;
+    let product_id = create_product(&pool, &tag, 3).await;
+    let order_id = create_order(&pool, user_id, &tag).await;
+
+    let mut tx = pool.begin().await.unwrap();
+    assert!(inventory::reserve_stock(&mut tx, order_id, product_id, 2, inventory::reservation_expiry()).await.unwrap());
+    assert!(!inventory::reserve_stock(&mut tx, order_id, product_id, 2, inventory::reservation_expiry()).await.unwrap());
+    inventory::release_reservations(&mut tx, order_id).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let (stock,): (i32,) = sqlx::query_as("SELECT stock FROM products WHERE id = $1")
+        .bind(product_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    cleanup(&pool, user_id, product_id).await;
+
+    assert_eq!(stock, 3);
+}
+
+#[tokio::test]
+#[ignore = "requires DATABASE_URL pointing at a migrated database"]
+async fn cancelling_a_confirmed_order_returns_consumed_stock() {
+    let pool = connect().await;
+    let tag = format!("consumed-{}", uuid::Uuid::new_v4().simple());
+    let user_id = create_user(&pool, &tag).await;
+    let product_id = create_product(&pool, &tag, 3).await;
+    let order_id = create_order(&pool, user_id, &tag).await;
+
+    let mut tx = pool.begin().await.unwrap();
+    assert!(inventory::reserve_stock(&mut tx, order_id, product_id, 2, inventory::reservation_expiry()).await.unwrap());
+    inventory::consume_reservations(&mut tx, order_id).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let mut tx = pool.begin().await.unwrap();
+    inventory::release_reservations(&mut tx, order_id).await.unwrap();
+    // Releasing twice must not put the units back twice
+    inventory::release_reservations(&mut tx, order_id).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let (stock, sold_count): (i32, Option<i32>) = sqlx::query_as("SELECT stock, sold_count FROM products WHERE id = $1")
+        .bind(product_id)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    cleanup(&pool, user_id, product_id).await;
+
+    assert_eq!(stock, 3);
+    assert_eq!(sold_count.unwrap_or(0), 0);
+}
@@ -0,0 +1,45 @@
+// PPN computation at checkout
+#[allow(dead_code)]
+#[path = "../src/services/tax.rs"]
+mod tax;
+
+use bigdecimal::BigDecimal;
+use std::str::FromStr;
+use tax::TaxRules;
+
+fn rules(prices_include_tax: bool) -> TaxRules {
+    TaxRules {
+        label: "PPN".to_string(),
+        rate: BigDecimal::from(11),
+        prices_include_tax,
+        exempt_categories: Vec::new(),
+    }
+}
+
+fn rp(amount: &str) -> BigDecimal {
+    BigDecimal::from_str(amount).unwrap()
+}
+
+#[test]
+fn inclusive_prices_contain_the_tax() {
+    assert_eq!(rules(true).line_tax(&rp("111000"), false), rp("11000"));
+    // 50000 * 11 / 111 = 4954.95...
+    assert_eq!(rules(true).line_tax(&rp("50000"), false), rp("4955"));
+}
+
+#[test]
+fn exclusive_prices_get_tax_added() {
+    assert_eq!(rules(false).line_tax(&rp("100000"), false), rp("11000"));
+    assert_eq!(rules(false).line_tax(&rp("12345"), false), rp("1358"));
+}
+
+#[test]
+fn exempt_and_free_lines_are_not_taxed() {
+    assert_eq!(rules(false).line_tax(&rp("100000"), true), rp("0"));
+    // A coupon can discount a line to nothing
+    assert_eq!(rules(false).line_tax(&rp("0"), false), rp("0"));
+
+    let mut no_tax = rules(false);
+    no_tax.rate = rp("0");
+    assert_eq!(no_tax.line_tax(&rp("100000"), false), rp("0"));
+}